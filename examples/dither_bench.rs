@@ -0,0 +1,47 @@
+use std::time::Instant;
+
+use catprinter::dithering::{apply_dithering, ImageDithering};
+use image::{GrayImage, Luma};
+
+/// Example: Time the row-independent dithers on a large synthetic image
+/// - Run once as is and once with `--features parallel` to compare
+/// - Prints the best of 5 runs and a checksum; checksums must match between builds
+fn main() {
+    let (width, height) = (2048, 4096);
+    let img = GrayImage::from_fn(width, height, |x, y| {
+        Luma([((x * 7 + y * 13) ^ (x * y)) as u8])
+    });
+
+    let cases = [
+        ("threshold", ImageDithering::Threshold { cutoff: 127 }),
+        ("bayer", ImageDithering::Bayer),
+        ("bayer8", ImageDithering::Bayer8),
+        ("clustered dot", ImageDithering::ClusteredDot),
+        ("blue noise", ImageDithering::BlueNoise),
+    ];
+    println!(
+        "{}x{} image, parallel feature {}",
+        width,
+        height,
+        if cfg!(feature = "parallel") {
+            "on"
+        } else {
+            "off"
+        }
+    );
+    for (name, dithering) in cases {
+        let mut best = f64::MAX;
+        let mut out = img.clone();
+        for _ in 0..5 {
+            let mut run = img.clone();
+            let start = Instant::now();
+            apply_dithering(&mut run, dithering);
+            best = best.min(start.elapsed().as_secs_f64());
+            out = run;
+        }
+        let checksum = out.iter().enumerate().fold(0u64, |acc, (i, &p)| {
+            acc.wrapping_add(p as u64 * (i as u64 % 9973))
+        });
+        println!("{:>14}: {:8.2} ms  checksum {}", name, best * 1e3, checksum);
+    }
+}
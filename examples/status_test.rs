@@ -1,8 +1,6 @@
 use std::io::{self, Write};
 use std::time::Duration;
 
-use tokio;
-
 use catprinter::ble::{connect, scan};
 
 /// Example: Query CatPrinter status and battery in a loop
@@ -32,10 +30,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         io::stdout().flush()?;
         input.clear();
         io::stdin().read_line(&mut input)?;
-        if let Ok(n) = input.trim().parse::<usize>() {
-            if n >= 1 && n <= devices.len() {
-                break &devices[n - 1];
-            }
+        if let Ok(n) = input.trim().parse::<usize>()
+            && n >= 1
+            && n <= devices.len()
+        {
+            break &devices[n - 1];
         }
         println!("Invalid selection.");
     };
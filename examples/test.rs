@@ -30,38 +30,39 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         io::stdout().flush()?;
         input.clear();
         io::stdin().read_line(&mut input)?;
-        if let Ok(n) = input.trim().parse::<usize>() {
-            if n >= 1 && n <= devices.len() {
-                let chosen = &devices[n - 1];
-                println!(
-                    "Connecting to device id={} name={:?} ...",
-                    chosen.id, chosen.name
-                );
-                match connect(&chosen.id, Duration::from_secs(10)).await {
-                    Ok(printer) => {
-                        println!("Connected successfully.");
-                        run_interactive_session(printer).await?;
+        if let Ok(n) = input.trim().parse::<usize>()
+            && n >= 1
+            && n <= devices.len()
+        {
+            let chosen = &devices[n - 1];
+            println!(
+                "Connecting to device id={} name={:?} ...",
+                chosen.id, chosen.name
+            );
+            match connect(&chosen.id, Duration::from_secs(10)).await {
+                Ok(printer) => {
+                    println!("Connected successfully.");
+                    run_interactive_session(printer).await?;
+                    return Ok(());
+                }
+                Err(e) => {
+                    eprintln!("Failed to connect: {}", e);
+                    // Ask to retry or choose another device
+                    print!("Try another device? (y/N): ");
+                    io::stdout().flush()?;
+                    input.clear();
+                    io::stdin().read_line(&mut input)?;
+                    if input.trim().to_lowercase() != "y" {
                         return Ok(());
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to connect: {}", e);
-                        // Ask to retry or choose another device
-                        print!("Try another device? (y/N): ");
-                        io::stdout().flush()?;
-                        input.clear();
-                        io::stdin().read_line(&mut input)?;
-                        if input.trim().to_lowercase() != "y" {
-                            return Ok(());
-                        } else {
-                            // re-list and continue loop to let user enter another index
-                            for (i, d) in devices.iter().enumerate() {
-                                println!("  {}) id={} name={:?}", i + 1, d.id, d.name);
-                            }
+                    } else {
+                        // re-list and continue loop to let user enter another index
+                        for (i, d) in devices.iter().enumerate() {
+                            println!("  {}) id={} name={:?}", i + 1, d.id, d.name);
                         }
                     }
                 }
-                break;
             }
+            break;
         }
         println!("Invalid selection.");
     }
@@ -140,7 +141,7 @@ async fn run_interactive_session(
             "2" => catprinter::dithering::ImageDithering::FloydSteinberg,
             "3" => catprinter::dithering::ImageDithering::Atkinson,
             "4" => catprinter::dithering::ImageDithering::Halftone,
-            _ => catprinter::dithering::ImageDithering::Threshold,
+            _ => catprinter::dithering::ImageDithering::threshold_default(),
         };
 
         // Print image
@@ -0,0 +1,331 @@
+use crate::error::PrinterError;
+use crate::font::{rasterize_text_aligned, Alignment};
+
+/// Linear barcode symbologies supported by render_barcode_to_pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Barcode {
+    /// Printable ASCII (space to DEL); digit runs are packed two per symbol.
+    Code128,
+    /// 12 digits (check digit computed) or 13 digits (check digit verified).
+    Ean13,
+}
+
+/// Font size of the human-readable caption under the bars.
+const CAPTION_FONT_SIZE: f32 = 28.0;
+/// Blank rows between the bars and the caption.
+const CAPTION_GAP: usize = 4;
+/// Widest module (narrowest bar) in pixels; wider bars add nothing for scanners.
+const MAX_MODULE_PX: usize = 4;
+
+/// Code 128 symbols 0-106 as 11-bit bar patterns (1 = bar), MSB first.
+const CODE128_PATTERNS: [u16; 106] = [
+    0x6CC, 0x66C, 0x666, 0x498, 0x48C, 0x44C, 0x4C8, 0x4C4, 0x464, 0x648, 0x644, 0x624, 0x59C,
+    0x4DC, 0x4CE, 0x5CC, 0x4EC, 0x4E6, 0x672, 0x65C, 0x64E, 0x6E4, 0x674, 0x76E, 0x74C, 0x72C,
+    0x726, 0x764, 0x734, 0x732, 0x6D8, 0x6C6, 0x636, 0x518, 0x458, 0x446, 0x588, 0x468, 0x462,
+    0x688, 0x628, 0x622, 0x5B8, 0x58E, 0x46E, 0x5D8, 0x5C6, 0x476, 0x776, 0x68E, 0x62E, 0x6E8,
+    0x6E2, 0x6EE, 0x758, 0x746, 0x716, 0x768, 0x762, 0x71A, 0x77A, 0x642, 0x78A, 0x530, 0x50C,
+    0x4B0, 0x486, 0x42C, 0x426, 0x590, 0x584, 0x4D0, 0x4C2, 0x434, 0x432, 0x612, 0x650, 0x7BA,
+    0x614, 0x47A, 0x53C, 0x4BC, 0x49E, 0x5E4, 0x4F4, 0x4F2, 0x7A4, 0x794, 0x792, 0x6DE, 0x6F6,
+    0x7B6, 0x578, 0x51E, 0x45E, 0x5E8, 0x5E2, 0x7A8, 0x7A2, 0x5DE, 0x5EE, 0x75E, 0x7AE, 0x684,
+    0x690, 0x69C,
+];
+/// Code 128 stop symbol, 13 modules including the final bar.
+const CODE128_STOP: u16 = 0x18EB;
+const CODE128_START_B: usize = 104;
+const CODE128_START_C: usize = 105;
+const CODE128_CODE_B: usize = 100;
+const CODE128_CODE_C: usize = 99;
+/// Quiet zone on each side, in modules.
+const CODE128_QUIET: usize = 10;
+
+/// EAN-13 left-hand odd parity (L) patterns, 7 modules each.
+const EAN_L: [u8; 10] = [0x0D, 0x19, 0x13, 0x3D, 0x23, 0x31, 0x2F, 0x3B, 0x37, 0x0B];
+/// Parity of the six left digits (bit set = G code) keyed by the first digit.
+const EAN_PARITY: [u8; 10] = [
+    0b000000, 0b001011, 0b001101, 0b001110, 0b010011, 0b011001, 0b011100, 0b010101, 0b010110,
+    0b011010,
+];
+/// EAN-13 quiet zones in modules (left, right).
+const EAN_QUIET: (usize, usize) = (11, 7);
+
+/// Renders a linear barcode with its human-readable caption underneath.
+///
+/// - `data`: text to encode (digits only for EAN-13)
+/// - `symbology`: barcode type
+/// - `height_px`: bar height in pixels
+/// - `width`: output image width in pixels; the code is centered horizontally
+///
+/// Returns (pixels, height) with pixels row-major, 0=black, 255=white
+pub fn render_barcode_to_pixels(
+    data: &str,
+    symbology: Barcode,
+    height_px: u32,
+    width: usize,
+) -> Result<(Vec<u8>, usize), PrinterError> {
+    let (modules, caption, (quiet_left, quiet_right)) = match symbology {
+        Barcode::Code128 => {
+            let (modules, caption) = encode_code128(data)?;
+            (modules, caption, (CODE128_QUIET, CODE128_QUIET))
+        }
+        Barcode::Ean13 => {
+            let (modules, caption) = encode_ean13(data)?;
+            (modules, caption, EAN_QUIET)
+        }
+    };
+
+    let total_modules = quiet_left + modules.len() + quiet_right;
+    let scale = usize::min(MAX_MODULE_PX, width / total_modules);
+    if scale == 0 {
+        return Err(PrinterError::Image(
+            "barcode too long for printer width".into(),
+        ));
+    }
+    let x_off = (width - total_modules * scale) / 2 + quiet_left * scale;
+    let bar_height = height_px.max(1) as usize;
+
+    let mut row = vec![255u8; width];
+    for (i, bar) in modules.iter().enumerate() {
+        if *bar {
+            let start = x_off + i * scale;
+            row[start..start + scale].fill(0);
+        }
+    }
+    let mut pixels = row.repeat(bar_height);
+
+    let text = rasterize_text_aligned(&caption, width, CAPTION_FONT_SIZE, Alignment::Center);
+    pixels.resize(pixels.len() + CAPTION_GAP * width, 255);
+    pixels.extend_from_slice(&text);
+    let height = pixels.len() / width;
+    Ok((pixels, height))
+}
+
+/// Encodes Code 128 using code set B, switching to code set C for runs of
+/// four or more digits.
+///
+/// Returns (modules, caption) with true for each bar module
+fn encode_code128(data: &str) -> Result<(Vec<bool>, String), PrinterError> {
+    if data.is_empty() {
+        return Err(PrinterError::Image("Code128 data is empty".into()));
+    }
+    if let Some(c) = data.chars().find(|c| !(' '..='\u{7F}').contains(c)) {
+        return Err(PrinterError::Image(format!(
+            "Code128 only encodes printable ASCII, got {:?}",
+            c
+        )));
+    }
+
+    let bytes = data.as_bytes();
+    let digit_run = |from: usize| {
+        bytes[from..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+    let mut symbols = Vec::new();
+    let mut in_c = digit_run(0) >= 4;
+    symbols.push(if in_c {
+        CODE128_START_C
+    } else {
+        CODE128_START_B
+    });
+    let mut i = 0;
+    while i < bytes.len() {
+        let run = digit_run(i);
+        if in_c {
+            if run >= 2 {
+                symbols.push(((bytes[i] - b'0') * 10 + (bytes[i + 1] - b'0')) as usize);
+                i += 2;
+                continue;
+            }
+            symbols.push(CODE128_CODE_B);
+            in_c = false;
+        } else if run >= 4 {
+            symbols.push(CODE128_CODE_C);
+            in_c = true;
+            // Odd runs send their first digit in code set B
+            if run % 2 == 1 {
+                symbols.insert(symbols.len() - 1, (bytes[i] - b' ') as usize);
+                i += 1;
+            }
+            continue;
+        }
+        symbols.push((bytes[i] - b' ') as usize);
+        i += 1;
+    }
+
+    let checksum = symbols
+        .iter()
+        .enumerate()
+        .map(|(pos, &v)| pos.max(1) * v)
+        .sum::<usize>()
+        % 103;
+    symbols.push(checksum);
+
+    let mut modules = Vec::with_capacity(symbols.len() * 11 + 13);
+    for &s in &symbols {
+        push_bits(&mut modules, CODE128_PATTERNS[s] as u32, 11);
+    }
+    push_bits(&mut modules, CODE128_STOP as u32, 13);
+    Ok((modules, data.to_string()))
+}
+
+/// Encodes EAN-13, computing the check digit from 12 digits or verifying it
+/// when 13 are given.
+///
+/// Returns (modules, caption) with true for each bar module
+fn encode_ean13(data: &str) -> Result<(Vec<bool>, String), PrinterError> {
+    if !data.chars().all(|c| c.is_ascii_digit()) || !(12..=13).contains(&data.len()) {
+        return Err(PrinterError::Image(format!(
+            "EAN-13 needs 12 or 13 digits, got {:?}",
+            data
+        )));
+    }
+    let mut digits: Vec<u8> = data.bytes().map(|b| b - b'0').collect();
+    let sum: u32 = digits[..12]
+        .iter()
+        .enumerate()
+        .map(|(i, &d)| d as u32 * if i % 2 == 0 { 1 } else { 3 })
+        .sum();
+    let check = ((10 - sum % 10) % 10) as u8;
+    match digits.get(12) {
+        Some(&given) if given != check => {
+            return Err(PrinterError::Image(format!(
+                "EAN-13 check digit is {} but should be {}",
+                given, check
+            )));
+        }
+        Some(_) => {}
+        None => digits.push(check),
+    }
+
+    let mut modules = Vec::with_capacity(95);
+    push_bits(&mut modules, 0b101, 3);
+    let parity = EAN_PARITY[digits[0] as usize];
+    for (i, &d) in digits[1..7].iter().enumerate() {
+        let l = EAN_L[d as usize];
+        let code = if parity & (0b100000 >> i) != 0 {
+            // G code: R code (inverted L) read right to left
+            (!l & 0x7F).reverse_bits() >> 1
+        } else {
+            l
+        };
+        push_bits(&mut modules, code as u32, 7);
+    }
+    push_bits(&mut modules, 0b01010, 5);
+    for &d in &digits[7..13] {
+        push_bits(&mut modules, (!EAN_L[d as usize] & 0x7F) as u32, 7);
+    }
+    push_bits(&mut modules, 0b101, 3);
+
+    let caption = digits.iter().map(|d| (b'0' + d) as char).collect();
+    Ok((modules, caption))
+}
+
+fn push_bits(modules: &mut Vec<bool>, bits: u32, count: u32) {
+    for i in (0..count).rev() {
+        modules.push(bits >> i & 1 == 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits(modules: &[bool]) -> String {
+        modules.iter().map(|&m| if m { '1' } else { '0' }).collect()
+    }
+
+    #[test]
+    fn ean13_known_code() {
+        // Guard bars, LGLLGG left half for the leading 4, then the R codes
+        let expected = [
+            "101", "0001101", "0100111", "0101111", "0111101", "0001001", "0110011", "01010",
+            "1000010", "1000010", "1000010", "1110100", "1000010", "1100110", "101",
+        ]
+        .concat();
+        let (modules, caption) = encode_ean13("4006381333931").unwrap();
+        assert_eq!(modules.len(), 95);
+        assert_eq!(bits(&modules), expected);
+        assert_eq!(caption, "4006381333931");
+
+        // The check digit is filled in from 12 digits
+        let (computed, caption) = encode_ean13("400638133393").unwrap();
+        assert_eq!(computed, modules);
+        assert_eq!(caption, "4006381333931");
+    }
+
+    #[test]
+    fn ean13_rejects_bad_input() {
+        for data in [
+            "4006381333932",
+            "40063813339310",
+            "40063813339",
+            "40063813339a",
+            "",
+        ] {
+            assert!(
+                matches!(encode_ean13(data), Err(PrinterError::Image(_))),
+                "{data:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn code128_switches_to_code_c_for_digit_runs() {
+        // Start B, "A", "B", "1" in B (odd run), Code C, 23, 45, checksum 7, stop
+        let expected = [
+            "11010010000",
+            "10100011000",
+            "10001011000",
+            "10011100110",
+            "10111011110",
+            "11101101110",
+            "10111011000",
+            "10011000100",
+            "1100011101011",
+        ]
+        .concat();
+        let (modules, caption) = encode_code128("AB12345").unwrap();
+        assert_eq!(bits(&modules), expected);
+        assert_eq!(caption, "AB12345");
+
+        // Leading digits start in C and switch back to B for the letter
+        let (modules, _) = encode_code128("1234X").unwrap();
+        let symbols: Vec<usize> = modules[..modules.len() - 13]
+            .chunks(11)
+            .map(|chunk| {
+                let value = chunk.iter().fold(0u16, |acc, &m| acc << 1 | m as u16);
+                CODE128_PATTERNS.iter().position(|&p| p == value).unwrap()
+            })
+            .collect();
+        let checksum = (105 + 12 + 2 * 34 + 3 * 100 + 4 * 56) % 103;
+        assert_eq!(symbols, [105, 12, 34, 100, 56, checksum]);
+    }
+
+    #[test]
+    fn code128_rejects_non_ascii_and_empty_input() {
+        for data in ["café", "tab\there", ""] {
+            assert!(
+                matches!(encode_code128(data), Err(PrinterError::Image(_))),
+                "{data:?}"
+            );
+        }
+        assert!(render_barcode_to_pixels("naïve", Barcode::Code128, 40, 384).is_err());
+    }
+
+    #[test]
+    fn barcode_is_centered_at_full_width() {
+        let width = 384;
+        let (pixels, height) =
+            render_barcode_to_pixels("4006381333931", Barcode::Ean13, 40, width).unwrap();
+        assert_eq!(pixels.len(), width * height);
+        assert!(height > 40 + CAPTION_GAP);
+        // 113 modules with the quiet zones fit at 3 pixels each
+        let first_row = &pixels[..width];
+        let left = first_row.iter().position(|&p| p == 0).unwrap();
+        let right = first_row.iter().rposition(|&p| p == 0).unwrap();
+        let x_off = (width - 113 * 3) / 2;
+        assert_eq!(left, x_off + EAN_QUIET.0 * 3);
+        assert_eq!(right, x_off + (EAN_QUIET.0 + 95) * 3 - 1);
+    }
+}
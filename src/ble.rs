@@ -1,116 +1,344 @@
-use crate::dithering::{atkinson_dither, bayer_dither, halftone_dither, ImageDithering};
-use crate::printer::PrinterStatus;
-use crate::protocol::{build_control_packet, chunk_data, pack_1bpp_pixels, parse_notification};
+use crate::barcode::{render_barcode_to_pixels, Barcode};
+use crate::dithering::{
+    flatten_alpha, GrayscaleMode, ImageDithering, DEFAULT_ALPHA_BACKGROUND,
+    DEFAULT_DIFFUSION_STRENGTH,
+};
+use crate::error::PrinterError;
+use crate::printer::{
+    autocrop_image, check_line_count, check_print_width, decode_oriented, ensure_ink,
+    prepare_image, preview_pixels, rotate_flip, test_page_info, FilterType, ImageSettings,
+    Orientation, PrinterErrorCode, PrinterState, PrinterStatus, ResizeMode, Rotation, Transport,
+    DEFAULT_ACK_TIMEOUT, DEFAULT_AUTOCROP_THRESHOLD, DEFAULT_CHUNK_SIZE, DEFAULT_COOLDOWN_DELAY,
+    DEFAULT_INTER_CHUNK_DELAY, DEFAULT_MAX_HEIGHT, DEFAULT_PRINT_TIMEOUT, DEFAULT_RETRY_DELAY,
+    READY_POLL_INTERVAL, RELIABLE_RETRIES,
+};
+use crate::protocol::{
+    build_cancel_packet, build_control_packet, build_data_end_packet, build_density_packet,
+    build_feed_packet, build_get_sleep_timeout_packet, build_print_request_packet,
+    build_set_sleep_timeout_packet, check_pixel_buffer, chunk_data_iter, invert_pixels,
+    pack_pixels_for_mode_ordered, pack_rows_into_ordered, packed_row_bytes, pad_pixels_vertical,
+    parse_battery, parse_device_info, parse_notification, parse_print_progress,
+    parse_printer_status, parse_sleep_timeout, render_formatted_to_pixels, render_layout,
+    render_test_page, render_text_to_pixels_signed, repeat_packed_rows, split_into_tiles,
+    stack_images, transform_pixels, BitOrder, Command, DeviceInfoReport, LayoutElement,
+    Notification, PacketAssembler, SignatureStyle, Transform, DEFAULT_DENSITY, DEFAULT_TEXT_SIZE,
+    MAX_DENSITY, MIN_DENSITY, MODE_1BPP, PRINTER_WIDTH,
+};
+use crate::qr::{render_qr_to_pixels, EcLevel, DEFAULT_EC_LEVEL};
 use async_trait::async_trait;
 use btleplug::api::{
-    Central as _, Characteristic, Manager as _, Peripheral as _, ScanFilter, WriteType,
+    Central as _, CentralEvent, CharPropFlags, Characteristic, Manager as _, Peripheral as _,
+    ScanFilter, WriteType,
 };
-use btleplug::platform::{Manager, Peripheral};
-use futures::stream::StreamExt;
+use btleplug::platform::{Adapter, Manager, Peripheral};
+use futures::future;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU8, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
 use tokio::time;
+pub use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
+/// A BLE device seen during a scan.
+///
+/// - `rssi`: signal strength in dBm, if the platform reports it
+/// - `manufacturer_data`: advertised manufacturer data, each entry as the
+///   little-endian company id followed by its bytes (empty if none)
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeviceInfo {
     pub id: String,
     pub name: Option<String>,
+    pub rssi: Option<i16>,
+    pub manufacturer_data: Vec<u8>,
 }
 
-#[derive(Debug)]
-struct BtleCharacteristics {
-    control: Characteristic, // AE01
-    notify: Characteristic,  // AE02
-    data: Characteristic,    // AE03
+/// Progress of the current (or last) print job, see CatPrinterAsync::get_live_status.
+///
+/// - `printing`: a job is between begin_print and end_print
+/// - `lines_total`: line count announced in the job's 0xA9 request
+/// - `lines_printed`: lines the printer last reported as printed (0xBB)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LiveStatus {
+    pub printing: bool,
+    pub lines_total: u16,
+    pub lines_printed: u16,
+}
+
+impl LiveStatus {
+    /// Lines still to print according to the last progress report.
+    pub fn lines_remaining(&self) -> u16 {
+        self.lines_total.saturating_sub(self.lines_printed)
+    }
 }
 
 #[async_trait]
 pub trait TransportAsync: Send + Sync {
-    async fn write_control(&self, data: &[u8]) -> Result<(), String>;
-    async fn write_data(&self, data: &[u8]) -> Result<(), String>;
-    async fn read_notification(&self, timeout: Duration) -> Result<Vec<u8>, String>;
+    async fn write_control(&self, data: &[u8]) -> Result<(), PrinterError>;
+    async fn write_data(&self, data: &[u8]) -> Result<(), PrinterError>;
+    /// Write data and wait until the link acknowledges it.
+    /// The default has no acknowledgement and just calls write_data.
+    async fn write_data_acked(&self, data: &[u8]) -> Result<(), PrinterError> {
+        self.write_data(data).await
+    }
+    /// Read a notification from the printer, returning
+    /// Err(PrinterError::Timeout(timeout)) if none arrives in time.
+    async fn read_notification(&self, timeout: Duration) -> Result<Vec<u8>, PrinterError>;
+    /// Stream of raw notification frames as they arrive.
+    /// The default reports that streaming is unsupported.
+    async fn notification_stream(&self) -> Result<BoxStream<'static, Vec<u8>>, PrinterError> {
+        Err(PrinterError::Transport(
+            "transport does not support notification streams".to_string(),
+        ))
+    }
+    /// Re-establish a dropped link and re-arm notifications.
+    /// The default reports that reconnecting is unsupported.
+    async fn reconnect(&self) -> Result<(), PrinterError> {
+        Err(PrinterError::Transport(
+            "transport does not support reconnect".to_string(),
+        ))
+    }
+    /// Negotiated ATT MTU of the link, if known.
+    /// The default reports none.
+    async fn mtu(&self) -> Option<u16> {
+        None
+    }
+    /// Release the underlying link. The default does nothing.
+    async fn disconnect(&self) -> Result<(), PrinterError> {
+        Ok(())
+    }
 }
 
 /// Scans for CatPrinter-compatible BLE devices.
 ///
 /// - `timeout`: scan duration
 ///
-/// Returns Vec<DeviceInfo> on success
-pub async fn scan(timeout: Duration) -> Result<Vec<DeviceInfo>, String> {
-    let manager = Manager::new()
-        .await
-        .map_err(|e| format!("manager error: {:?}", e))?;
-    let adapters = manager
-        .adapters()
+/// Returns Vec<DeviceInfo> sorted by descending RSSI (nearest first)
+pub async fn scan(timeout: Duration) -> Result<Vec<DeviceInfo>, PrinterError> {
+    let mut list: Vec<DeviceInfo> = scan_stream(timeout).await?.collect().await;
+    // Devices without RSSI sort last
+    list.sort_by_key(|d| std::cmp::Reverse(d.rssi.unwrap_or(i16::MIN)));
+    Ok(list)
+}
+
+/// Scans for BLE devices, yielding each one as soon as it is discovered.
+///
+/// Devices the adapter already knows come first, then newly discovered ones
+/// in discovery order. Each id is yielded once; the stream ends when
+/// `timeout` elapses.
+///
+/// - `timeout`: scan duration
+///
+/// Returns a stream of DeviceInfo
+pub async fn scan_stream(
+    timeout: Duration,
+) -> Result<impl Stream<Item = DeviceInfo> + Send + 'static, PrinterError> {
+    let adapter = default_adapter().await?;
+    let events = adapter
+        .events()
         .await
-        .map_err(|e| format!("adapter list error: {:?}", e))?;
-    let adapter = adapters
-        .into_iter()
-        .next()
-        .ok_or_else(|| "no BLE adapters found".to_string())?;
+        .map_err(|e| PrinterError::Transport(format!("event stream error: {:?}", e)))?;
     adapter
         .start_scan(ScanFilter::default())
         .await
-        .map_err(|e| format!("scan start error: {:?}", e))?;
-    time::sleep(timeout).await;
-    let peripherals = adapter
+        .map_err(|e| PrinterError::Transport(format!("scan start error: {:?}", e)))?;
+    let known = adapter
         .peripherals()
         .await
-        .map_err(|e| format!("peripherals error: {:?}", e))?;
-    let mut list = vec![];
-    for p in peripherals {
-        let id = p.id().to_string();
-        let name = p
-            .properties()
-            .await
-            .ok()
-            .flatten()
-            .and_then(|props| props.local_name);
-        list.push(DeviceInfo { id, name });
+        .map_err(|e| PrinterError::Transport(format!("peripherals error: {:?}", e)))?;
+
+    let discovered = events.filter_map(move |event| {
+        let adapter = adapter.clone();
+        async move {
+            match event {
+                CentralEvent::DeviceDiscovered(id) => adapter.peripheral(&id).await.ok(),
+                _ => None,
+            }
+        }
+    });
+    let mut seen = HashSet::new();
+    Ok(stream::iter(known)
+        .chain(discovered)
+        .then(|p| async move { device_info(&p).await })
+        .filter(move |d| future::ready(seen.insert(d.id.clone())))
+        .take_until(time::sleep(timeout)))
+}
+
+/// Reads id, name, RSSI and manufacturer data from a peripheral.
+async fn device_info(p: &Peripheral) -> DeviceInfo {
+    let id = p.id().to_string();
+    let props = p.properties().await.ok().flatten();
+    let (name, rssi, manufacturer_data) = match props {
+        Some(props) => {
+            let mut entries: Vec<_> = props.manufacturer_data.into_iter().collect();
+            entries.sort_by_key(|(company, _)| *company);
+            let mut data = Vec::new();
+            for (company, bytes) in entries {
+                data.extend_from_slice(&company.to_le_bytes());
+                data.extend_from_slice(&bytes);
+            }
+            (props.local_name, props.rssi, data)
+        }
+        None => (None, None, Vec::new()),
+    };
+    DeviceInfo {
+        id,
+        name,
+        rssi,
+        manufacturer_data,
     }
-    Ok(list)
 }
 
-/// Connects to a CatPrinter BLE device by ID.
-///
-/// - `device_id`: device identifier string
-/// - `_timeout`: connection timeout (unused)
-///
-/// Returns CatPrinterAsync on success
-pub async fn connect(device_id: &str, _timeout: Duration) -> Result<CatPrinterAsync, String> {
+/// Returns the first BLE adapter of the platform manager.
+async fn default_adapter() -> Result<Adapter, PrinterError> {
     let manager = Manager::new()
         .await
-        .map_err(|e| format!("manager error: {:?}", e))?;
+        .map_err(|e| PrinterError::Transport(format!("manager error: {:?}", e)))?;
     let adapters = manager
         .adapters()
         .await
-        .map_err(|e| format!("adapter list error: {:?}", e))?;
-    let adapter = adapters
+        .map_err(|e| PrinterError::Transport(format!("adapter list error: {:?}", e)))?;
+    adapters
         .into_iter()
         .next()
-        .ok_or_else(|| "no BLE adapters found".to_string())?;
+        .ok_or_else(|| PrinterError::Transport("no BLE adapters found".to_string()))
+}
+
+/// Scans for BLE devices whose advertised name starts with a prefix.
+///
+/// - `timeout`: scan duration
+/// - `name_prefix`: case-insensitive name prefix, e.g. "MXW"; devices without
+///   a name are dropped unless the prefix is empty
+///
+/// Returns matching Vec<DeviceInfo>, nearest first
+pub async fn scan_filtered(
+    timeout: Duration,
+    name_prefix: &str,
+) -> Result<Vec<DeviceInfo>, PrinterError> {
+    let devices = scan(timeout).await?;
+    let prefix = name_prefix.to_lowercase();
+    Ok(devices
+        .into_iter()
+        .filter(|d| match &d.name {
+            Some(name) => name.to_lowercase().starts_with(&prefix),
+            None => prefix.is_empty(),
+        })
+        .collect())
+}
+
+/// Connects to a CatPrinter BLE device by ID.
+///
+/// The chunk size is set from the link MTU via auto_chunk_size. btleplug 0.11
+/// exposes no negotiated MTU, so over BLE it stays at DEFAULT_CHUNK_SIZE until
+/// overridden with with_chunk_size.
+///
+/// - `device_id`: device identifier string
+/// - `_timeout`: connection timeout (unused)
+///
+/// Returns CatPrinterAsync on success
+pub async fn connect(device_id: &str, _timeout: Duration) -> Result<CatPrinterAsync, PrinterError> {
+    let transport = open_transport(device_id).await?;
+    let mut cat = CatPrinterAsync::new(Box::new(transport));
+    cat.auto_chunk_size().await;
+    Ok(cat)
+}
+
+/// Bytes of every ATT write taken by the opcode and handle.
+pub const ATT_HEADER_LEN: usize = 3;
+/// Smallest ATT MTU the BLE spec allows; lower reported values are treated as this.
+pub const MIN_ATT_MTU: u16 = 23;
+
+/// Largest data chunk that fits one write on a link with this MTU.
+///
+/// - `mtu`: negotiated ATT MTU
+///
+/// Returns mtu - ATT_HEADER_LEN
+pub fn chunk_size_for_mtu(mtu: u16) -> usize {
+    usize::from(mtu.max(MIN_ATT_MTU)) - ATT_HEADER_LEN
+}
+
+/// Scans and connects to the nearest matching printer without user input.
+///
+/// Errors right after the scan window if no device matched, instead of
+/// waiting for one to appear.
+///
+/// - `scan_duration`: how long to scan
+/// - `connect_timeout`: limit for connecting to the chosen device
+/// - `name_prefix`: case-insensitive name prefix such as "MXW"; None accepts
+///   any device, which may pick up unrelated BLE hardware
+///
+/// Returns CatPrinterAsync connected to the device with the best RSSI
+pub async fn connect_first(
+    scan_duration: Duration,
+    connect_timeout: Duration,
+    name_prefix: Option<&str>,
+) -> Result<CatPrinterAsync, PrinterError> {
+    let devices = scan_filtered(scan_duration, name_prefix.unwrap_or("")).await?;
+    let device = devices.into_iter().next().ok_or_else(|| {
+        PrinterError::Transport(match name_prefix {
+            Some(prefix) => format!("no device named {:?}* found", prefix),
+            None => "no BLE devices found".to_string(),
+        })
+    })?;
+    time::timeout(connect_timeout, connect(&device.id, connect_timeout))
+        .await
+        .map_err(|_| {
+            PrinterError::Transport(format!(
+                "connecting to {} timed out after {:?}",
+                device.id, connect_timeout
+            ))
+        })?
+}
+
+/// Finds a known peripheral by ID and sets it up as a BtleTransport.
+///
+/// - `device_id`: device identifier string
+///
+/// Returns BtleTransport on success
+async fn open_transport(device_id: &str) -> Result<BtleTransport, PrinterError> {
+    let adapter = default_adapter().await?;
     let peripherals = adapter
         .peripherals()
         .await
-        .map_err(|e| format!("peripherals error: {:?}", e))?;
+        .map_err(|e| PrinterError::Transport(format!("peripherals error: {:?}", e)))?;
     let maybe = peripherals
         .into_iter()
         .find(|p| p.id().to_string() == device_id);
-    let peripheral = maybe.ok_or_else(|| format!("device {} not found", device_id))?;
+    let peripheral =
+        maybe.ok_or_else(|| PrinterError::Transport(format!("device {} not found", device_id)))?;
+    let (control, notify, data) = setup_peripheral(&peripheral).await?;
+
+    Ok(BtleTransport::new(peripheral, control, notify, data))
+}
+
+/// Connects (if needed), discovers services, finds the AE01/AE02/AE03
+/// characteristics and subscribes to notifications.
+///
+/// - `peripheral`: printer peripheral
+///
+/// Returns (control, notify, data) characteristics on success
+async fn setup_peripheral(
+    peripheral: &Peripheral,
+) -> Result<(Characteristic, Characteristic, Characteristic), PrinterError> {
     if !peripheral
         .is_connected()
         .await
-        .map_err(|e| format!("{:?}", e))?
+        .map_err(|e| PrinterError::Transport(format!("{:?}", e)))?
     {
         peripheral
             .connect()
             .await
-            .map_err(|e| format!("connect error: {:?}", e))?;
+            .map_err(|e| PrinterError::Transport(format!("connect error: {:?}", e)))?;
     }
     peripheral
         .discover_services()
         .await
-        .map_err(|e| format!("discover error: {:?}", e))?;
+        .map_err(|e| PrinterError::Transport(format!("discover error: {:?}", e)))?;
 
     let ae01 = Uuid::parse_str("0000ae01-0000-1000-8000-00805f9b34fb").unwrap();
     let ae02 = Uuid::parse_str("0000ae02-0000-1000-8000-00805f9b34fb").unwrap();
@@ -131,23 +359,21 @@ pub async fn connect(device_id: &str, _timeout: Duration) -> Result<CatPrinterAs
             data_c = Some(c.clone());
         }
     }
-    let control = control_c.ok_or_else(|| "AE01 control characteristic not found".to_string())?;
-    let notify = notify_c.ok_or_else(|| "AE02 notify characteristic not found".to_string())?;
-    let data = data_c.ok_or_else(|| "AE03 data characteristic not found".to_string())?;
+    let control = control_c.ok_or_else(|| {
+        PrinterError::Transport("AE01 control characteristic not found".to_string())
+    })?;
+    let notify = notify_c.ok_or_else(|| {
+        PrinterError::Transport("AE02 notify characteristic not found".to_string())
+    })?;
+    let data = data_c
+        .ok_or_else(|| PrinterError::Transport("AE03 data characteristic not found".to_string()))?;
 
     peripheral
         .subscribe(&notify)
         .await
-        .map_err(|e| format!("subscribe error: {:?}", e))?;
-
-    let transport = BtleTransport::new(
-        peripheral.clone(),
-        control.clone(),
-        notify.clone(),
-        data.clone(),
-    );
-    let cat = CatPrinterAsync::new(Box::new(transport));
-    Ok(cat)
+        .map_err(|e| PrinterError::Transport(format!("subscribe error: {:?}", e)))?;
+
+    Ok((control, notify, data))
 }
 
 pub struct BtleTransport {
@@ -175,24 +401,33 @@ impl BtleTransport {
 
 #[async_trait]
 impl TransportAsync for BtleTransport {
-    async fn write_control(&self, data: &[u8]) -> Result<(), String> {
+    async fn write_control(&self, data: &[u8]) -> Result<(), PrinterError> {
         self.peripheral
             .write(&self.control, data, WriteType::WithoutResponse)
             .await
-            .map_err(|e| format!("write_control error: {:?}", e))
+            .map_err(|e| PrinterError::Transport(format!("write_control error: {:?}", e)))
     }
-    async fn write_data(&self, data: &[u8]) -> Result<(), String> {
+    async fn write_data(&self, data: &[u8]) -> Result<(), PrinterError> {
         self.peripheral
             .write(&self.data, data, WriteType::WithoutResponse)
             .await
-            .map_err(|e| format!("write_data error: {:?}", e))
+            .map_err(|e| PrinterError::Transport(format!("write_data error: {:?}", e)))
     }
-    async fn read_notification(&self, timeout: Duration) -> Result<Vec<u8>, String> {
-        let mut notifications = self
-            .peripheral
-            .notifications()
+    async fn write_data_acked(&self, data: &[u8]) -> Result<(), PrinterError> {
+        // Not every firmware accepts write requests on the data characteristic
+        if !self.data.properties.contains(CharPropFlags::WRITE) {
+            return self.write_data(data).await;
+        }
+        self.peripheral
+            .write(&self.data, data, WriteType::WithResponse)
             .await
-            .map_err(|e| format!("notifications stream error: {:?}", e))?;
+            .map_err(|e| PrinterError::Transport(format!("write_data error: {:?}", e)))
+    }
+    async fn read_notification(&self, timeout: Duration) -> Result<Vec<u8>, PrinterError> {
+        let mut notifications =
+            self.peripheral.notifications().await.map_err(|e| {
+                PrinterError::Transport(format!("notifications stream error: {:?}", e))
+            })?;
         let deadline = time::Instant::now() + timeout;
         loop {
             let remaining = deadline
@@ -207,154 +442,1819 @@ impl TransportAsync for BtleTransport {
                         continue;
                     }
                 }
-                Ok(None) => return Err("notifications stream ended".to_string()),
-                Err(_) => return Err("timeout waiting for notification".to_string()),
+                Ok(None) => {
+                    return Err(PrinterError::Transport(
+                        "notifications stream ended".to_string(),
+                    ))
+                }
+                Err(_) => return Err(PrinterError::Timeout(timeout)),
             }
         }
     }
+    async fn notification_stream(&self) -> Result<BoxStream<'static, Vec<u8>>, PrinterError> {
+        let notifications =
+            self.peripheral.notifications().await.map_err(|e| {
+                PrinterError::Transport(format!("notifications stream error: {:?}", e))
+            })?;
+        let notify_uuid = self.notify.uuid;
+        Ok(notifications
+            .filter_map(move |note| async move { (note.uuid == notify_uuid).then_some(note.value) })
+            .boxed())
+    }
+    async fn reconnect(&self) -> Result<(), PrinterError> {
+        setup_peripheral(&self.peripheral).await?;
+        Ok(())
+    }
+    async fn mtu(&self) -> Option<u16> {
+        // btleplug 0.11 doesn't expose the negotiated MTU on any platform
+        None
+    }
+    async fn disconnect(&self) -> Result<(), PrinterError> {
+        // The link may already be gone; unsubscribe is best-effort
+        let _ = self.peripheral.unsubscribe(&self.notify).await;
+        let connected = self.peripheral.is_connected().await.unwrap_or(false);
+        if connected {
+            self.peripheral
+                .disconnect()
+                .await
+                .map_err(|e| PrinterError::Transport(format!("disconnect error: {:?}", e)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Blocking BLE transport for the sync CatPrinter.
+///
+/// Owns its own multi-threaded Tokio runtime and drives a BtleTransport with
+/// `block_on`, so btleplug's background tasks keep running between calls.
+/// Each method blocks the calling thread until the BLE operation finishes.
+/// Don't call it from inside another Tokio runtime (block_on panics there);
+/// async code should use CatPrinterAsync instead.
+pub struct BleSyncTransport {
+    runtime: tokio::runtime::Runtime,
+    inner: BtleTransport,
+}
+
+impl BleSyncTransport {
+    /// Connects to a CatPrinter BLE device by ID, starting a private runtime.
+    ///
+    /// - `device_id`: device identifier string (see scan)
+    ///
+    /// Returns BleSyncTransport ready for CatPrinter::new
+    pub fn connect(device_id: &str) -> Result<Self, PrinterError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()?;
+        let inner = runtime.block_on(open_transport(device_id))?;
+        Ok(Self { runtime, inner })
+    }
+
+    /// Disconnects from the printer and shuts down the runtime.
+    pub fn disconnect(self) -> Result<(), PrinterError> {
+        self.runtime.block_on(self.inner.disconnect())
+    }
+}
+
+impl Transport for BleSyncTransport {
+    fn write_control(&mut self, data: &[u8]) -> Result<(), PrinterError> {
+        self.runtime.block_on(self.inner.write_control(data))
+    }
+    fn write_data(&mut self, data: &[u8]) -> Result<(), PrinterError> {
+        self.runtime.block_on(self.inner.write_data(data))
+    }
+    fn write_data_acked(&mut self, data: &[u8]) -> Result<(), PrinterError> {
+        self.runtime.block_on(self.inner.write_data_acked(data))
+    }
+    fn read_notification(&mut self, timeout: Duration) -> Result<Vec<u8>, PrinterError> {
+        self.runtime.block_on(self.inner.read_notification(timeout))
+    }
+}
+
+/// Background ping task started with CatPrinterAsync::start_keepalive.
+///
+/// Dropping the handle aborts the task; use stop to also wait until it has
+/// released its printer clone.
+pub struct Keepalive {
+    task: JoinHandle<()>,
+    lost: Option<oneshot::Receiver<PrinterError>>,
+}
+
+impl Keepalive {
+    /// Wait until a ping fails. Cancel-safe.
+    ///
+    /// Returns the ping error, or None if the task was already stopped or the
+    /// loss was reported by an earlier call
+    pub async fn link_lost(&mut self) -> Option<PrinterError> {
+        // Poll by reference so the receiver survives a cancelled select! branch
+        let result = self.lost.as_mut()?.await;
+        self.lost = None;
+        result.ok()
+    }
+
+    /// Abort the task and wait for it to finish.
+    pub async fn stop(mut self) {
+        self.task.abort();
+        let _ = (&mut self.task).await;
+    }
+}
+
+impl Drop for Keepalive {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
 }
 
 /// Asynchronous CatPrinter API for printing text and images.
 ///
 /// - `transport`: implements TransportAsync trait (BLE)
-/// - `chunk_size`: bytes per data chunk (default: 180)
+/// - `chunk_size`: bytes per data chunk (default: DEFAULT_CHUNK_SIZE; connect
+///   sets it from the link MTU when the transport reports one, which
+///   BtleTransport can't as btleplug 0.11 exposes no MTU)
+/// - `density`: print energy sent before each job (default: DEFAULT_DENSITY)
+/// - `width`: print head width in pixels (default: PRINTER_WIDTH)
+/// - `ack_timeout`: wait for command acknowledgements (default: 2s)
+/// - `print_timeout`: wait for print complete (default: 60s)
+/// - `transform`: orientation change applied to every job (default: None)
+/// - `text_transform`: orientation of rendered text (default: Rotate180)
+/// - `rotation`, `flip_h`, `flip_v`: applied to loaded images in that order,
+///   after EXIF orientation (default: none)
+/// - `alpha_background`: color transparent areas of loaded images are blended
+///   onto (default: DEFAULT_ALPHA_BACKGROUND, white)
+/// - `max_download`: largest image body print_image_from_url accepts, `http`
+///   feature only (default: http::DEFAULT_MAX_DOWNLOAD, 16 MiB)
+/// - `pad_top`, `pad_bottom`: white rows added above and below loaded images
+///   (default: 0)
+/// - `min_height`: loaded images shorter than this are centered vertically on
+///   white rows up to it (default: 0, off)
+/// - `unsharp`: (sigma, threshold) of an unsharp mask applied after resizing,
+///   before brightness/contrast, gamma and dithering (default: None)
+/// - `diffusion_strength`: share of the error the diffusion dithers propagate,
+///   see CatPrinter (default: DEFAULT_DIFFUSION_STRENGTH, 1.0)
+/// - `resize_mode`: how tall images are fitted (default: FitWidth)
+/// - `resize_filter`: scaling filter for loaded images; None picks Nearest when
+///   dithering is None and Lanczos3 otherwise (default: None). See CatPrinter
+///   for the tradeoffs on 1bpp output
+/// - `max_height`: maximum image height in pixels (default: DEFAULT_MAX_HEIGHT, 800)
+/// - `grayscale`: how loaded color images collapse to gray (default: Luma709)
+/// - `signature`: how print_text sets off the author line (default: "-- " after
+///   one blank line)
+/// - `cooldown_temp`: if set, poll status before each job and wait while the head
+///   is hotter than this; trades speed for avoiding mid-print thermal stalls
+///   (default: None, off)
+/// - `cooldown_delay`: wait between those polls (default: DEFAULT_COOLDOWN_DELAY)
+/// - `precheck`: query status before each job and refuse it if can_print fails;
+///   costs one extra round trip per job (default: false)
+/// - `busy_wait`: if set, a print request rejected as Busy waits up to this long
+///   with wait_until_ready and is sent once more (default: None)
+/// - `autocrop`: crop loaded images to their non-white content before resizing (default: false)
+/// - `autocrop_threshold`: gray level at or above which autocrop treats pixels as
+///   background (default: DEFAULT_AUTOCROP_THRESHOLD)
+/// - `bit_order`: pixel order within packed 1bpp bytes (default: Lsb0)
+/// - `on_progress`: called with the lines printed so far for each 0xBB progress
+///   notification while waiting for print complete; `Fn`, not `FnMut` as in
+///   CatPrinter, see with_progress (default: None)
+/// - `reliable`: send data chunks with write_data_acked and retry a failed one
+///   RELIABLE_RETRIES times, so it is sent at most 1 + RELIABLE_RETRIES times;
+///   slower (default: false)
+/// - `inter_chunk_delay`: pause after each data chunk write; works around
+///   firmware whose buffer overruns and drops bands when chunks arrive back to
+///   back (default: Some(DEFAULT_INTER_CHUNK_DELAY), the pacing this printer
+///   always used; None sends at full speed)
 pub struct CatPrinterAsync {
     pub transport: Box<dyn TransportAsync + Send + Sync>,
     chunk_size: usize,
+    density: AtomicU8,
+    width: usize,
+    ack_timeout: Duration,
+    print_timeout: Duration,
+    unsharp: Option<(f32, i32)>,
+    brightness: i16,
+    contrast: f32,
+    gamma: f32,
+    diffusion_strength: f32,
+    resize_mode: ResizeMode,
+    resize_filter: Option<FilterType>,
+    max_height: u32,
+    alpha_background: image::Rgb<u8>,
+    #[cfg(feature = "http")]
+    max_download: usize,
+    pad_top: u16,
+    pad_bottom: u16,
+    min_height: u16,
+    grayscale: GrayscaleMode,
+    signature: SignatureStyle,
+    orientation: Option<Orientation>,
+    rotation: Rotation,
+    flip_h: bool,
+    flip_v: bool,
+    transform: Transform,
+    text_transform: Transform,
+    invert: bool,
+    cooldown_temp: Option<u8>,
+    cooldown_delay: Duration,
+    precheck: bool,
+    busy_wait: Option<Duration>,
+    autocrop: bool,
+    autocrop_threshold: u8,
+    bit_order: BitOrder,
+    on_progress: Option<Box<dyn Fn(u16) + Send + Sync>>,
+    reliable: bool,
+    inter_chunk_delay: Option<Duration>,
+    // Set between begin_print and end_print so keepalive pings don't steal job acks
+    printing: AtomicBool,
+    // Line count of the current job and the last 0xBB progress, for get_live_status
+    job_lines: AtomicU16,
+    lines_printed: AtomicU16,
+    // Notification fragments waiting for the rest of their packet
+    assembler: std::sync::Mutex<PacketAssembler>,
 }
 
 impl CatPrinterAsync {
     pub fn new(transport: Box<dyn TransportAsync + Send + Sync>) -> Self {
         Self {
             transport,
-            chunk_size: 180,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            density: AtomicU8::new(DEFAULT_DENSITY),
+            width: PRINTER_WIDTH,
+            ack_timeout: DEFAULT_ACK_TIMEOUT,
+            print_timeout: DEFAULT_PRINT_TIMEOUT,
+            unsharp: None,
+            brightness: 0,
+            contrast: 1.0,
+            gamma: 1.0,
+            diffusion_strength: DEFAULT_DIFFUSION_STRENGTH,
+            resize_mode: ResizeMode::FitWidth,
+            resize_filter: None,
+            max_height: DEFAULT_MAX_HEIGHT,
+            alpha_background: DEFAULT_ALPHA_BACKGROUND,
+            #[cfg(feature = "http")]
+            max_download: crate::http::DEFAULT_MAX_DOWNLOAD,
+            pad_top: 0,
+            pad_bottom: 0,
+            min_height: 0,
+            grayscale: GrayscaleMode::Luma709,
+            signature: SignatureStyle::default(),
+            orientation: None,
+            rotation: Rotation::None,
+            flip_h: false,
+            flip_v: false,
+            transform: Transform::None,
+            text_transform: Transform::Rotate180,
+            invert: false,
+            cooldown_temp: None,
+            cooldown_delay: DEFAULT_COOLDOWN_DELAY,
+            precheck: false,
+            busy_wait: None,
+            autocrop: false,
+            autocrop_threshold: DEFAULT_AUTOCROP_THRESHOLD,
+            bit_order: BitOrder::Lsb0,
+            on_progress: None,
+            reliable: false,
+            inter_chunk_delay: Some(DEFAULT_INTER_CHUNK_DELAY),
+            printing: AtomicBool::new(false),
+            job_lines: AtomicU16::new(0),
+            lines_printed: AtomicU16::new(0),
+            assembler: std::sync::Mutex::new(PacketAssembler::new()),
         }
     }
 
+    /// Bytes per data chunk; over btleplug this is the only way to use the
+    /// larger chunks a high MTU link would allow.
     pub fn with_chunk_size(mut self, size: usize) -> Self {
         self.chunk_size = size;
         self
     }
 
-    pub async fn get_status(&self, timeout: Duration) -> Result<PrinterStatus, String> {
-        let req = build_control_packet(0xA1, &[0x00]);
-        self.transport.write_control(&req).await?;
-        let raw = self.transport.read_notification(timeout).await?;
-        println!("DEBUG: Raw status notification bytes (0xA1): {:?}", raw);
-        let notif = parse_notification(&raw).map_err(|e| e.to_string())?;
-        Ok(crate::protocol::parse_printer_status(&notif.payload))
+    /// Sizes data chunks to fill each write, from the transport's MTU
+    /// (chunk_size_for_mtu). Keeps the current chunk size when the transport
+    /// doesn't report an MTU, which is always the case with BtleTransport
+    /// as btleplug can't read it yet.
+    ///
+    /// Returns the chunk size now in use
+    pub async fn auto_chunk_size(&mut self) -> usize {
+        if let Some(mtu) = self.transport.mtu().await {
+            self.chunk_size = chunk_size_for_mtu(mtu);
+        }
+        self.chunk_size
     }
 
-    pub async fn get_battery(&self, timeout: Duration) -> Result<u8, String> {
-        let req = build_control_packet(0xAB, &[0x00]);
-        self.transport.write_control(&req).await?;
-        let raw = self.transport.read_notification(timeout).await?;
-        println!("DEBUG: Raw battery notification bytes (0xAB): {:?}", raw);
-        let notif = parse_notification(&raw).map_err(|e| e.to_string())?;
-        // Print all payload bytes for debugging
-        println!("DEBUG: Parsed battery payload: {:?}", notif.payload);
-        // Try to extract battery percent from payload
-        if !notif.payload.is_empty() {
-            Ok(notif.payload[0])
-        } else {
-            Err("Battery payload too short".to_string())
-        }
+    /// Print head width in pixels: 384 for the MXW01, 576 for wide-head
+    /// models. It must match the printer; a wrong value shifts every row and
+    /// produces a skewed, garbled print.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
     }
 
-    pub async fn print_text(&self, main: &str, author: &str) -> Result<(), String> {
-        let width = 384usize;
-        let pixels = crate::protocol::render_text_to_pixels(main, author, width);
-        let height = pixels.len() / width;
-        let rotated_pixels = crate::protocol::rotate_mirror_pixels(&pixels, width, height);
-        self.print_image(&rotated_pixels, width, height, 0x00, None)
-            .await
+    /// Print density applied at the start of every job.
+    ///
+    /// - `level`: clamped to MIN_DENSITY..=MAX_DENSITY
+    pub fn with_density(mut self, level: u8) -> Self {
+        *self.density.get_mut() = level.clamp(MIN_DENSITY, MAX_DENSITY);
+        self
     }
 
-    pub async fn print_image_from_path(
+    /// How long to wait for command acknowledgements.
+    pub fn with_ack_timeout(mut self, timeout: Duration) -> Self {
+        self.ack_timeout = timeout;
+        self
+    }
+
+    /// How long to wait for the print complete notification.
+    pub fn with_print_timeout(mut self, timeout: Duration) -> Self {
+        self.print_timeout = timeout;
+        self
+    }
+
+    /// Sharpen loaded images after resizing with an unsharp mask.
+    ///
+    /// - `sigma`: blur radius of the mask, e.g. 1.0
+    /// - `threshold`: minimum difference that gets sharpened, e.g. 4
+    pub fn with_unsharp(mut self, sigma: f32, threshold: i32) -> Self {
+        self.unsharp = Some((sigma, threshold));
+        self
+    }
+
+    /// Brightness and contrast applied to images before gamma and dithering;
+    /// see apply_brightness_contrast.
+    pub fn with_brightness_contrast(mut self, brightness: i16, contrast: f32) -> Self {
+        self.brightness = brightness;
+        self.contrast = contrast;
+        self
+    }
+
+    /// Gamma correction applied to images before dithering; see apply_gamma.
+    pub fn with_gamma(mut self, gamma: f32) -> Self {
+        self.gamma = gamma;
+        self
+    }
+
+    /// Share of the error the diffusion dithers propagate; see
+    /// apply_dithering_strength.
+    pub fn with_diffusion_strength(mut self, strength: f32) -> Self {
+        self.diffusion_strength = strength;
+        self
+    }
+
+    /// Set the separator and spacing print_text puts before the author.
+    pub fn with_signature(mut self, signature: SignatureStyle) -> Self {
+        self.signature = signature;
+        self
+    }
+
+    /// How images taller than max height are fitted.
+    pub fn with_resize_mode(mut self, mode: ResizeMode) -> Self {
+        self.resize_mode = mode;
+        self
+    }
+
+    /// Maximum image height in pixels.
+    pub fn with_max_height(mut self, height: u32) -> Self {
+        self.max_height = height;
+        self
+    }
+
+    /// Scale loaded images with this filter instead of picking one from the
+    /// dithering mode; see CatPrinter for the tradeoffs.
+    pub fn with_resize_filter(mut self, filter: FilterType) -> Self {
+        self.resize_filter = Some(filter);
+        self
+    }
+
+    /// Color transparent areas of loaded images are blended onto; white
+    /// leaves them unprinted.
+    pub fn with_alpha_background(mut self, background: image::Rgb<u8>) -> Self {
+        self.alpha_background = background;
+        self
+    }
+
+    /// Largest image body print_image_from_url downloads, so a huge response
+    /// can't exhaust memory.
+    #[cfg(feature = "http")]
+    pub fn with_max_download(mut self, bytes: usize) -> Self {
+        self.max_download = bytes;
+        self
+    }
+
+    /// White rows added above and below loaded images.
+    pub fn with_padding(mut self, top: u16, bottom: u16) -> Self {
+        self.pad_top = top;
+        self.pad_bottom = bottom;
+        self
+    }
+
+    /// Center loaded images vertically on at least this many rows.
+    pub fn with_min_height(mut self, height: u16) -> Self {
+        self.min_height = height;
+        self
+    }
+
+    /// Choose how loaded color images are converted to gray.
+    pub fn with_grayscale(mut self, mode: GrayscaleMode) -> Self {
+        self.grayscale = mode;
+        self
+    }
+
+    /// Force a rotation/flip for loaded images instead of reading EXIF.
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = Some(orientation);
+        self
+    }
+
+    /// Rotate loaded images clockwise after EXIF orientation is applied.
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Mirror loaded images after rotation: left-right and/or top-bottom.
+    pub fn with_flip(mut self, flip_h: bool, flip_v: bool) -> Self {
+        self.flip_h = flip_h;
+        self.flip_v = flip_v;
+        self
+    }
+
+    /// Orientation change applied to every printed job, e.g. FlipH for iron-on transfers.
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Orientation of rendered text (print_text, print_layout, print_barcode).
+    pub fn with_text_transform(mut self, transform: Transform) -> Self {
+        self.text_transform = transform;
+        self
+    }
+
+    /// Invert images after dithering so they print white-on-black.
+    pub fn with_invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    /// Wait before each job while the print head is hotter than `temp`,
+    /// polling status every `delay`. Slower, but avoids mid-print stalls on
+    /// back-to-back long prints.
+    pub fn with_cooldown(mut self, temp: u8, delay: Duration) -> Self {
+        self.cooldown_temp = Some(temp);
+        self.cooldown_delay = delay;
+        self
+    }
+
+    /// Pause after every data chunk write, or None to send chunks back to
+    /// back. Raise it if a printer drops bands because chunks arrive faster
+    /// than its firmware can buffer them.
+    pub fn with_inter_chunk_delay(mut self, delay: Option<Duration>) -> Self {
+        self.inter_chunk_delay = delay;
+        self
+    }
+
+    /// Check the printer status before each job and fail fast with NotReady.
+    pub fn with_precheck(mut self, precheck: bool) -> Self {
+        self.precheck = precheck;
+        self
+    }
+
+    /// When a print request is rejected as Busy, wait up to `timeout` for the
+    /// printer to become idle and send it once more.
+    pub fn with_busy_wait(mut self, timeout: Duration) -> Self {
+        self.busy_wait = Some(timeout);
+        self
+    }
+
+    /// Crop loaded images to their non-white content before resizing.
+    ///
+    /// - `enabled`: turn autocrop on or off
+    /// - `white_threshold`: gray level at or above which pixels count as background
+    pub fn with_autocrop(mut self, enabled: bool, white_threshold: u8) -> Self {
+        self.autocrop = enabled;
+        self.autocrop_threshold = white_threshold;
+        self
+    }
+
+    /// Bit order for packed 1bpp rows; Msb0 fixes prints scrambled within each byte.
+    pub fn with_bit_order(mut self, bit_order: BitOrder) -> Self {
+        self.bit_order = bit_order;
+        self
+    }
+
+    /// Report print progress (lines printed so far) while a job prints.
+    ///
+    /// CatPrinterAsync prints through `&self` and may be shared between tasks,
+    /// so the callback is `Fn + Sync` rather than CatPrinter's `FnMut`; keep
+    /// any state it updates in an atomic or a Mutex.
+    pub fn with_progress(mut self, callback: impl Fn(u16) + Send + Sync + 'static) -> Self {
+        self.on_progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Acknowledge and retransmit data chunks; see RELIABLE_RETRIES.
+    pub fn with_reliable(mut self, reliable: bool) -> Self {
+        self.reliable = reliable;
+        self
+    }
+
+    /// Set the print density and send it to the printer right away.
+    ///
+    /// - `level`: clamped to MIN_DENSITY..=MAX_DENSITY; 0x40-0x6F suits most paper
+    ///
+    /// The value is also re-sent at the start of every print job.
+    pub async fn set_density(&self, level: u8) -> Result<(), PrinterError> {
+        let level = level.clamp(MIN_DENSITY, MAX_DENSITY);
+        self.density.store(level, Ordering::Relaxed);
+        let req = build_density_packet(level);
+        self.transport.write_control(&req).await
+    }
+
+    /// Subscribe to notifications as they arrive, including unsolicited ones
+    /// like error states or 0xAA print complete.
+    ///
+    /// Frames are joined into whole packets by a PacketAssembler of the
+    /// stream's own, so packets split over several BLE events come out once.
+    /// The stream yields parsed notifications and silently skips packets that
+    /// fail to parse. It ends when the link drops.
+    pub async fn notifications(
         &self,
-        path: &str,
-        dithering: ImageDithering,
-    ) -> Result<(), String> {
-        let img = image::open(path).map_err(|e| e.to_string())?;
-        let width = 384;
-        let resized = img.thumbnail(width, u32::MAX);
-        let mut gray = resized.to_luma8();
-
-        match dithering {
-            ImageDithering::FloydSteinberg => {
-                image::imageops::dither(&mut gray, &image::imageops::BiLevel);
-            }
-            ImageDithering::Atkinson => {
-                atkinson_dither(&mut gray);
-            }
-            ImageDithering::Bayer => {
-                bayer_dither(&mut gray);
+    ) -> Result<impl Stream<Item = Notification> + Send + 'static, PrinterError> {
+        let raw = self.transport.notification_stream().await?;
+        Ok(raw
+            .scan(PacketAssembler::new(), |assembler, frame| {
+                assembler.push(&frame);
+                let packets: Vec<Vec<u8>> =
+                    std::iter::from_fn(|| assembler.next_packet()).collect();
+                future::ready(Some(stream::iter(packets)))
+            })
+            .flatten()
+            .filter_map(|packet| async move { parse_notification(&packet).ok() }))
+    }
+
+    /// Reconnect to the same device after the link dropped.
+    ///
+    /// - `attempts`: maximum number of reconnect attempts
+    /// - `base_delay`: wait after the first failure, doubled after each further one
+    ///
+    /// Returns the number of attempts used, or the last error once all attempts fail
+    pub async fn reconnect(
+        &self,
+        attempts: usize,
+        base_delay: Duration,
+    ) -> Result<usize, PrinterError> {
+        let mut last_err =
+            PrinterError::Transport("reconnect called with zero attempts".to_string());
+        let mut delay = base_delay;
+        for attempt in 1..=attempts {
+            match self.transport.reconnect().await {
+                Ok(()) => {
+                    // Fragments from the old link can't be completed any more
+                    self.lock_assembler().clear();
+                    return Ok(attempt);
+                }
+                Err(e) => last_err = e,
             }
-            ImageDithering::Halftone => {
-                gray = halftone_dither(&gray);
+            if attempt < attempts {
+                time::sleep(delay).await;
+                delay = delay.saturating_mul(2);
             }
-            ImageDithering::Threshold => {
-                // manual thresholding
-                for pixel in gray.pixels_mut() {
-                    if pixel[0] > 127 {
-                        pixel[0] = 255;
-                    } else {
-                        pixel[0] = 0;
-                    }
+        }
+        Err(last_err)
+    }
+
+    /// Stop notifications and drop the BLE connection.
+    ///
+    /// Consumes the printer so it can't be used afterwards. Safe to call if the
+    /// device already dropped the link; calls on a printer whose link is gone
+    /// return transport errors (or time out) instead of hanging.
+    pub async fn disconnect(self) -> Result<(), PrinterError> {
+        self.transport.disconnect().await
+    }
+
+    /// Check that the link is still up by sending a status query (0xA1).
+    ///
+    /// - `timeout`: wait for the response
+    ///
+    /// Returns Ok if the device answered with any notification
+    pub async fn ping(&self, timeout: Duration) -> Result<(), PrinterError> {
+        let req = build_control_packet(Command::GetStatus, &[0x00]);
+        self.transport.write_control(&req).await?;
+        let raw = self.read_frame(timeout).await?;
+        parse_notification(&raw)?;
+        Ok(())
+    }
+
+    /// Ping the printer every `interval` in a background task.
+    ///
+    /// Pings are skipped while a print job is running. The first failed ping
+    /// (waiting up to the ack timeout) ends the task and is reported through
+    /// Keepalive::link_lost, e.g. to trigger reconnect.
+    ///
+    /// The task holds a clone of the Arc, so stop the returned Keepalive
+    /// before unwrapping the printer to disconnect it.
+    ///
+    /// - `interval`: time between pings
+    pub fn start_keepalive(self: &Arc<Self>, interval: Duration) -> Keepalive {
+        let printer = Arc::clone(self);
+        let (tx, rx) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let mut ticker = time::interval(interval);
+            ticker.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+            // The first tick completes immediately
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if printer.printing.load(Ordering::Acquire) {
+                    continue;
+                }
+                if let Err(err) = printer.ping(printer.ack_timeout).await {
+                    let _ = tx.send(err);
+                    return;
                 }
             }
+        });
+        Keepalive {
+            task,
+            lost: Some(rx),
         }
+    }
+
+    pub async fn get_status(&self, timeout: Duration) -> Result<PrinterStatus, PrinterError> {
+        let req = build_control_packet(Command::GetStatus, &[0x00]);
+        self.transport.write_control(&req).await?;
+        let raw = self.read_frame(timeout).await?;
+        let notif = parse_notification(&raw)?;
+        Ok(crate::protocol::parse_printer_status(&notif.payload))
+    }
+
+    pub async fn get_battery(&self, timeout: Duration) -> Result<u8, PrinterError> {
+        let req = build_control_packet(Command::GetBattery, &[0x00]);
+        self.transport.write_control(&req).await?;
+        let notif = self
+            .wait_for_notification(Command::GetBattery, timeout)
+            .await?;
+        parse_battery(&notif.payload)
+            .ok_or_else(|| PrinterError::Protocol("Battery payload too short".to_string()))
+    }
 
-        let (width, height) = gray.dimensions();
-        let pixels = gray.as_raw();
-        self.print_image(pixels, width as usize, height as usize, 0x00, None)
+    /// Print the diagnostic page described at render_test_page; see
+    /// CatPrinter::print_test_page.
+    pub async fn print_test_page(&self) -> Result<(), PrinterError> {
+        let info = self.get_device_info(self.ack_timeout).await;
+        let status = self.get_status(self.ack_timeout).await;
+        let lines = test_page_info(
+            info.ok(),
+            status.ok(),
+            self.density.load(Ordering::Relaxed),
+            self.width,
+        );
+        let (pixels, height) = render_test_page(&lines, self.width);
+        let rotated = transform_pixels(&pixels, self.width, height, self.text_transform);
+        self.print_image(&rotated, self.width, height, MODE_1BPP, None)
             .await
     }
 
-    pub async fn print_image(
+    /// Query firmware/hardware version (0xB1), e.g. to check grayscale support.
+    ///
+    /// - `timeout`: wait for the response
+    ///
+    /// Returns DeviceInfoReport on success
+    pub async fn get_device_info(
         &self,
-        pixels: &[u8],
-        width: usize,
-        height: usize,
-        mode: u8,
-        chunk_size: Option<usize>,
-    ) -> Result<(), String> {
-        let packed = pack_1bpp_pixels(pixels, width, height).map_err(|e| e.to_string())?;
-        let line_count: u16 = height as u16;
-        let mut a9_payload = Vec::new();
-        a9_payload.extend_from_slice(&line_count.to_le_bytes());
-        a9_payload.push(0x30);
-        a9_payload.push(mode);
-        let a9 = build_control_packet(0xA9, &a9_payload);
-        self.transport.write_control(&a9).await?;
-        let resp = self
-            .transport
-            .read_notification(Duration::from_secs(2))
+        timeout: Duration,
+    ) -> Result<DeviceInfoReport, PrinterError> {
+        let req = build_control_packet(Command::GetDeviceInfo, &[0x00]);
+        self.transport.write_control(&req).await?;
+        let notif = self
+            .wait_for_notification(Command::GetDeviceInfo, timeout)
             .await?;
-        let parsed = parse_notification(&resp).map_err(|e| e.to_string())?;
-        if parsed.command_id != 0xA9 || parsed.payload.first() == Some(&0x01u8) {
-            return Err("printer rejected print request".into());
-        }
-        let size = chunk_size.unwrap_or(self.chunk_size);
-        for chunk in chunk_data(&packed, size) {
-            self.transport.write_data(chunk).await?;
-            time::sleep(Duration::from_millis(10)).await;
-        }
-        let ad = build_control_packet(0xAD, &[0x00]);
-        self.transport.write_control(&ad).await?;
-        let deadline = time::Instant::now() + Duration::from_secs(60);
+        Ok(parse_device_info(&notif.payload)?)
+    }
+
+    /// Read the auto-sleep timer.
+    ///
+    /// - `timeout`: wait for the response
+    ///
+    /// Returns idle minutes before the printer powers off, SLEEP_NEVER if it
+    /// stays awake
+    pub async fn get_sleep_timeout(&self, timeout: Duration) -> Result<u16, PrinterError> {
+        self.transport
+            .write_control(&build_get_sleep_timeout_packet())
+            .await?;
+        let notif = self
+            .wait_for_notification(Command::GetSleepTimeout, timeout)
+            .await?;
+        parse_sleep_timeout(&notif.payload)
+            .ok_or_else(|| PrinterError::Protocol("sleep timeout payload too short".into()))
+    }
+
+    /// Set the auto-sleep timer, e.g. SLEEP_NEVER for kiosk setups that must
+    /// keep the connection.
+    ///
+    /// - `minutes`: idle minutes before power off, SLEEP_NEVER to never sleep
+    /// - `timeout`: wait for the confirmation
+    ///
+    /// Returns Err(PrinterError::Rejected) if the printer reports a different
+    /// value back, e.g. because the firmware doesn't support SLEEP_NEVER
+    pub async fn set_sleep_timeout(
+        &self,
+        minutes: u16,
+        timeout: Duration,
+    ) -> Result<(), PrinterError> {
+        self.transport
+            .write_control(&build_set_sleep_timeout_packet(minutes))
+            .await?;
+        let notif = self
+            .wait_for_notification(Command::SetSleepTimeout, timeout)
+            .await?;
+        match parse_sleep_timeout(&notif.payload) {
+            Some(set) if set == minutes => Ok(()),
+            Some(_) => Err(PrinterError::Rejected),
+            None => Err(PrinterError::Protocol(
+                "sleep timeout payload too short".into(),
+            )),
+        }
+    }
+
+    /// Advance the paper, e.g. to tear off a finished print.
+    ///
+    /// - `lines`: dot lines to feed (clamped to MAX_FEED_LINES)
+    pub async fn feed_paper(&self, lines: u16) -> Result<(), PrinterError> {
+        self.move_paper(Command::FeedPaper, lines).await
+    }
+
+    /// Pull the paper back into the printer.
+    ///
+    /// - `lines`: dot lines to retract (clamped to MAX_FEED_LINES)
+    pub async fn retract_paper(&self, lines: u16) -> Result<(), PrinterError> {
+        self.move_paper(Command::RetractPaper, lines).await
+    }
+
+    /// Reads one whole notification packet, waiting for further fragments
+    /// if the link delivered only part of it (see PacketAssembler).
+    ///
+    /// - `timeout`: wait for the whole packet
+    ///
+    /// Returns the raw packet
+    async fn read_frame(&self, timeout: Duration) -> Result<Vec<u8>, PrinterError> {
+        let deadline = time::Instant::now() + timeout;
+        loop {
+            if let Some(packet) = self.lock_assembler().next_packet() {
+                return Ok(packet);
+            }
+            let remaining = deadline.saturating_duration_since(time::Instant::now());
+            if remaining.is_zero() {
+                return Err(PrinterError::Timeout(timeout));
+            }
+            let fragment = self
+                .transport
+                .read_notification(remaining)
+                .await
+                .map_err(|e| e.timed_out_after(timeout))?;
+            self.lock_assembler().push(&fragment);
+        }
+    }
+
+    fn lock_assembler(&self) -> std::sync::MutexGuard<'_, PacketAssembler> {
+        // The assembler is a plain byte buffer, still usable after a panic
+        self.assembler.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn move_paper(&self, command: Command, lines: u16) -> Result<(), PrinterError> {
+        let req = build_feed_packet(command, lines);
+        self.transport.write_control(&req).await?;
+        let notif = self
+            .wait_for_notification(command, self.ack_timeout)
+            .await?;
+        if notif.payload.first() == Some(&0x01u8) {
+            return Err(PrinterError::Busy);
+        }
+        Ok(())
+    }
+
+    /// Print text with an author signature; an empty `author` prints none.
+    /// Refuses with Err(PrinterError::Image) if nothing black was rendered.
+    pub async fn print_text(&self, main: &str, author: &str) -> Result<(), PrinterError> {
+        let width = self.width;
+        let pixels = render_text_to_pixels_signed(
+            main,
+            author,
+            width,
+            DEFAULT_TEXT_SIZE,
+            0,
+            &self.signature,
+        );
+        ensure_ink(&pixels)?;
+        let height = pixels.len() / width;
+        let rotated_pixels = transform_pixels(&pixels, width, height, self.text_transform);
+        self.print_image(&rotated_pixels, width, height, MODE_1BPP, None)
+            .await
+    }
+
+    /// Show exactly what print_image would put on paper, see
+    /// CatPrinter::preview_image.
+    ///
+    /// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+    /// - `width`, `height`: image dimensions
+    ///
+    /// Returns the preview as a GrayImage of the same size
+    pub fn preview_image(
+        &self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Result<image::GrayImage, PrinterError> {
+        preview_pixels(
+            pixels,
+            width,
+            height,
+            self.width,
+            self.transform,
+            self.bit_order,
+        )
+    }
+
+    /// Preview of print_text, see preview_image.
+    ///
+    /// - `main`: main text
+    /// - `author`: author name; empty prints no signature
+    ///
+    /// Returns the preview as a GrayImage `width` pixels wide
+    pub fn preview_text(&self, main: &str, author: &str) -> Result<image::GrayImage, PrinterError> {
+        let width = self.width;
+        let pixels = render_text_to_pixels_signed(
+            main,
+            author,
+            width,
+            DEFAULT_TEXT_SIZE,
+            0,
+            &self.signature,
+        );
+        let height = pixels.len() / width;
+        let rotated = transform_pixels(&pixels, width, height, self.text_transform);
+        self.preview_image(&rotated, width, height)
+    }
+
+    /// Print lightly formatted text: `# ` title lines at a larger size and
+    /// blank-line paragraph breaks, see render_formatted_to_pixels for the
+    /// syntax. Rotated like print_text.
+    ///
+    /// - `text`: formatted text
+    ///
+    /// Returns Ok(()) on success
+    pub async fn print_formatted(&self, text: &str) -> Result<(), PrinterError> {
+        let width = self.width;
+        let pixels = render_formatted_to_pixels(text, width, DEFAULT_TEXT_SIZE);
+        ensure_ink(&pixels)?;
+        let height = pixels.len() / width;
+        let rotated = transform_pixels(&pixels, width, height, self.text_transform);
+        self.print_image(&rotated, width, height, MODE_1BPP, None)
+            .await
+    }
+
+    /// Print a linear barcode with its human-readable caption underneath.
+    /// The page is rotated like print_text so the caption reads upright.
+    ///
+    /// - `data`: text to encode (12 or 13 digits for EAN-13)
+    /// - `symbology`: Barcode::Code128 or Barcode::Ean13
+    /// - `height_px`: bar height in pixels
+    pub async fn print_barcode(
+        &self,
+        data: &str,
+        symbology: Barcode,
+        height_px: u32,
+    ) -> Result<(), PrinterError> {
+        let (pixels, height) = render_barcode_to_pixels(data, symbology, height_px, self.width)?;
+        let rotated = transform_pixels(&pixels, self.width, height, self.text_transform);
+        self.print_image(&rotated, self.width, height, MODE_1BPP, None)
+            .await
+    }
+
+    /// Print images and text stacked top to bottom as one job, e.g. a logo
+    /// followed by receipt text. The page is rotated like print_text so it
+    /// reads in the same direction as text prints.
+    ///
+    /// - `elements`: layout blocks in print order
+    pub async fn print_layout(&self, elements: &[LayoutElement<'_>]) -> Result<(), PrinterError> {
+        let (pixels, height) = render_layout(elements, self.width)?;
+        let rotated = transform_pixels(&pixels, self.width, height, self.text_transform);
+        self.print_image(&rotated, self.width, height, MODE_1BPP, None)
+            .await
+    }
+
+    /// Print an image from a file path, with optional dithering; processed
+    /// like CatPrinter::print_image_from_path.
+    ///
+    /// - `path`: path to image file
+    /// - `dithering`: dithering algorithm to apply
+    pub async fn print_image_from_path(
+        &self,
+        path: &str,
+        dithering: ImageDithering,
+    ) -> Result<(), PrinterError> {
+        let img = decode_oriented(image::ImageReader::open(path)?, self.orientation)?;
+        self.print_dynamic_image(img, dithering).await
+    }
+
+    /// Print an image from encoded bytes (PNG, JPEG, ...), with optional dithering.
+    ///
+    /// - `data`: encoded image file contents
+    /// - `dithering`: dithering algorithm to apply
+    pub async fn print_image_from_bytes(
+        &self,
+        data: &[u8],
+        dithering: ImageDithering,
+    ) -> Result<(), PrinterError> {
+        let reader = image::ImageReader::new(std::io::Cursor::new(data));
+        let img = decode_oriented(reader, self.orientation)?;
+        self.print_dynamic_image(img, dithering).await
+    }
+
+    /// Download an image and print it like print_image_from_bytes.
+    /// Needs the `http` feature; see http::fetch_image for what URLs and
+    /// responses are accepted.
+    ///
+    /// - `url`: `http://` or `https://` URL of the image
+    /// - `dithering`: dithering algorithm to apply
+    #[cfg(feature = "http")]
+    pub async fn print_image_from_url(
+        &self,
+        url: &str,
+        dithering: ImageDithering,
+    ) -> Result<(), PrinterError> {
+        let data = crate::http::fetch_image(url, self.max_download).await?;
+        self.print_image_from_bytes(&data, dithering).await
+    }
+
+    async fn print_dynamic_image(
+        &self,
+        img: image::DynamicImage,
+        dithering: ImageDithering,
+    ) -> Result<(), PrinterError> {
+        let img = rotate_flip(img, self.rotation, self.flip_h, self.flip_v);
+        let img = flatten_alpha(img, self.alpha_background);
+        let img = if self.autocrop {
+            autocrop_image(&img, self.autocrop_threshold, self.grayscale)
+        } else {
+            img
+        };
+        let settings = ImageSettings {
+            width: self.width,
+            grayscale: self.grayscale,
+            resize_mode: self.resize_mode,
+            resize_filter: self.resize_filter,
+            max_height: self.max_height,
+            unsharp: self.unsharp,
+            brightness: self.brightness,
+            contrast: self.contrast,
+            gamma: self.gamma,
+            diffusion_strength: self.diffusion_strength,
+        };
+        let (mut pixels, width, _) = prepare_image(&img, dithering, &settings);
+        if self.invert {
+            invert_pixels(&mut pixels);
+        }
+        let (pixels, height) = pad_pixels_vertical(
+            &pixels,
+            width,
+            self.pad_top.into(),
+            self.pad_bottom.into(),
+            self.min_height.into(),
+        );
+        self.print_image(&pixels, width, height, MODE_1BPP, None)
+            .await
+    }
+
+    /// Print a QR code centered on the paper, with the default error correction.
+    ///
+    /// - `data`: text or URL to encode
+    /// - `scale`: pixels per QR module
+    pub async fn print_qr(&self, data: &str, scale: u32) -> Result<(), PrinterError> {
+        self.print_qr_with_level(data, scale, DEFAULT_EC_LEVEL)
+            .await
+    }
+
+    /// Print a QR code centered on the paper.
+    ///
+    /// - `data`: text or URL to encode
+    /// - `scale`: pixels per QR module
+    /// - `ec_level`: error-correction level
+    pub async fn print_qr_with_level(
+        &self,
+        data: &str,
+        scale: u32,
+        ec_level: EcLevel,
+    ) -> Result<(), PrinterError> {
+        let width = self.width;
+        let (pixels, height) = render_qr_to_pixels(data, scale, ec_level, width)?;
+        self.print_image(&pixels, width, height, MODE_1BPP, None)
+            .await
+    }
+
+    pub async fn print_image(
+        &self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        mode: u8,
+        chunk_size: Option<usize>,
+    ) -> Result<(), PrinterError> {
+        let never = CancellationToken::new();
+        self.print_image_cancellable(pixels, width, height, mode, chunk_size, &never)
+            .await
+    }
+
+    /// Print a raw grayscale pixel buffer, stopping early when `cancel` fires.
+    /// On cancel the printer is sent 0xAC (cancel print) so it isn't left
+    /// mid-job, and Err(PrinterError::Cancelled) is returned.
+    ///
+    /// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+    /// - `width`, `height`: image dimensions
+    /// - `mode`: print mode (0x00 = 1bpp, 0x02 = 4bpp grayscale)
+    /// - `chunk_size`: optional override for data chunk size
+    /// - `cancel`: token a UI or another task can cancel
+    pub async fn print_image_cancellable(
+        &self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        mode: u8,
+        chunk_size: Option<usize>,
+        cancel: &CancellationToken,
+    ) -> Result<(), PrinterError> {
+        let line_count = check_line_count(height)?;
+        let packed = self.pack_job(pixels, width, height, mode)?;
+        if cancel.is_cancelled() {
+            return Err(PrinterError::Cancelled);
+        }
+        self.begin_print(line_count, mode).await?;
+        let size = chunk_size.unwrap_or(self.chunk_size);
+        let result = async {
+            for chunk in chunk_data_iter(&packed, size) {
+                if cancel.is_cancelled() {
+                    return Err(PrinterError::Cancelled);
+                }
+                self.write_chunk(chunk).await?;
+            }
+            tokio::select! {
+                res = self.end_print() => res,
+                _ = cancel.cancelled() => Err(PrinterError::Cancelled),
+            }
+        }
+        .await;
+        if let Err(PrinterError::Cancelled) = result {
+            // Best-effort: the link may be what's stuck
+            let _ = self.transport.write_control(&build_cancel_packet()).await;
+            self.printing.store(false, Ordering::Release);
+        }
+        result
+    }
+
+    /// Print `copies` of an image in one continuous job, e.g. a sheet of
+    /// labels; see CatPrinter::print_image_copies for why one job is preferred.
+    ///
+    /// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+    /// - `width`, `height`: image dimensions
+    /// - `mode`: print mode (0x00 = 1bpp, 0x02 = 4bpp grayscale)
+    /// - `copies`: number of copies, at least 1
+    /// - `gap_lines`: blank rows between copies
+    ///
+    /// Returns Err(PrinterError::Image) if copies is 0 or the whole run exceeds
+    /// 65535 lines
+    pub async fn print_image_copies(
+        &self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        mode: u8,
+        copies: usize,
+        gap_lines: u16,
+    ) -> Result<(), PrinterError> {
+        let packed = self.pack_job(pixels, width, height, mode)?;
+        let row_bytes = packed_row_bytes(width, mode)?;
+        let (data, line_count) = repeat_packed_rows(&packed, row_bytes, copies, gap_lines)
+            .map_err(|e| PrinterError::Image(e.into()))?;
+        self.begin_print(line_count, mode).await?;
+        self.send_data(&data).await?;
+        self.end_print().await
+    }
+
+    /// Validates, transforms and packs a pixel buffer as print_image sends it.
+    fn pack_job(
+        &self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        mode: u8,
+    ) -> Result<Vec<u8>, PrinterError> {
+        check_print_width(width, self.width)?;
+        check_pixel_buffer(pixels, width, height)?;
+        let packed = match self.transform {
+            Transform::None => {
+                pack_pixels_for_mode_ordered(pixels, width, height, mode, self.bit_order)?
+            }
+            t => pack_pixels_for_mode_ordered(
+                &transform_pixels(pixels, width, height, t),
+                width,
+                height,
+                mode,
+                self.bit_order,
+            )?,
+        };
+        Ok(packed)
+    }
+
+    /// Print several printer-width images as a single print job.
+    ///
+    /// - `images`: (pixels, width, height) for each image, top to bottom
+    /// - `mode`: print mode (0x00 = 1bpp, 0x02 = 4bpp grayscale)
+    pub async fn print_images(
+        &self,
+        images: &[(&[u8], usize, usize)],
+        mode: u8,
+    ) -> Result<(), PrinterError> {
+        self.print_images_with_gap(images, mode, 0).await
+    }
+
+    /// Print several printer-width images as a single print job, separated by blank rows.
+    ///
+    /// - `images`: (pixels, width, height) for each image, top to bottom
+    /// - `mode`: print mode (0x00 = 1bpp, 0x02 = 4bpp grayscale)
+    /// - `gap`: blank rows between images
+    pub async fn print_images_with_gap(
+        &self,
+        images: &[(&[u8], usize, usize)],
+        mode: u8,
+        gap: usize,
+    ) -> Result<(), PrinterError> {
+        let width = self.width;
+        let (pixels, height) = stack_images(images, width, gap)?;
+        self.print_image(&pixels, width, height, mode, None).await
+    }
+
+    /// Print an image wider than the printer by cutting it into printer-width vertical
+    /// tiles and printing them one below the other, left tile first.
+    ///
+    /// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+    /// - `width`, `height`: image dimensions (any width)
+    /// - `mode`: print mode (0x00 = 1bpp, 0x02 = 4bpp grayscale)
+    /// - `separator`: blank rows printed between tiles
+    pub async fn print_image_tiled(
+        &self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        mode: u8,
+        separator: usize,
+    ) -> Result<(), PrinterError> {
+        let tiles = split_into_tiles(pixels, width, height, self.width)?;
+        let images: Vec<(&[u8], usize, usize)> = tiles
+            .iter()
+            .map(|t| (t.as_slice(), self.width, height))
+            .collect();
+        self.print_images_with_gap(&images, mode, separator).await
+    }
+
+    /// Print a raw grayscale pixel buffer in 1bpp mode, packing and sending a
+    /// few rows at a time instead of building the whole packed image first.
+    /// Sends exactly the same bytes and chunks as print_image, except that the
+    /// transform setting is not applied; pass pixels already oriented.
+    ///
+    /// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+    /// - `width`, `height`: image dimensions
+    /// - `chunk_size`: optional override for data chunk size
+    pub async fn print_image_streaming(
+        &self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        chunk_size: Option<usize>,
+    ) -> Result<(), PrinterError> {
+        check_print_width(width, self.width)?;
+        check_pixel_buffer(pixels, width, height)?;
+        self.begin_print(check_line_count(height)?, MODE_1BPP)
+            .await?;
+        let size = chunk_size.unwrap_or(self.chunk_size);
+        let bytes_per_row = width.div_ceil(8);
+        let rows_per_batch = if size == 0 {
+            height
+        } else {
+            (size / bytes_per_row).max(1)
+        };
+        let mut buf = Vec::with_capacity(size.max(bytes_per_row) + bytes_per_row * rows_per_batch);
+        let mut row = 0;
+        while row < height {
+            let end = usize::min(row + rows_per_batch, height);
+            pack_rows_into_ordered(pixels, width, row..end, &mut buf, self.bit_order);
+            row = end;
+            if size > 0 {
+                let mut sent = 0;
+                for chunk in buf.chunks_exact(size) {
+                    self.write_chunk(chunk).await?;
+                    sent += size;
+                }
+                buf.drain(..sent);
+            }
+        }
+        if !buf.is_empty() {
+            self.write_chunk(&buf).await?;
+        }
+        self.end_print().await
+    }
+
+    /// Print rows that are already packed in the printer's format, e.g. by
+    /// pack_pixels_for_mode or an external pipeline, without repacking them.
+    ///
+    /// - `packed`: exactly `line_count` rows of `width` pixels, the configured
+    ///   print width (packed_row_bytes(width, mode) bytes each, e.g. 48 in 1bpp
+    ///   and 192 in 4bpp at PRINTER_WIDTH); transform and invert are not applied
+    /// - `line_count`: rows in `packed`
+    /// - `mode`: print mode (0x00 = 1bpp, 0x02 = 4bpp grayscale)
+    ///
+    /// Returns Ok(()) once the printer reports print complete
+    pub async fn print_packed(
+        &self,
+        packed: &[u8],
+        line_count: u16,
+        mode: u8,
+    ) -> Result<(), PrinterError> {
+        let expected = packed_row_bytes(self.width, mode)? * line_count as usize;
+        if line_count == 0 || packed.len() != expected {
+            return Err(PrinterError::Image(format!(
+                "packed data is {} bytes, {} lines need {}",
+                packed.len(),
+                line_count,
+                expected
+            )));
+        }
+        self.begin_print(line_count, mode).await?;
+        self.send_data(packed).await?;
+        self.end_print().await
+    }
+
+    /// Start a print job by hand: sends density and the 0xA9 print request and
+    /// checks the printer accepted it. Follow with send_data and end_print.
+    ///
+    /// The printer needs the line count up front. When content is generated
+    /// lazily and the total height is unknown, declare an upper bound and pad
+    /// with white rows up to it before end_print.
+    ///
+    /// - `line_count`: rows that will be sent
+    /// - `mode`: print mode (0x00 = 1bpp, 0x02 = 4bpp grayscale)
+    pub async fn begin_print(&self, line_count: u16, mode: u8) -> Result<(), PrinterError> {
+        self.job_lines.store(line_count, Ordering::Relaxed);
+        self.lines_printed.store(0, Ordering::Relaxed);
+        self.printing.store(true, Ordering::Release);
+        let result = self.request_print(line_count, mode).await;
+        if result.is_err() {
+            self.printing.store(false, Ordering::Release);
+        }
+        result
+    }
+
+    async fn request_print(&self, line_count: u16, mode: u8) -> Result<(), PrinterError> {
+        if let Some(limit) = self.cooldown_temp {
+            self.wait_for_cooldown(limit).await?;
+        }
+        if self.precheck {
+            self.get_status(self.ack_timeout).await?.can_print()?;
+        }
+        match (
+            self.try_print_request(line_count, mode).await,
+            self.busy_wait,
+        ) {
+            (Err(PrinterError::Busy), Some(wait)) => {
+                self.wait_until_ready(wait).await?;
+                self.try_print_request(line_count, mode).await
+            }
+            (result, _) => result,
+        }
+    }
+
+    /// One density + 0xA9 exchange; a rejection is explained by a status query.
+    async fn try_print_request(&self, line_count: u16, mode: u8) -> Result<(), PrinterError> {
+        let density = build_density_packet(self.density.load(Ordering::Relaxed));
+        self.transport.write_control(&density).await?;
+        let a9 = build_print_request_packet(line_count, mode);
+        self.transport.write_control(&a9).await?;
+        let parsed = self
+            .wait_for_notification(Command::PrintStart, self.ack_timeout)
+            .await?;
+        if parsed.payload.first() == Some(&0x01u8) {
+            return Err(match self.get_status(self.ack_timeout).await {
+                Ok(status) => status.rejection_reason(),
+                Err(_) => PrinterError::Rejected,
+            });
+        }
+        Ok(())
+    }
+
+    /// Poll the status every READY_POLL_INTERVAL until the printer is idle
+    /// (Standby), e.g. before retrying a job rejected with Busy.
+    ///
+    /// - `timeout`: longest time to wait
+    ///
+    /// Returns Ok(()) once idle, or Err(PrinterError::Timeout) if it is still
+    /// printing or in an error state when `timeout` runs out
+    pub async fn wait_until_ready(&self, timeout: Duration) -> Result<(), PrinterError> {
+        let deadline = time::Instant::now() + timeout;
+        loop {
+            if self.get_status(self.ack_timeout).await?.state == PrinterState::Standby {
+                return Ok(());
+            }
+            if time::Instant::now() + READY_POLL_INTERVAL > deadline {
+                return Err(PrinterError::Timeout(timeout));
+            }
+            time::sleep(READY_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Stop the job the printer is working on: sends 0xAC (cancel print) and
+    /// polls status until the printer reports Standby. See
+    /// CatPrinter::cancel_print.
+    ///
+    /// Returns Ok(()) once idle, or Err(PrinterError::Timeout) if the printer
+    /// isn't back in Standby within the ack timeout
+    pub async fn cancel_print(&self) -> Result<(), PrinterError> {
+        self.transport.write_control(&build_cancel_packet()).await?;
+        self.printing.store(false, Ordering::Release);
+        let deadline = time::Instant::now() + self.ack_timeout;
+        loop {
+            let req = build_control_packet(Command::GetStatus, &[0x00]);
+            self.transport.write_control(&req).await?;
+            let remaining = deadline.saturating_duration_since(time::Instant::now());
+            let notif = self
+                .wait_for_notification(Command::GetStatus, remaining)
+                .await
+                .map_err(|e| e.timed_out_after(self.ack_timeout))?;
+            if parse_printer_status(&notif.payload).state == PrinterState::Standby {
+                return Ok(());
+            }
+            if time::Instant::now() + READY_POLL_INTERVAL > deadline {
+                return Err(PrinterError::Timeout(self.ack_timeout));
+            }
+            time::sleep(READY_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Reads notifications until one with `command_id` arrives, skipping
+    /// unrelated frames such as stray status reports.
+    ///
+    /// Returns the matching notification, or Err(PrinterError::Timeout) if none
+    /// arrives within `timeout`
+    async fn wait_for_notification(
+        &self,
+        command: Command,
+        timeout: Duration,
+    ) -> Result<Notification, PrinterError> {
+        let deadline = time::Instant::now() + timeout;
         loop {
             let remaining = deadline
                 .checked_duration_since(time::Instant::now())
                 .unwrap_or_else(|| Duration::from_secs(0));
             if remaining.is_zero() {
-                return Err("timed out waiting for print complete".into());
+                return Err(PrinterError::Timeout(timeout));
             }
-            let raw = self.transport.read_notification(remaining).await?;
-            let notif = parse_notification(&raw).map_err(|e| e.to_string())?;
-            if notif.command_id == 0xAA {
+            let raw = self
+                .read_frame(remaining)
+                .await
+                .map_err(|e| e.timed_out_after(timeout))?;
+            let notif = parse_notification(&raw)?;
+            if notif.command_id == u8::from(command) {
+                return Ok(notif);
+            }
+        }
+    }
+
+    /// Polls status every cooldown_delay until the head temperature is at or
+    /// below `limit`, giving up with NotReady(Overheated) after print_timeout.
+    async fn wait_for_cooldown(&self, limit: u8) -> Result<(), PrinterError> {
+        let deadline = time::Instant::now() + self.print_timeout;
+        loop {
+            let status = self.get_status(self.ack_timeout).await?;
+            if status.temperature.is_none_or(|t| t <= limit) {
                 return Ok(());
             }
+            if time::Instant::now() + self.cooldown_delay > deadline {
+                return Err(PrinterError::NotReady(PrinterErrorCode::Overheated));
+            }
+            time::sleep(self.cooldown_delay).await;
+        }
+    }
+
+    /// Send packed row data for a job started with begin_print, split into
+    /// chunk_size writes. Pack rows with pack_pixels_for_mode first.
+    ///
+    /// - `data`: packed rows
+    pub async fn send_data(&self, data: &[u8]) -> Result<(), PrinterError> {
+        for chunk in chunk_data_iter(data, self.chunk_size) {
+            self.write_chunk(chunk).await?;
+        }
+        Ok(())
+    }
+
+    /// State and progress of the print in flight, safe to call from another
+    /// task while a job runs.
+    ///
+    /// The MXW01 answers every query on the one notification characteristic
+    /// the job is waiting on, so a 0xA1 status query mid-print could swallow
+    /// the job's 0xAA completion. This therefore sends nothing: it reports the
+    /// 0xBB progress notifications the printer emits on its own once all data
+    /// is sent (the same ones passed to the progress callback).
+    ///
+    /// Returns the current LiveStatus
+    pub fn get_live_status(&self) -> LiveStatus {
+        LiveStatus {
+            printing: self.printing.load(Ordering::Acquire),
+            lines_total: self.job_lines.load(Ordering::Relaxed),
+            lines_printed: self.lines_printed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Finish a job started with begin_print: sends 0xAD and waits up to the
+    /// print timeout for the 0xAA print complete notification.
+    pub async fn end_print(&self) -> Result<(), PrinterError> {
+        let result = self.finish_print(self.print_timeout).await;
+        self.printing.store(false, Ordering::Release);
+        result
+    }
+
+    /// Writes one data chunk, acknowledged and retried in reliable mode, then
+    /// waits inter_chunk_delay. A failed write ends the job, so printing is
+    /// cleared before the error is returned.
+    async fn write_chunk(&self, chunk: &[u8]) -> Result<(), PrinterError> {
+        if let Err(err) = self.send_chunk(chunk).await {
+            self.printing.store(false, Ordering::Release);
+            return Err(err);
+        }
+        if let Some(delay) = self.inter_chunk_delay {
+            time::sleep(delay).await;
+        }
+        Ok(())
+    }
+
+    async fn send_chunk(&self, chunk: &[u8]) -> Result<(), PrinterError> {
+        if !self.reliable {
+            return self.transport.write_data(chunk).await;
+        }
+        let mut attempt = 0;
+        loop {
+            match self.transport.write_data_acked(chunk).await {
+                Err(_) if attempt < RELIABLE_RETRIES => {
+                    attempt += 1;
+                    time::sleep(DEFAULT_RETRY_DELAY).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn finish_print(&self, timeout: Duration) -> Result<(), PrinterError> {
+        self.transport
+            .write_control(&build_data_end_packet())
+            .await?;
+        let deadline = time::Instant::now() + timeout;
+        loop {
+            let remaining = deadline
+                .checked_duration_since(time::Instant::now())
+                .unwrap_or_else(|| Duration::from_secs(0));
+            if remaining.is_zero() {
+                return Err(PrinterError::Timeout(timeout));
+            }
+            let raw = self
+                .read_frame(remaining)
+                .await
+                .map_err(|e| e.timed_out_after(timeout))?;
+            let notif = parse_notification(&raw)?;
+            match Command::try_from(notif.command_id) {
+                Ok(Command::PrintComplete) => {
+                    let total = self.job_lines.load(Ordering::Relaxed);
+                    self.lines_printed.store(total, Ordering::Relaxed);
+                    return Ok(());
+                }
+                Ok(Command::PrintProgress) => {
+                    if let Some(lines) = parse_print_progress(&notif.payload) {
+                        self.lines_printed.store(lines, Ordering::Relaxed);
+                        if let Some(cb) = &self.on_progress {
+                            cb(lines);
+                        }
+                    }
+                }
+                _ => {}
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::SLEEP_NEVER;
+    use crate::testutil::MockTransportAsync;
+
+    fn printer() -> (CatPrinterAsync, MockTransportAsync) {
+        let mock = MockTransportAsync::new();
+        (CatPrinterAsync::new(Box::new(mock.clone())), mock)
+    }
+
+    #[tokio::test]
+    async fn set_density_is_used_by_later_jobs() {
+        let (p, mock) = printer();
+        p.set_density(0xF0).await.unwrap();
+        mock.lock().push_print_success();
+        p.print_image(&[0; 8], 8, 1, MODE_1BPP, None).await.unwrap();
+        let writes = mock.lock().control_writes.clone();
+        assert_eq!(writes[0], build_density_packet(MAX_DENSITY));
+        assert_eq!(
+            writes[1],
+            [
+                0x22,
+                0x21,
+                0xA2,
+                0x00,
+                0x01,
+                0x00,
+                0x7F,
+                crate::wire::crc8(&[0x7F]),
+                0xFF
+            ]
+        );
+        assert_eq!(writes[2][2], u8::from(Command::PrintStart));
+    }
+
+    #[tokio::test]
+    async fn streaming_sends_the_same_chunks_as_print_image() {
+        let (width, height) = (100, 37);
+        let pixels: Vec<u8> = (0..width * height)
+            .map(|i| if (i * 7 + i / width) % 5 < 2 { 0 } else { 255 })
+            .collect();
+        let (whole, whole_mock) = printer();
+        let whole = whole.with_inter_chunk_delay(None);
+        whole_mock.lock().push_print_success();
+        whole
+            .print_image(&pixels, width, height, MODE_1BPP, Some(50))
+            .await
+            .unwrap();
+        let (streamed, streamed_mock) = printer();
+        let streamed = streamed.with_inter_chunk_delay(None);
+        streamed_mock.lock().push_print_success();
+        streamed
+            .print_image_streaming(&pixels, width, height, Some(50))
+            .await
+            .unwrap();
+        let whole_writes = whole_mock.lock().data_writes.clone();
+        assert_eq!(whole_writes, streamed_mock.lock().data_writes);
+    }
+
+    #[tokio::test]
+    async fn get_device_info_skips_stray_notifications() {
+        let (p, mock) = printer();
+        mock.lock().push_response(Command::GetStatus, &[0; 12]);
+        mock.lock()
+            .push_response(Command::GetDeviceInfo, b"V1.2.3\0HW2\0SN42");
+        let info = p.get_device_info(Duration::from_secs(1)).await.unwrap();
+        assert_eq!(info.firmware_version, "V1.2.3");
+        assert_eq!(info.hardware_version.as_deref(), Some("HW2"));
+        assert_eq!(info.serial.as_deref(), Some("SN42"));
+    }
+
+    #[tokio::test]
+    async fn sleep_timeout_round_trip() {
+        let (p, mock) = printer();
+        mock.lock()
+            .push_response(Command::GetSleepTimeout, &[0x0A, 0x00]);
+        assert_eq!(
+            p.get_sleep_timeout(Duration::from_secs(1)).await.unwrap(),
+            10
+        );
+
+        mock.lock()
+            .push_response(Command::SetSleepTimeout, &[0x00, 0x00]);
+        p.set_sleep_timeout(SLEEP_NEVER, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(
+            mock.lock().control_writes.last().unwrap(),
+            &build_set_sleep_timeout_packet(SLEEP_NEVER)
+        );
+
+        // Firmware that ignores SLEEP_NEVER answers with its own value
+        mock.lock()
+            .push_response(Command::SetSleepTimeout, &[0x1E, 0x00]);
+        assert!(matches!(
+            p.set_sleep_timeout(SLEEP_NEVER, Duration::from_secs(1))
+                .await,
+            Err(PrinterError::Rejected)
+        ));
+    }
+
+    #[tokio::test]
+    async fn failed_data_write_ends_the_job() {
+        let (p, mock) = printer();
+        let p = p.with_inter_chunk_delay(None);
+        let pixels = vec![0u8; PRINTER_WIDTH * 4];
+        mock.lock().fail_data = true;
+
+        mock.lock().push_response(Command::PrintStart, &[0x00]);
+        assert!(p
+            .print_image(&pixels, PRINTER_WIDTH, 4, MODE_1BPP, None)
+            .await
+            .is_err());
+        assert!(!p.get_live_status().printing);
+
+        mock.lock().push_response(Command::PrintStart, &[0x00]);
+        assert!(p
+            .print_image_streaming(&pixels, PRINTER_WIDTH, 4, None)
+            .await
+            .is_err());
+        assert!(!p.get_live_status().printing);
+
+        mock.lock().push_response(Command::PrintStart, &[0x00]);
+        p.begin_print(4, MODE_1BPP).await.unwrap();
+        assert!(p.get_live_status().printing);
+        assert!(p.send_data(&[0; 48 * 4]).await.is_err());
+        assert!(!p.get_live_status().printing);
+    }
+
+    #[tokio::test]
+    async fn blank_text_is_refused_without_printing() {
+        let (p, mock) = printer();
+        assert!(matches!(
+            p.print_text("", "").await,
+            Err(PrinterError::Image(_))
+        ));
+        assert!(mock.lock().control_writes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn get_battery_skips_stray_notifications() {
+        let (p, mock) = printer();
+        mock.lock().push_response(Command::GetStatus, &[0; 12]);
+        mock.lock().push_response(Command::GetBattery, &[64]);
+        assert_eq!(p.get_battery(Duration::from_secs(1)).await.unwrap(), 64);
+        mock.lock().push_response(Command::GetBattery, &[]);
+        assert!(matches!(
+            p.get_battery(Duration::from_secs(1)).await,
+            Err(PrinterError::Protocol(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn loaded_images_match_the_sync_pipeline() {
+        let img =
+            image::GrayImage::from_fn(60, 400, |x, y| image::Luma([((x * 4 + y) % 256) as u8]));
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        for (mode, dithering) in [
+            (ResizeMode::CropCenter, ImageDithering::FloydSteinberg),
+            (ResizeMode::FitWidth, ImageDithering::None),
+        ] {
+            let mut sync =
+                crate::printer::CatPrinter::builder(crate::testutil::MockTransport::new())
+                    .resize_mode(mode)
+                    .max_height(120)
+                    .build();
+            sync.transport.push_print_success();
+            sync.print_image_from_bytes(&png, dithering).unwrap();
+
+            let (p, mock) = printer();
+            let p = p
+                .with_inter_chunk_delay(None)
+                .with_resize_mode(mode)
+                .with_max_height(120);
+            mock.lock().push_print_success();
+            p.print_image_from_bytes(&png, dithering).await.unwrap();
+
+            assert_eq!(mock.lock().data_writes, sync.transport.data_writes);
+            assert_eq!(mock.lock().data_bytes().len(), 120 * 48);
+        }
+    }
+
+    #[tokio::test]
+    async fn cancel_print_sends_0xac_and_polls_until_standby() {
+        let (p, mock) = printer();
+        let status = |state| {
+            let mut payload = [0; 13];
+            payload[6] = state;
+            payload
+        };
+        mock.lock().push_response(Command::GetStatus, &status(1));
+        mock.lock().push_response(Command::GetStatus, &status(0));
+        mock.lock().push_response(Command::GetStatus, &status(1));
+        p.cancel_print().await.unwrap();
+
+        let req = build_control_packet(Command::GetStatus, &[0x00]);
+        let inner = mock.lock();
+        assert_eq!(
+            inner.control_writes,
+            [build_cancel_packet(), req.clone(), req]
+        );
+        assert_eq!(inner.control_writes[0][2], 0xAC);
+        assert_eq!(inner.notifications.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn cancel_print_times_out_after_ack_timeout() {
+        let (p, _mock) = printer();
+        let p = p.with_ack_timeout(Duration::from_millis(50));
+        let err = p.cancel_print().await.unwrap_err();
+        assert!(
+            matches!(err, PrinterError::Timeout(t) if t == Duration::from_millis(50)),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn move_paper_waits_for_its_own_reply() {
+        let (p, mock) = printer();
+        mock.lock().push_response(Command::GetStatus, &[0; 13]);
+        mock.lock()
+            .push_response(Command::PrintProgress, &[0x10, 0x00]);
+        mock.lock().push_response(Command::FeedPaper, &[0x01]);
+        assert!(matches!(p.feed_paper(40).await, Err(PrinterError::Busy)));
+        assert!(mock.lock().notifications.is_empty());
+
+        mock.lock().push_response(Command::GetStatus, &[0; 13]);
+        mock.lock().push_response(Command::RetractPaper, &[0x00]);
+        p.retract_paper(40).await.unwrap();
+        assert!(mock.lock().notifications.is_empty());
+    }
+
+    #[tokio::test]
+    async fn timeouts_report_the_whole_wait() {
+        let (p, mock) = printer();
+        let p = p.with_print_timeout(Duration::from_millis(30));
+        mock.lock().push_response(Command::PrintStart, &[0x00]);
+        mock.lock()
+            .push_response(Command::PrintProgress, &[0x01, 0x00]);
+        let err = p
+            .print_image(&[0; 8], 8, 1, MODE_1BPP, None)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, PrinterError::Timeout(t) if t == Duration::from_millis(30)),
+            "{err:?}"
+        );
+
+        let wait = Duration::from_millis(20);
+        mock.lock().push_response(Command::GetStatus, &[0; 13]);
+        let err = p
+            .wait_for_notification(Command::PrintStart, wait)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, PrinterError::Timeout(t) if t == wait),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn chunk_size_fills_the_mtu() {
+        assert_eq!(chunk_size_for_mtu(MIN_ATT_MTU), 20);
+        assert_eq!(chunk_size_for_mtu(247), 244);
+        assert_eq!(chunk_size_for_mtu(517), 514);
+        // Below the spec minimum counts as the minimum
+        assert_eq!(chunk_size_for_mtu(0), 20);
+    }
+
+    #[tokio::test]
+    async fn auto_chunk_size_follows_the_transport_mtu() {
+        // No MTU reported, as with btleplug: the default stays
+        let (mut p, mock) = printer();
+        assert_eq!(p.auto_chunk_size().await, DEFAULT_CHUNK_SIZE);
+
+        mock.lock().mtu = Some(247);
+        assert_eq!(p.auto_chunk_size().await, 244);
+        mock.lock().push_print_success();
+        p.print_image(&[0; 384 * 8], 384, 8, MODE_1BPP, None)
+            .await
+            .unwrap();
+        let sizes: Vec<usize> = mock.lock().data_writes.iter().map(Vec::len).collect();
+        assert_eq!(sizes, [244, 140]);
+
+        // An explicit size still wins afterwards
+        let p = p.with_chunk_size(100);
+        assert_eq!(p.chunk_size, 100);
+    }
+
+    #[tokio::test]
+    async fn notifications_join_split_packets() {
+        let (p, mock) = printer();
+        let status = build_control_packet(Command::GetStatus, &[0x5A; 13]);
+        let battery = build_control_packet(Command::GetBattery, &[80]);
+        mock.lock().push_notification(status[..5].to_vec());
+        mock.lock().push_notification(status[5..12].to_vec());
+        // The status tail and the whole battery reply in one event
+        mock.lock()
+            .push_notification([&status[12..], &battery[..]].concat());
+        mock.lock().push_notification(vec![0x00, 0x01]);
+
+        let notes: Vec<Notification> = p.notifications().await.unwrap().collect().await;
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].command_id, u8::from(Command::GetStatus));
+        assert_eq!(notes[0].payload, [0x5A; 13]);
+        assert_eq!(notes[0].crc, Some(crate::protocol::crc8(&[0x5A; 13])));
+        assert_eq!(notes[1].command_id, u8::from(Command::GetBattery));
+        assert_eq!(notes[1].payload, [80]);
+    }
+}
@@ -1,72 +1,652 @@
-use image::{GrayImage, Luma};
+use image::{DynamicImage, GrayImage, Luma, Rgb, RgbImage};
 use imageproc::drawing::draw_filled_circle_mut;
 
+/// 64x64 blue-noise threshold mask, row-major, each value 0..=255 used 16 times.
+/// Generated for this crate with Ulichney's void-and-cluster method (gaussian
+/// sigma 1.5, toroidal so it tiles seamlessly); covered by the crate's MIT license.
+static BLUE_NOISE_64: &[u8; 4096] = include_bytes!("../blue_noise_64.bin");
+const BLUE_NOISE_SIZE: u32 = 64;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ImageDithering {
-    Threshold,
+    /// No dithering: threshold at the midpoint only. Use for sources that are
+    /// already black and white (line art, logos, pre-rendered QR codes) so
+    /// they print pixel-perfect instead of picking up dither noise.
+    None,
+    /// Pixels brighter than `cutoff` become white, the rest black.
+    Threshold {
+        cutoff: u8,
+    },
     FloydSteinberg,
     Atkinson,
     Halftone,
+    /// Ordered dithering with the 4x4 Bayer matrix.
     Bayer,
+    /// Ordered dithering with the 8x8 Bayer matrix (finer pattern, 65 gray levels).
+    Bayer8,
+    /// Ordered dithering with an 8x8 clustered-dot (newspaper-style) matrix.
+    ClusteredDot,
+    Stucki,
+    Jarvis,
+    Sierra,
+    SierraLite,
+    Burkes,
+    /// Threshold against a tiled blue-noise mask: no crosshatch, organic grain.
+    BlueNoise,
+    /// Black outlines on white instead of tones, e.g. for coloring pages: pixels
+    /// whose Sobel gradient magnitude (0..=1442) exceeds `threshold` become
+    /// black. Lower values pick up fainter edges and more texture.
+    Outline {
+        threshold: u16,
+    },
+}
+
+impl ImageDithering {
+    /// Default cutoff used by `threshold_default`.
+    pub const DEFAULT_THRESHOLD: u8 = 127;
+
+    /// Default edge threshold used by `outline_default`.
+    pub const DEFAULT_OUTLINE_THRESHOLD: u16 = 160;
+
+    /// Threshold dithering with the default cutoff (127).
+    pub fn threshold_default() -> Self {
+        ImageDithering::Threshold {
+            cutoff: Self::DEFAULT_THRESHOLD,
+        }
+    }
+
+    /// Outline mode with the default edge threshold (160).
+    pub fn outline_default() -> Self {
+        ImageDithering::Outline {
+            threshold: Self::DEFAULT_OUTLINE_THRESHOLD,
+        }
+    }
+}
+
+/// Color channel picked by GrayscaleMode::Channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChannel {
+    Red,
+    Green,
+    Blue,
+}
+
+/// How color images collapse to gray before dithering. The choice decides
+/// which colored areas end up black in 1bpp output: pure red becomes gray 54
+/// under Luma709 and prints mostly black, but drops out entirely under
+/// Channel(Red).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GrayscaleMode {
+    /// Rec. 709 luma weights (0.2126 R + 0.7152 G + 0.0722 B); this is what
+    /// image's to_luma8 computes, so the default keeps the output of
+    /// printers that converted with to_luma8 before GrayscaleMode existed.
+    #[default]
+    Luma709,
+    /// Rec. 601 luma weights (0.299 R + 0.587 G + 0.114 B), common in video
+    /// and older imaging tools; reds and blues come out darker than Luma709.
+    Luma601,
+    /// Plain average of R, G and B.
+    Average,
+    /// A single channel's value; colors without that channel print black.
+    Channel(ColorChannel),
+    /// HSL lightness, (max(R, G, B) + min(R, G, B)) / 2.
+    Desaturate,
+}
+
+/// Converts a decoded image to grayscale. Alpha is ignored.
+///
+/// - `img`: decoded source image
+/// - `mode`: channel weighting
+///
+/// Returns the grayscale image
+pub fn to_grayscale(img: &DynamicImage, mode: GrayscaleMode) -> GrayImage {
+    if mode == GrayscaleMode::Luma709 {
+        return img.to_luma8();
+    }
+    let rgb = img.to_rgb8();
+    let (w, h) = rgb.dimensions();
+    let raw = rgb
+        .pixels()
+        .map(|p| {
+            let [r, g, b] = p.0.map(u32::from);
+            (match mode {
+                GrayscaleMode::Luma709 => (2126 * r + 7152 * g + 722 * b + 5000) / 10000,
+                GrayscaleMode::Luma601 => (299 * r + 587 * g + 114 * b + 500) / 1000,
+                GrayscaleMode::Average => (r + g + b + 1) / 3,
+                GrayscaleMode::Channel(ColorChannel::Red) => r,
+                GrayscaleMode::Channel(ColorChannel::Green) => g,
+                GrayscaleMode::Channel(ColorChannel::Blue) => b,
+                GrayscaleMode::Desaturate => (r.max(g).max(b) + r.min(g).min(b)).div_ceil(2),
+            }) as u8
+        })
+        .collect();
+    GrayImage::from_raw(w, h, raw).expect("one gray byte per RGB pixel")
+}
+
+/// Background transparent pixels are blended onto by default: white, i.e. unprinted.
+pub const DEFAULT_ALPHA_BACKGROUND: Rgb<u8> = Rgb([255, 255, 255]);
+
+/// Composites an image with transparency over a solid background, so
+/// transparent areas take the background color instead of whatever color the
+/// fully transparent pixels happen to store (often black).
+///
+/// - `img`: decoded source image; returned unchanged if it has no alpha channel
+/// - `background`: color behind the image, e.g. DEFAULT_ALPHA_BACKGROUND
+///
+/// Returns the opaque image
+pub fn flatten_alpha(img: DynamicImage, background: Rgb<u8>) -> DynamicImage {
+    if !img.color().has_alpha() {
+        return img;
+    }
+    let rgba = img.to_rgba8();
+    let (w, h) = rgba.dimensions();
+    let out = RgbImage::from_fn(w, h, |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let a = a as u32;
+        let blend = |c: u8, bg: u8| ((c as u32 * a + bg as u32 * (255 - a) + 127) / 255) as u8;
+        Rgb([
+            blend(r, background[0]),
+            blend(g, background[1]),
+            blend(b, background[2]),
+        ])
+    });
+    DynamicImage::ImageRgb8(out)
+}
+
+/// Adjusts brightness and contrast of a grayscale image, computing
+/// `(p - 128) * contrast + 128 + brightness` clamped to 0..=255 for each pixel.
+/// Lightening slightly before dithering helps thin lines survive 1bpp output.
+///
+/// - `img`: mutable reference to GrayImage
+/// - `brightness`: added to every pixel (0 = no change, positive = lighter)
+/// - `contrast`: multiplier around mid-gray (1.0 = no change)
+pub fn apply_brightness_contrast(img: &mut GrayImage, brightness: i16, contrast: f32) {
+    if brightness == 0 && contrast == 1.0 {
+        return;
+    }
+    let mut lut = [0u8; 256];
+    for (i, v) in lut.iter_mut().enumerate() {
+        let adjusted = (i as f32 - 128.0) * contrast + 128.0 + brightness as f32;
+        *v = adjusted.round().clamp(0.0, 255.0) as u8;
+    }
+    for p in img.pixels_mut() {
+        p[0] = lut[p[0] as usize];
+    }
+}
+
+/// Applies gamma correction to a grayscale image through a 256-entry lookup table.
+/// Run this before dithering: thermal paper darkens midtones more than a
+/// linear grayscale conversion expects, so photos come out muddy without it.
+///
+/// - `img`: mutable reference to GrayImage
+/// - `gamma`: 1.0 leaves the image unchanged, values above 1.0 lighten
+///   midtones (1.5-2.2 works well for photos), values below 1.0 darken them.
+///   Non-positive or non-finite values are ignored.
+pub fn apply_gamma(img: &mut GrayImage, gamma: f32) {
+    if gamma == 1.0 || gamma <= 0.0 || !gamma.is_finite() {
+        return;
+    }
+    let mut lut = [0u8; 256];
+    for (i, v) in lut.iter_mut().enumerate() {
+        *v = (255.0 * (i as f32 / 255.0).powf(1.0 / gamma)).round() as u8;
+    }
+    for p in img.pixels_mut() {
+        p[0] = lut[p[0] as usize];
+    }
+}
+
+/// Applies the selected dithering algorithm to a grayscale image.
+///
+/// - `img`: mutable reference to GrayImage (replaced for algorithms that resize, like Halftone)
+/// - `dithering`: algorithm to apply
+pub fn apply_dithering(img: &mut GrayImage, dithering: ImageDithering) {
+    match dithering {
+        ImageDithering::None => {
+            threshold_dither(img, ImageDithering::DEFAULT_THRESHOLD);
+        }
+        ImageDithering::FloydSteinberg => {
+            floyd_steinberg_dither(img);
+        }
+        ImageDithering::Atkinson => {
+            atkinson_dither(img);
+        }
+        ImageDithering::Bayer => {
+            bayer_dither(img);
+        }
+        ImageDithering::Bayer8 => {
+            ordered_dither(img, &BAYER_8);
+        }
+        ImageDithering::ClusteredDot => {
+            ordered_dither(img, &CLUSTERED_8);
+        }
+        ImageDithering::Halftone => {
+            *img = halftone_dither(img);
+        }
+        ImageDithering::Threshold { cutoff } => {
+            threshold_dither(img, cutoff);
+        }
+        ImageDithering::Stucki => {
+            stucki_dither(img);
+        }
+        ImageDithering::Jarvis => {
+            jarvis_dither(img);
+        }
+        ImageDithering::Sierra => {
+            sierra_dither(img);
+        }
+        ImageDithering::SierraLite => {
+            sierra_lite_dither(img);
+        }
+        ImageDithering::Burkes => {
+            burkes_dither(img);
+        }
+        ImageDithering::BlueNoise => {
+            blue_noise_dither(img);
+        }
+        ImageDithering::Outline { threshold } => {
+            *img = outline_dither(img, threshold);
+        }
+    }
+}
+
+/// Default diffusion strength: the full error is propagated.
+pub const DEFAULT_DIFFUSION_STRENGTH: f32 = 1.0;
+
+/// Applies the selected dithering algorithm with scaled error diffusion.
+///
+/// Error diffusion variants (FloydSteinberg, Atkinson, Stucki, Jarvis, Sierra,
+/// SierraLite, Burkes) push only `strength` times the usual error to the
+/// neighbors, trading gradients for less grain; 0.0 is a plain threshold at
+/// 127. Other algorithms ignore `strength`.
+///
+/// - `img`: mutable reference to GrayImage
+/// - `dithering`: algorithm to apply
+/// - `strength`: share of the error to propagate, clamped to 0.0..=1.0
+///   (non-finite counts as 1.0)
+pub fn apply_dithering_strength(img: &mut GrayImage, dithering: ImageDithering, strength: f32) {
+    let strength = if strength.is_finite() {
+        strength.clamp(0.0, 1.0)
+    } else {
+        DEFAULT_DIFFUSION_STRENGTH
+    };
+    match diffusion_kernel(dithering) {
+        Some(kernel) => diffuse_error(img, kernel, strength),
+        None => apply_dithering(img, dithering),
+    }
+}
+
+/// Applies a plain threshold to a grayscale image buffer in-place.
+///
+/// - `img`: mutable reference to GrayImage
+/// - `cutoff`: pixels above this value become white, the rest black
+pub fn threshold_dither(img: &mut GrayImage, cutoff: u8) {
+    for_each_row(img, |_, row| {
+        for p in row {
+            *p = if *p > cutoff { 255 } else { 0 };
+        }
+    });
+}
+
+/// Applies Floyd-Steinberg dithering to a grayscale image buffer in-place.
+///
+/// Error is spread 7/16 right, 3/16 bottom-left, 5/16 below and 1/16 bottom-right.
+///
+/// - `img`: mutable reference to GrayImage
+pub fn floyd_steinberg_dither(img: &mut GrayImage) {
+    diffuse_error(img, &FLOYD_STEINBERG, DEFAULT_DIFFUSION_STRENGTH);
 }
 
 /// Applies Atkinson dithering to a grayscale image buffer in-place.
 ///
 /// - `img`: mutable reference to GrayImage
 pub fn atkinson_dither(img: &mut GrayImage) {
-    let (width, height) = img.dimensions();
+    diffuse_error(img, &ATKINSON, DEFAULT_DIFFUSION_STRENGTH);
+}
+
+/// Applies Stucki dithering to a grayscale image buffer in-place.
+///
+/// Spreads error over 12 neighbors (divisor 42), keeping more detail in dark areas.
+///
+/// - `img`: mutable reference to GrayImage
+pub fn stucki_dither(img: &mut GrayImage) {
+    diffuse_error(img, &STUCKI, DEFAULT_DIFFUSION_STRENGTH);
+}
+
+/// Applies Jarvis-Judice-Ninke dithering to a grayscale image buffer in-place.
+///
+/// Spreads error over 12 neighbors (divisor 48) for smooth gradients.
+///
+/// - `img`: mutable reference to GrayImage
+pub fn jarvis_dither(img: &mut GrayImage) {
+    diffuse_error(img, &JARVIS, DEFAULT_DIFFUSION_STRENGTH);
+}
+
+/// Applies Sierra (three-row) dithering to a grayscale image buffer in-place.
+///
+/// Spreads error over 10 neighbors (divisor 32), close to Jarvis but cheaper.
+///
+/// - `img`: mutable reference to GrayImage
+pub fn sierra_dither(img: &mut GrayImage) {
+    diffuse_error(img, &SIERRA, DEFAULT_DIFFUSION_STRENGTH);
+}
+
+/// Applies Sierra Lite dithering to a grayscale image buffer in-place.
+///
+/// Error is spread 2/4 right, 1/4 bottom-left and 1/4 below.
+///
+/// - `img`: mutable reference to GrayImage
+pub fn sierra_lite_dither(img: &mut GrayImage) {
+    diffuse_error(img, &SIERRA_LITE, DEFAULT_DIFFUSION_STRENGTH);
+}
+
+/// Applies Burkes dithering to a grayscale image buffer in-place.
+///
+/// Spreads error over 7 neighbors in two rows (divisor 32).
+///
+/// - `img`: mutable reference to GrayImage
+pub fn burkes_dither(img: &mut GrayImage) {
+    diffuse_error(img, &BURKES, DEFAULT_DIFFUSION_STRENGTH);
+}
+
+/// An error diffusion kernel: (dx, dy, weight) neighbors and the divisor
+/// the weights are scaled by.
+type DiffusionKernel = (&'static [(i32, i32, u8)], f32);
+
+const FLOYD_STEINBERG: DiffusionKernel = (&[(1, 0, 7), (-1, 1, 3), (0, 1, 5), (1, 1, 1)], 16.0);
+// Atkinson only spreads 6/8 of the error on purpose, keeping highlights crisp
+const ATKINSON: DiffusionKernel = (
+    &[
+        (1, 0, 1),
+        (2, 0, 1),
+        (-1, 1, 1),
+        (0, 1, 1),
+        (1, 1, 1),
+        (0, 2, 1),
+    ],
+    8.0,
+);
+const STUCKI: DiffusionKernel = (
+    &[
+        (1, 0, 8),
+        (2, 0, 4),
+        (-2, 1, 2),
+        (-1, 1, 4),
+        (0, 1, 8),
+        (1, 1, 4),
+        (2, 1, 2),
+        (-2, 2, 1),
+        (-1, 2, 2),
+        (0, 2, 4),
+        (1, 2, 2),
+        (2, 2, 1),
+    ],
+    42.0,
+);
+const JARVIS: DiffusionKernel = (
+    &[
+        (1, 0, 7),
+        (2, 0, 5),
+        (-2, 1, 3),
+        (-1, 1, 5),
+        (0, 1, 7),
+        (1, 1, 5),
+        (2, 1, 3),
+        (-2, 2, 1),
+        (-1, 2, 3),
+        (0, 2, 5),
+        (1, 2, 3),
+        (2, 2, 1),
+    ],
+    48.0,
+);
+const SIERRA: DiffusionKernel = (
+    &[
+        (1, 0, 5),
+        (2, 0, 3),
+        (-2, 1, 2),
+        (-1, 1, 4),
+        (0, 1, 5),
+        (1, 1, 4),
+        (2, 1, 2),
+        (-1, 2, 2),
+        (0, 2, 3),
+        (1, 2, 2),
+    ],
+    32.0,
+);
+const SIERRA_LITE: DiffusionKernel = (&[(1, 0, 2), (-1, 1, 1), (0, 1, 1)], 4.0);
+const BURKES: DiffusionKernel = (
+    &[
+        (1, 0, 8),
+        (2, 0, 4),
+        (-2, 1, 2),
+        (-1, 1, 4),
+        (0, 1, 8),
+        (1, 1, 4),
+        (2, 1, 2),
+    ],
+    32.0,
+);
+
+/// Kernel of an error diffusion variant, None for the other algorithms.
+fn diffusion_kernel(dithering: ImageDithering) -> Option<&'static DiffusionKernel> {
+    match dithering {
+        ImageDithering::FloydSteinberg => Some(&FLOYD_STEINBERG),
+        ImageDithering::Atkinson => Some(&ATKINSON),
+        ImageDithering::Stucki => Some(&STUCKI),
+        ImageDithering::Jarvis => Some(&JARVIS),
+        ImageDithering::Sierra => Some(&SIERRA),
+        ImageDithering::SierraLite => Some(&SIERRA_LITE),
+        ImageDithering::Burkes => Some(&BURKES),
+        _ => None,
+    }
+}
+
+/// Thresholds each pixel at 127 and pushes the quantization error to the
+/// neighbors in the kernel, each getting weight/divisor of it times `strength`.
+///
+/// Pixel values are tracked as f32 and never clamped while error builds up,
+/// so apart from what falls off the image edges (and the share Atkinson drops
+/// on purpose) no error is lost to rounding or saturation.
+fn diffuse_error(img: &mut GrayImage, kernel: &DiffusionKernel, strength: f32) {
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    let mut values: Vec<f32> = img.as_raw().iter().map(|&p| p as f32).collect();
     let raw = img.as_mut();
     for y in 0..height {
         for x in 0..width {
-            let idx = (y * width + x) as usize;
-            let old_pixel = raw[idx];
-            let new_pixel = if old_pixel > 127 { 255 } else { 0 };
+            let idx = y * width + x;
+            let new_pixel = if values[idx] > 127.0 { 255u8 } else { 0 };
             raw[idx] = new_pixel;
-            let error = old_pixel as i16 - new_pixel as i16;
-
-            let update_pixel = |x: i32, y: i32, factor: f32, raw: &mut [u8]| {
-                if x >= 0 && x < width as i32 && y >= 0 && y < height as i32 {
-                    let idx = (y as u32 * width + x as u32) as usize;
-                    let new_val = raw[idx] as i16 + (error as f32 * factor) as i16;
-                    raw[idx] = new_val.clamp(0, 255) as u8;
-                }
-            };
+            let error = (values[idx] - new_pixel as f32) * strength;
+            spread_error(&mut values, width, height, (x, y), error, kernel);
+        }
+    }
+}
 
-            update_pixel(x as i32 + 1, y as i32, 1.0 / 8.0, raw);
-            update_pixel(x as i32 + 2, y as i32, 1.0 / 8.0, raw);
-            update_pixel(x as i32 - 1, y as i32 + 1, 1.0 / 8.0, raw);
-            update_pixel(x as i32, y as i32 + 1, 1.0 / 8.0, raw);
-            update_pixel(x as i32 + 1, y as i32 + 1, 1.0 / 8.0, raw);
-            update_pixel(x as i32, y as i32 + 2, 1.0 / 8.0, raw);
+/// Adds weight/divisor of `error` to each in-bounds kernel neighbor of `pos`.
+fn spread_error(
+    values: &mut [f32],
+    width: usize,
+    height: usize,
+    (x, y): (usize, usize),
+    error: f32,
+    &(kernel, divisor): &DiffusionKernel,
+) {
+    for &(dx, dy, weight) in kernel {
+        let (Some(nx), Some(ny)) = (
+            x.checked_add_signed(dx as isize),
+            y.checked_add(dy as usize),
+        ) else {
+            continue;
+        };
+        if nx < width && ny < height {
+            values[ny * width + nx] += error * weight as f32 / divisor;
         }
     }
 }
 
+/// Threshold matrix for ordered dithering.
+///
+/// - `size`: matrix side length
+/// - `values`: `size * size` thresholds, row-major, a permutation of 0..size*size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderedMatrix {
+    pub size: usize,
+    pub values: &'static [u8],
+}
+
+/// 4x4 Bayer matrix.
+pub const BAYER_4: OrderedMatrix = OrderedMatrix {
+    size: 4,
+    values: &[0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5],
+};
+
+/// 8x8 Bayer matrix.
+#[rustfmt::skip]
+pub const BAYER_8: OrderedMatrix = OrderedMatrix {
+    size: 8,
+    values: &[
+         0, 32,  8, 40,  2, 34, 10, 42,
+        48, 16, 56, 24, 50, 18, 58, 26,
+        12, 44,  4, 36, 14, 46,  6, 38,
+        60, 28, 52, 20, 62, 30, 54, 22,
+         3, 35, 11, 43,  1, 33,  9, 41,
+        51, 19, 59, 27, 49, 17, 57, 25,
+        15, 47,  7, 39, 13, 45,  5, 37,
+        63, 31, 55, 23, 61, 29, 53, 21,
+    ],
+};
+
+/// 8x8 clustered-dot matrix: dots grow from the center of each cell like a
+/// printed newspaper screen, which survives thermal bleed better than Bayer.
+#[rustfmt::skip]
+pub const CLUSTERED_8: OrderedMatrix = OrderedMatrix {
+    size: 8,
+    values: &[
+        24, 10, 12, 26, 35, 47, 49, 37,
+         8,  0,  2, 14, 45, 59, 61, 51,
+        22,  6,  4, 16, 43, 57, 63, 53,
+        30, 20, 18, 28, 33, 41, 55, 39,
+        34, 46, 48, 36, 25, 11, 13, 27,
+        44, 58, 60, 50,  9,  1,  3, 15,
+        42, 56, 62, 52, 23,  7,  5, 17,
+        32, 40, 54, 38, 31, 21, 19, 29,
+    ],
+};
+
+/// Applies ordered dithering with the given threshold matrix in-place.
+///
+/// Pixel intensity is scaled to the matrix range (0..size*size) and compared
+/// against the matrix entry at the pixel position, tiled across the image.
+///
+/// - `img`: mutable reference to GrayImage
+/// - `matrix`: threshold matrix, e.g. BAYER_4, BAYER_8 or CLUSTERED_8
+pub fn ordered_dither(img: &mut GrayImage, matrix: &OrderedMatrix) {
+    let n = matrix.size;
+    if n == 0 || matrix.values.len() < n * n {
+        return;
+    }
+    let levels = (n * n) as u32;
+    for_each_row(img, |y, row| {
+        let thresholds = &matrix.values[(y % n) * n..(y % n + 1) * n];
+        for (p, &threshold) in row.iter_mut().zip(thresholds.iter().cycle()) {
+            let scaled_intensity = *p as u32 * levels / 256;
+            *p = if scaled_intensity > threshold as u32 {
+                255
+            } else {
+                0
+            };
+        }
+    });
+}
+
 /// Applies Bayer dithering (4x4 matrix) to a grayscale image buffer in-place.
 ///
 /// - `img`: mutable reference to GrayImage
 pub fn bayer_dither(img: &mut GrayImage) {
-    let (width, height) = img.dimensions();
-    // 4x4 Bayer matrix
-    const BAYER_MATRIX: [[u8; 4]; 4] =
-        [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
-    // The matrix values are in [0, 15]. We scale the pixel intensity to that range.
-    const FACTOR: u8 = 16;
+    ordered_dither(img, &BAYER_4);
+}
 
-    for y in 0..height {
-        for x in 0..width {
-            let pixel = img.get_pixel_mut(x, y);
-            let threshold = BAYER_MATRIX[(y % 4) as usize][(x % 4) as usize];
-            let scaled_intensity = pixel[0] / FACTOR;
-            if scaled_intensity > threshold {
-                pixel[0] = 255; // White
-            } else {
-                pixel[0] = 0; // Black
-            }
+/// Applies blue-noise dithering to a grayscale image buffer in-place.
+///
+/// Each pixel is compared against the embedded 64x64 blue-noise mask, tiled
+/// across the image (the mask wraps seamlessly at its edges), which avoids the
+/// regular crosshatch of Bayer dithering.
+///
+/// - `img`: mutable reference to GrayImage
+pub fn blue_noise_dither(img: &mut GrayImage) {
+    let size = BLUE_NOISE_SIZE as usize;
+    for_each_row(img, |y, row| {
+        let mask = &BLUE_NOISE_64[(y % size) * size..(y % size + 1) * size];
+        for (p, &threshold) in row.iter_mut().zip(mask.iter().cycle()) {
+            // Stretch 0..=255 to 0..=256 so pure white never hits the top mask value
+            let level = *p as u32 * 256 / 255;
+            *p = if level > threshold as u32 { 255 } else { 0 };
         }
+    });
+}
+
+/// Rows per rayon task; smaller bands cost more in scheduling than they save.
+#[cfg(feature = "parallel")]
+const PARALLEL_BAND_ROWS: usize = 64;
+
+/// Runs `f(y, row)` on every row of the image.
+///
+/// With the `parallel` feature rows are spread across the rayon thread pool,
+/// so `f` must only depend on its own row (true for threshold, ordered and
+/// blue-noise dithering, not for error diffusion). Output is identical either way.
+fn for_each_row<F>(img: &mut GrayImage, f: F)
+where
+    F: Fn(usize, &mut [u8]) + Send + Sync,
+{
+    let width = img.width() as usize;
+    if width == 0 {
+        return;
+    }
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        img.par_chunks_mut(width * PARALLEL_BAND_ROWS)
+            .enumerate()
+            .for_each(|(band, rows)| {
+                for (i, row) in rows.chunks_mut(width).enumerate() {
+                    f(band * PARALLEL_BAND_ROWS + i, row);
+                }
+            });
+    }
+    #[cfg(not(feature = "parallel"))]
+    for (y, row) in img.chunks_mut(width).enumerate() {
+        f(y, row);
     }
 }
 
+/// Blur applied before edge detection in outline_dither so photo grain and
+/// JPEG noise don't turn into speckles.
+const OUTLINE_BLUR_SIGMA: f32 = 1.0;
+
+/// Turns a grayscale image into black outlines on white, returning a new image.
+///
+/// The image is slightly blurred (OUTLINE_BLUR_SIGMA), then every pixel whose
+/// Sobel gradient magnitude is above `threshold` becomes black and the rest
+/// white, so flat areas of any shade print white.
+///
+/// - `img`: reference to GrayImage
+/// - `threshold`: gradient magnitude, 0..=1442 (a full black/white step is
+///   about 1020), above which a pixel counts as an edge
+///
+/// Returns a new GrayImage with the outlines
+pub fn outline_dither(img: &GrayImage, threshold: u16) -> GrayImage {
+    let blurred = imageproc::filter::gaussian_blur_f32(img, OUTLINE_BLUR_SIGMA);
+    let gradients = imageproc::gradients::sobel_gradients(&blurred);
+    GrayImage::from_fn(img.width(), img.height(), |x, y| {
+        if gradients.get_pixel(x, y)[0] > threshold {
+            Luma([0])
+        } else {
+            Luma([255])
+        }
+    })
+}
+
 /// Applies halftone dithering to a grayscale image, returning a new image.
 ///
 /// - `img`: reference to GrayImage
@@ -119,3 +699,275 @@ pub fn halftone_dither(img: &GrayImage) -> GrayImage {
     }
     canvas
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(width: u32, height: u32, value: u8) -> GrayImage {
+        GrayImage::from_pixel(width, height, Luma([value]))
+    }
+
+    #[test]
+    fn threshold_cutoff_is_exclusive() {
+        let mut img = GrayImage::from_raw(4, 1, vec![159, 160, 161, 255]).unwrap();
+        apply_dithering(&mut img, ImageDithering::Threshold { cutoff: 160 });
+        assert_eq!(img.as_raw(), &[0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn threshold_default_uses_127() {
+        assert_eq!(
+            ImageDithering::threshold_default(),
+            ImageDithering::Threshold { cutoff: 127 }
+        );
+        let mut img = GrayImage::from_raw(2, 1, vec![127, 128]).unwrap();
+        apply_dithering(&mut img, ImageDithering::threshold_default());
+        assert_eq!(img.as_raw(), &[0, 255]);
+    }
+
+    #[test]
+    fn threshold_extremes_map_every_pixel_one_way() {
+        let mut img = gray(3, 3, 255);
+        threshold_dither(&mut img, 255);
+        assert!(img.pixels().all(|p| p[0] == 0));
+        let mut img = gray(3, 3, 1);
+        threshold_dither(&mut img, 0);
+        assert!(img.pixels().all(|p| p[0] == 255));
+    }
+
+    /// Error one interior pixel hands to its neighbors, as a share of its own.
+    fn spread_share(kernel: &DiffusionKernel) -> f32 {
+        let mut values = vec![0.0; 7 * 7];
+        spread_error(&mut values, 7, 7, (3, 0), 100.0, kernel);
+        values.iter().sum::<f32>() / 100.0
+    }
+
+    #[test]
+    fn floyd_steinberg_weights() {
+        let mut values = vec![0.0; 3 * 2];
+        spread_error(&mut values, 3, 2, (1, 0), 16.0, &FLOYD_STEINBERG);
+        assert_eq!(values, [0.0, 0.0, 7.0, 3.0, 5.0, 1.0]);
+    }
+
+    #[test]
+    fn floyd_steinberg_conserves_error_along_the_first_row() {
+        assert_eq!(spread_share(&FLOYD_STEINBERG), 1.0);
+        // 7/16 goes right along the row and 9/16 down to row 1: nothing lost
+        let mut values = vec![0.0; 4 * 2];
+        spread_error(&mut values, 4, 2, (1, 0), 48.0, &FLOYD_STEINBERG);
+        assert_eq!(values.iter().sum::<f32>(), 48.0);
+        // A pixel on the right edge loses the 7/16 and 1/16 that fall off
+        let mut values = vec![0.0; 4 * 2];
+        spread_error(&mut values, 4, 2, (3, 0), 16.0, &FLOYD_STEINBERG);
+        assert_eq!(values.iter().sum::<f32>(), 8.0);
+    }
+
+    #[test]
+    fn floyd_steinberg_keeps_average_gray() {
+        for level in [1u8, 32, 64, 100, 128, 160, 200, 254] {
+            let mut img = gray(256, 256, level);
+            floyd_steinberg_dither(&mut img);
+            let mean = img.pixels().map(|p| p[0] as f64).sum::<f64>() / (256.0 * 256.0);
+            assert!(
+                (mean - level as f64).abs() < 1.0,
+                "level {} came out as {}",
+                level,
+                mean
+            );
+        }
+    }
+
+    fn black_share(img: &GrayImage) -> f64 {
+        img.pixels().filter(|p| p[0] == 0).count() as f64 / img.len() as f64
+    }
+
+    #[test]
+    fn stucki_and_jarvis_are_black_and_white_with_matching_density() {
+        for dither in [stucki_dither, jarvis_dither] {
+            for level in [32u8, 64, 128, 192] {
+                let mut img = gray(128, 128, level);
+                dither(&mut img);
+                assert!(img.pixels().all(|p| p[0] == 0 || p[0] == 255));
+                let expected = 1.0 - level as f64 / 255.0;
+                assert!(
+                    (black_share(&img) - expected).abs() < 0.01,
+                    "level {} gave {} black",
+                    level,
+                    black_share(&img)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn stucki_and_jarvis_leave_black_and_white_alone() {
+        for dither in [stucki_dither, jarvis_dither] {
+            for level in [0u8, 255] {
+                let mut img = gray(16, 16, level);
+                dither(&mut img);
+                assert_eq!(img, gray(16, 16, level));
+            }
+        }
+    }
+
+    #[test]
+    fn brightness_and_contrast_clamp_at_both_ends() {
+        let ramp = || GrayImage::from_fn(256, 1, |x, _| image::Luma([x as u8]));
+
+        let mut img = ramp();
+        apply_brightness_contrast(&mut img, 300, 1.0);
+        assert!(img.pixels().all(|p| p[0] == 255));
+        let mut img = ramp();
+        apply_brightness_contrast(&mut img, -300, 1.0);
+        assert!(img.pixels().all(|p| p[0] == 0));
+
+        let mut img = ramp();
+        apply_brightness_contrast(&mut img, 0, 10.0);
+        assert_eq!(img.get_pixel(0, 0)[0], 0);
+        assert_eq!(img.get_pixel(255, 0)[0], 255);
+        assert_eq!(img.get_pixel(110, 0)[0], 0);
+        assert_eq!(img.get_pixel(146, 0)[0], 255);
+        assert!(img.as_raw().windows(2).all(|w| w[0] <= w[1]));
+
+        let mut img = ramp();
+        apply_brightness_contrast(&mut img, 0, 0.0);
+        assert!(img.pixels().all(|p| p[0] == 128));
+    }
+
+    #[test]
+    fn sierra_family_weights() {
+        let mut values = vec![0.0; 5 * 3];
+        spread_error(&mut values, 5, 3, (2, 0), 32.0, &SIERRA);
+        #[rustfmt::skip]
+        assert_eq!(values, [
+            0.0, 0.0, 0.0, 5.0, 3.0,
+            2.0, 4.0, 5.0, 4.0, 2.0,
+            0.0, 2.0, 3.0, 2.0, 0.0,
+        ]);
+
+        let mut values = vec![0.0; 3 * 2];
+        spread_error(&mut values, 3, 2, (1, 0), 4.0, &SIERRA_LITE);
+        assert_eq!(values, [0.0, 0.0, 2.0, 1.0, 1.0, 0.0]);
+
+        let mut values = vec![0.0; 5 * 2];
+        spread_error(&mut values, 5, 2, (2, 0), 32.0, &BURKES);
+        #[rustfmt::skip]
+        assert_eq!(values, [
+            0.0, 0.0, 0.0, 8.0, 4.0,
+            2.0, 4.0, 8.0, 4.0, 2.0,
+        ]);
+    }
+
+    #[test]
+    fn kernels_conserve_error_away_from_the_edges() {
+        for kernel in [&STUCKI, &JARVIS, &SIERRA, &SIERRA_LITE, &BURKES] {
+            assert!((spread_share(kernel) - 1.0).abs() < 1e-5);
+        }
+        assert!((spread_share(&ATKINSON) - 0.75).abs() < 1e-5);
+    }
+
+    #[test]
+    fn sierra_family_keeps_density() {
+        for dither in [sierra_dither, sierra_lite_dither, burkes_dither] {
+            for level in [0u8, 32, 64, 128, 192, 255] {
+                let mut img = gray(128, 128, level);
+                dither(&mut img);
+                assert!(img.pixels().all(|p| p[0] == 0 || p[0] == 255));
+                let expected = 1.0 - level as f64 / 255.0;
+                assert!(
+                    (black_share(&img) - expected).abs() < 0.01,
+                    "level {} gave {} black",
+                    level,
+                    black_share(&img)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn solid_red_under_each_grayscale_mode() {
+        let red = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(2, 2, Rgb([255, 0, 0])));
+        let level = |mode| to_grayscale(&red, mode).get_pixel(1, 1)[0];
+        assert_eq!(level(GrayscaleMode::default()), 54);
+        assert_eq!(level(GrayscaleMode::Luma709), 54);
+        assert_eq!(level(GrayscaleMode::Luma601), 76);
+        assert_eq!(level(GrayscaleMode::Average), 85);
+        assert_eq!(level(GrayscaleMode::Channel(ColorChannel::Red)), 255);
+        assert_eq!(level(GrayscaleMode::Channel(ColorChannel::Green)), 0);
+        assert_eq!(level(GrayscaleMode::Channel(ColorChannel::Blue)), 0);
+        assert_eq!(level(GrayscaleMode::Desaturate), 128);
+    }
+
+    #[test]
+    fn flatten_alpha_blends_half_transparent_pixels() {
+        let rgba = |px: [u8; 4]| {
+            DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(1, 1, image::Rgba(px)))
+        };
+        let flat = |px, bg| flatten_alpha(rgba(px), bg).to_rgb8().get_pixel(0, 0).0;
+
+        assert_eq!(
+            flat([0, 0, 0, 128], DEFAULT_ALPHA_BACKGROUND),
+            [127, 127, 127]
+        );
+        assert_eq!(
+            flat([255, 0, 0, 128], DEFAULT_ALPHA_BACKGROUND),
+            [255, 127, 127]
+        );
+        assert_eq!(flat([255, 255, 255, 128], Rgb([0, 0, 0])), [128, 128, 128]);
+        // Fully transparent pixels take the background whatever they store
+        assert_eq!(
+            flat([0, 0, 0, 0], DEFAULT_ALPHA_BACKGROUND),
+            [255, 255, 255]
+        );
+        assert_eq!(
+            flat([10, 20, 30, 255], DEFAULT_ALPHA_BACKGROUND),
+            [10, 20, 30]
+        );
+
+        let gray_alpha = DynamicImage::ImageLumaA8(image::GrayAlphaImage::from_pixel(
+            1,
+            1,
+            image::LumaA([0, 128]),
+        ));
+        let flat = flatten_alpha(gray_alpha, DEFAULT_ALPHA_BACKGROUND);
+        assert!(!flat.color().has_alpha());
+        assert_eq!(flat.to_luma8().get_pixel(0, 0)[0], 127);
+
+        let opaque = DynamicImage::ImageLuma8(gray(2, 2, 40));
+        assert_eq!(
+            flatten_alpha(opaque.clone(), DEFAULT_ALPHA_BACKGROUND),
+            opaque
+        );
+    }
+
+    #[test]
+    fn strength_spans_threshold_to_full_diffusion() {
+        let ramp = GrayImage::from_fn(32, 16, |x, y| Luma([((x * 8 + y * 3) % 256) as u8]));
+        for dithering in [
+            ImageDithering::FloydSteinberg,
+            ImageDithering::Atkinson,
+            ImageDithering::Stucki,
+            ImageDithering::Jarvis,
+            ImageDithering::Sierra,
+            ImageDithering::SierraLite,
+            ImageDithering::Burkes,
+        ] {
+            let mut none = ramp.clone();
+            apply_dithering_strength(&mut none, dithering, 0.0);
+            let mut threshold = ramp.clone();
+            threshold_dither(&mut threshold, 127);
+            assert_eq!(none, threshold, "{dithering:?} at strength 0");
+
+            let mut full = ramp.clone();
+            apply_dithering_strength(&mut full, dithering, 1.0);
+            let mut plain = ramp.clone();
+            apply_dithering(&mut plain, dithering);
+            assert_eq!(full, plain, "{dithering:?} at strength 1");
+            assert_ne!(
+                full, threshold,
+                "{dithering:?} should diffuse at strength 1"
+            );
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::printer::PrinterErrorCode;
+use std::fmt;
+use std::time::Duration;
+
+/// Errors returned by the CatPrinter APIs.
+#[derive(Debug)]
+pub enum PrinterError {
+    /// The printer answered the print request (0xA9) with a rejection and its
+    /// status gave no reason.
+    Rejected,
+    /// The printer reported it is busy (e.g. still printing) and ignored the
+    /// command or print request.
+    Busy,
+    /// The printer status says a job would fail (error state, low battery, overheated).
+    NotReady(PrinterErrorCode),
+    /// The print job was cancelled through its cancellation token.
+    Cancelled,
+    /// A wait ran out: for a printer reply (print request ack, status, battery,
+    /// device info, print complete), for the printer to return to Standby, or
+    /// for an image download. Holds the whole time waited.
+    Timeout(Duration),
+    /// The underlying transport (BLE, mock, ...) failed.
+    Transport(String),
+    /// A notification or buffer could not be parsed.
+    Protocol(String),
+    /// A file could not be read or decoded.
+    Io(std::io::Error),
+    /// An image could not be loaded or processed.
+    Image(String),
+}
+
+impl fmt::Display for PrinterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrinterError::Rejected => write!(f, "printer rejected print request"),
+            PrinterError::Busy => write!(f, "printer is busy"),
+            PrinterError::NotReady(code) => write!(f, "printer not ready: {}", code),
+            PrinterError::Cancelled => write!(f, "print job cancelled"),
+            PrinterError::Timeout(waited) => {
+                write!(f, "timed out after {:?}", waited)
+            }
+            PrinterError::Transport(msg) => write!(f, "transport error: {}", msg),
+            PrinterError::Protocol(msg) => write!(f, "protocol error: {}", msg),
+            PrinterError::Io(e) => write!(f, "io error: {}", e),
+            PrinterError::Image(msg) => write!(f, "image error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for PrinterError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PrinterError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl PrinterError {
+    /// Reports a Timeout as the whole `waited` duration. Loops that give each
+    /// read only the time left before their deadline use this so the error
+    /// names the full wait rather than the last slice of it.
+    pub(crate) fn timed_out_after(self, waited: Duration) -> Self {
+        match self {
+            PrinterError::Timeout(_) => PrinterError::Timeout(waited),
+            e => e,
+        }
+    }
+}
+
+/// Parse errors from the protocol module are `&'static str`.
+impl From<&'static str> for PrinterError {
+    fn from(msg: &'static str) -> Self {
+        PrinterError::Protocol(msg.to_string())
+    }
+}
+
+impl From<std::io::Error> for PrinterError {
+    fn from(e: std::io::Error) -> Self {
+        PrinterError::Io(e)
+    }
+}
+
+impl From<image::ImageError> for PrinterError {
+    fn from(e: image::ImageError) -> Self {
+        match e {
+            image::ImageError::IoError(e) => PrinterError::Io(e),
+            other => PrinterError::Image(other.to_string()),
+        }
+    }
+}
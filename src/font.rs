@@ -1,9 +1,69 @@
+use crate::dithering::{apply_dithering, ImageDithering};
+use crate::protocol::PRINTER_WIDTH;
+use image::GrayImage;
 use once_cell::sync::Lazy;
-use rusttype::{point, Font, PositionedGlyph, Scale};
+use rusttype::{point, Font, Scale, VMetrics};
 
 static FONT_DATA: &[u8] = include_bytes!("../Frisky Puppy.ttf");
-static FONT: Lazy<Font<'static>> =
-    Lazy::new(|| Font::try_from_bytes(FONT_DATA).expect("Failed to load embedded TTF)"));
+static FONT: Lazy<FontHandle> = Lazy::new(|| FontHandle {
+    font: Font::try_from_bytes(FONT_DATA).expect("Failed to load embedded TTF)"),
+});
+
+/// Horizontal placement of each rendered line within the output width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Alignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Reading direction of rendered text.
+///
+/// Rtl draws each line's characters in reverse order and measures alignment
+/// from the right edge, which is enough for pure Arabic or Hebrew text in a
+/// font that has the needed glyphs. It is not a bidi implementation: mixed
+/// runs (e.g. Latin words or digits inside RTL text) come out reversed too,
+/// and there is no shaping, so Arabic letters print in their isolated forms
+/// without joining.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    #[default]
+    Ltr,
+    Rtl,
+}
+
+/// A loaded TTF/OTF font usable for text rasterization.
+#[derive(Clone)]
+pub struct FontHandle {
+    font: Font<'static>,
+}
+
+/// Loads a font from raw TTF/OTF bytes.
+///
+/// - `data`: font file contents
+///
+/// Returns FontHandle, or Err if the bytes are not a valid font
+pub fn load_font_from_bytes(data: Vec<u8>) -> Result<FontHandle, String> {
+    Font::try_from_vec(data)
+        .map(|font| FontHandle { font })
+        .ok_or_else(|| "invalid font data: not a valid TTF/OTF file".to_string())
+}
+
+/// Loads a font from a TTF/OTF file on disk.
+///
+/// - `path`: path to font file
+///
+/// Returns FontHandle, or Err if the file can't be read or parsed
+pub fn load_font_from_path(path: &str) -> Result<FontHandle, String> {
+    let data = std::fs::read(path).map_err(|e| format!("failed to read font {}: {}", path, e))?;
+    load_font_from_bytes(data)
+}
+
+/// Returns the embedded default font.
+pub fn default_font() -> &'static FontHandle {
+    &FONT
+}
 
 /// Rasterizes text into a grayscale pixel buffer for printing.
 ///
@@ -13,22 +73,828 @@ static FONT: Lazy<Font<'static>> =
 ///
 /// Returns a Vec<u8> (row-major, 0=black, 255=white)
 pub fn rasterize_text(text: &str, width: usize, font_size: f32) -> Vec<u8> {
-    let scale = Scale::uniform(font_size);
-    let v_metrics = FONT.v_metrics(scale);
-    let line_height = (v_metrics.ascent - v_metrics.descent + v_metrics.line_gap).ceil() as usize;
+    rasterize_text_with(
+        text,
+        &TextRenderOptions::new().width(width).font_size(font_size),
+    )
+}
+
+/// Every text rendering setting in one place, for rasterize_text_with.
+///
+/// - `fonts`: fonts in priority order, see rasterize_text_with_fallback
+///   (default: empty, the embedded font)
+/// - `font_size`: font size in points (default: 48.0)
+/// - `alignment`: placement of each line (default: Left)
+/// - `line_spacing`: multiplier on the line height, see rasterize_text_spaced
+///   (default: 1.0)
+/// - `tab_stops`: tab positions in pixels, see rasterize_text_tabbed (default: none)
+/// - `width`: output image width in pixels (default: PRINTER_WIDTH)
+/// - `margin`: blank columns on each side, reduced if it leaves no room (default: 0)
+/// - `wrap_width`: wrap lines narrower than the space between the margins; the
+///   text block is then placed per `alignment` (default: None, full width; so is 0)
+/// - `direction`: reading direction; with Rtl, Left and Right alignment mean the
+///   line start and end, so the default Left puts lines at the right edge
+///   (default: Ltr)
+#[derive(Clone)]
+pub struct TextRenderOptions {
+    pub fonts: Vec<FontHandle>,
+    pub font_size: f32,
+    pub alignment: Alignment,
+    pub line_spacing: f32,
+    pub tab_stops: Vec<usize>,
+    pub width: usize,
+    pub margin: usize,
+    pub wrap_width: Option<usize>,
+    pub direction: Direction,
+}
+
+impl Default for TextRenderOptions {
+    fn default() -> Self {
+        Self {
+            fonts: Vec::new(),
+            font_size: 48.0,
+            alignment: Alignment::Left,
+            line_spacing: 1.0,
+            tab_stops: Vec::new(),
+            width: PRINTER_WIDTH,
+            margin: 0,
+            wrap_width: None,
+            direction: Direction::Ltr,
+        }
+    }
+}
+
+impl TextRenderOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render with this font instead of the embedded one.
+    pub fn font(mut self, font: FontHandle) -> Self {
+        self.fonts = vec![font];
+        self
+    }
+
+    /// Render with these fonts, picking per character the first that has it.
+    pub fn fonts(mut self, fonts: Vec<FontHandle>) -> Self {
+        self.fonts = fonts;
+        self
+    }
+
+    pub fn font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    pub fn alignment(mut self, alignment: Alignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    pub fn line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+
+    pub fn tab_stops(mut self, tab_stops: Vec<usize>) -> Self {
+        self.tab_stops = tab_stops;
+        self
+    }
+
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn margin(mut self, margin: usize) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn wrap_width(mut self, wrap_width: usize) -> Self {
+        self.wrap_width = Some(wrap_width);
+        self
+    }
+
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+}
+
+/// Rasterizes text with all settings taken from `options`.
+///
+/// - `text`: The text to render (supports multiline)
+/// - `options`: font, size, layout and output width
+///
+/// Returns a Vec<u8> (row-major, 0=black, 255=white), options.width wide
+pub fn rasterize_text_with(text: &str, options: &TextRenderOptions) -> Vec<u8> {
+    let width = options.width;
+    let margin = options.margin.min(width.saturating_sub(1) / 2);
+    let inner = width - 2 * margin;
+    let wrap = match options.wrap_width {
+        Some(w) if w > 0 => w.min(inner),
+        _ => inner,
+    };
+    // In RTL the line start is the right edge, so Left/Right trade places
+    let alignment = match (options.direction, options.alignment) {
+        (Direction::Rtl, Alignment::Left) => Alignment::Right,
+        (Direction::Rtl, Alignment::Right) => Alignment::Left,
+        (_, alignment) => alignment,
+    };
+    let layout = Layout {
+        alignment,
+        line_spacing: options.line_spacing,
+        tab_stops: &options.tab_stops,
+        direction: options.direction,
+        ..Layout::new(or_default(&options.fonts))
+    };
+    let text = rasterize_fonts(text, wrap, options.font_size, &layout);
+    if wrap == width {
+        return text;
+    }
+    let x0 = margin
+        + match alignment {
+            Alignment::Left => 0,
+            Alignment::Center => (inner - wrap) / 2,
+            Alignment::Right => inner - wrap,
+        };
+    let height = text.len().checked_div(wrap).unwrap_or(0);
+    let mut pixels = vec![255u8; width * height];
+    for (row, line) in text.chunks_exact(wrap).enumerate() {
+        let start = row * width + x0;
+        pixels[start..start + wrap].copy_from_slice(line);
+    }
+    pixels
+}
+
+/// Rasterizes text with a custom font into a grayscale pixel buffer.
+///
+/// - `text`: The text to render (supports multiline)
+/// - `width`: Output image width in pixels
+/// - `font_size`: Font size in points
+/// - `font`: font to render with
+///
+/// Returns a Vec<u8> (row-major, 0=black, 255=white)
+pub fn rasterize_text_with_font(
+    text: &str,
+    width: usize,
+    font_size: f32,
+    font: &FontHandle,
+) -> Vec<u8> {
+    rasterize_text_aligned_with_font(text, width, font_size, font, Alignment::Left)
+}
+
+/// Rasterizes text with each line aligned within the output width.
+///
+/// - `text`: The text to render (supports multiline)
+/// - `width`: Output image width in pixels
+/// - `font_size`: Font size in points
+/// - `alignment`: left, center or right placement of each line
+///
+/// Returns a Vec<u8> (row-major, 0=black, 255=white)
+pub fn rasterize_text_aligned(
+    text: &str,
+    width: usize,
+    font_size: f32,
+    alignment: Alignment,
+) -> Vec<u8> {
+    rasterize_text_aligned_with_font(text, width, font_size, &FONT, alignment)
+}
+
+/// Rasterizes text with a custom font and per-line alignment.
+///
+/// - `text`: The text to render (supports multiline)
+/// - `width`: Output image width in pixels
+/// - `font_size`: Font size in points
+/// - `font`: font to render with
+/// - `alignment`: left, center or right placement of each line
+///
+/// Returns a Vec<u8> (row-major, 0=black, 255=white)
+pub fn rasterize_text_aligned_with_font(
+    text: &str,
+    width: usize,
+    font_size: f32,
+    font: &FontHandle,
+    alignment: Alignment,
+) -> Vec<u8> {
+    let layout = Layout {
+        alignment,
+        ..Layout::new(std::slice::from_ref(font))
+    };
+    rasterize_fonts(text, width, font_size, &layout)
+}
+
+/// Rasterizes text using a list of fonts, picking per character the first
+/// font that has a glyph for it. Characters missing from every font are drawn
+/// as a hollow box so gaps stay visible.
+///
+/// - `text`: The text to render (supports multiline)
+/// - `width`: Output image width in pixels
+/// - `font_size`: Font size in points
+/// - `fonts`: fonts in priority order; line spacing comes from the first.
+///   An empty list uses the embedded font.
+///
+/// Returns a Vec<u8> (row-major, 0=black, 255=white)
+pub fn rasterize_text_with_fallback(
+    text: &str,
+    width: usize,
+    font_size: f32,
+    fonts: &[FontHandle],
+) -> Vec<u8> {
+    rasterize_fonts(text, width, font_size, &Layout::new(or_default(fonts)))
+}
+
+/// Rasterizes text with the distance between lines scaled.
+///
+/// - `text`: The text to render (supports multiline)
+/// - `width`: Output image width in pixels
+/// - `font_size`: Font size in points
+/// - `line_spacing`: multiplier on the font's line height, e.g. 0.9 for dense
+///   receipts or 1.5 for signs; 1.0 matches rasterize_text. Below 1.0 the last
+///   line keeps its full height so descenders aren't cut off.
+///
+/// Returns a Vec<u8> (row-major, 0=black, 255=white)
+pub fn rasterize_text_spaced(
+    text: &str,
+    width: usize,
+    font_size: f32,
+    line_spacing: f32,
+) -> Vec<u8> {
+    let layout = Layout {
+        line_spacing,
+        ..Layout::new(std::slice::from_ref(&*FONT))
+    };
+    rasterize_fonts(text, width, font_size, &layout)
+}
+
+/// Rasterizes text where each tab character jumps to the next tab stop, so
+/// tab-separated columns line up across lines.
+///
+/// - `text`: The text to render (supports multiline)
+/// - `width`: Output image width in pixels
+/// - `font_size`: Font size in points
+/// - `tab_stops`: x positions in pixels from the line start, ascending. Past
+///   the last one (or if empty) stops repeat every DEFAULT_TAB_WIDTH_EM font
+///   sizes. A tab at or past a stop moves on to the next one.
+///
+/// Returns a Vec<u8> (row-major, 0=black, 255=white)
+pub fn rasterize_text_tabbed(
+    text: &str,
+    width: usize,
+    font_size: f32,
+    tab_stops: &[usize],
+) -> Vec<u8> {
+    let layout = Layout {
+        tab_stops,
+        ..Layout::new(std::slice::from_ref(&*FONT))
+    };
+    rasterize_fonts(text, width, font_size, &layout)
+}
+
+/// Rasterizes text whose font is chosen inline with markup tokens, e.g. for
+/// bilingual signs mixing two scripts on one line.
+///
+/// Markup syntax:
+/// - `{font:N}` switches to `fonts[N]` (0-based) until the next switch,
+///   carrying over line breaks; text starts in font 0
+/// - `{{` is a literal `{`; any other `{` is an error
+///
+/// Unlike rasterize_text_with_fallback there is no fallback: characters the
+/// selected font lacks are drawn as a hollow box. Lines are tall enough for
+/// the largest ascent and descent among `fonts`. Whitespace takes the font
+/// of the character before it.
+///
+/// - `text`: marked-up text to render (supports multiline)
+/// - `width`: Output image width in pixels
+/// - `font_size`: Font size in points
+/// - `fonts`: fonts selectable by index (empty = embedded font only)
+///
+/// Returns a Vec<u8> (row-major, 0=black, 255=white), or Err for malformed
+/// markup or a font index out of range
+pub fn rasterize_text_markup(
+    text: &str,
+    width: usize,
+    font_size: f32,
+    fonts: &[FontHandle],
+) -> Result<Vec<u8>, String> {
+    let fonts = or_default(fonts);
+    let marked = parse_font_markup(text, fonts.len())?;
+    let layout = Layout {
+        font_markup: true,
+        ..Layout::new(fonts)
+    };
+    Ok(rasterize_fonts(&marked, width, font_size, &layout))
+}
+
+/// Measures text as rasterize_text_markup would render it.
+///
+/// - `text`: marked-up text to measure (supports multiline)
+/// - `width`: Wrap width in pixels
+/// - `font_size`: Font size in points
+/// - `fonts`: fonts selectable by index (empty = embedded font only)
+///
+/// Returns (width_used, height) in pixels, or Err for malformed markup
+pub fn measure_text_markup(
+    text: &str,
+    width: usize,
+    font_size: f32,
+    fonts: &[FontHandle],
+) -> Result<(usize, usize), String> {
+    let fonts = or_default(fonts);
+    let marked = parse_font_markup(text, fonts.len())?;
+    let layout = Layout {
+        font_markup: true,
+        ..Layout::new(fonts)
+    };
+    Ok(measure_fonts(&marked, width, font_size, &layout))
+}
+
+/// First of the private-use code points that stand in for `{font:N}` once
+/// markup is parsed, so wrapping can keep treating lines as plain strings.
+const FONT_SWITCH_BASE: u32 = 0x10_0000;
+/// Highest font index the markup accepts.
+const MAX_MARKUP_FONTS: usize = 0x1_0000;
+
+/// Replaces markup tokens with switch characters. Every word starts with the
+/// switch for its font, so any word, wrapped line or broken word piece
+/// measures the same on its own as inside the whole text.
+fn parse_font_markup(text: &str, font_count: usize) -> Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut font = 0;
+    let mut emitted = None;
+    let mut rest = text;
+    while let Some(ch) = rest.chars().next() {
+        let at = text.len() - rest.len();
+        rest = &rest[ch.len_utf8()..];
+        let ch = if ch == '{' {
+            if let Some(after) = rest.strip_prefix('{') {
+                rest = after;
+                '{'
+            } else {
+                let token = rest
+                    .strip_prefix("font:")
+                    .and_then(|t| t.split_once('}'))
+                    .filter(|(n, _)| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+                let Some((index, after)) = token else {
+                    return Err(format!(
+                        "invalid markup at byte {}: expected {{font:N}} or {{{{",
+                        at
+                    ));
+                };
+                font = index
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&n| n < font_count.min(MAX_MARKUP_FONTS))
+                    .ok_or_else(|| {
+                        format!(
+                            "font index {} at byte {} out of range ({} fonts)",
+                            index, at, font_count
+                        )
+                    })?;
+                rest = after;
+                continue;
+            }
+        } else {
+            ch
+        };
+        if font_switch_index(ch).is_some() {
+            return Err(format!(
+                "reserved character U+{:X} at byte {}",
+                ch as u32, at
+            ));
+        }
+        if ch.is_whitespace() {
+            // Force a switch before the next word
+            emitted = None;
+        } else if emitted != Some(font) {
+            out.push(font_switch_char(font));
+            emitted = Some(font);
+        }
+        out.push(ch);
+    }
+    Ok(out)
+}
+
+fn font_switch_char(index: usize) -> char {
+    char::from_u32(FONT_SWITCH_BASE + index as u32).expect("index below MAX_MARKUP_FONTS")
+}
+
+fn font_switch_index(ch: char) -> Option<usize> {
+    (ch as u32)
+        .checked_sub(FONT_SWITCH_BASE)
+        .map(|i| i as usize)
+        .filter(|&i| i < MAX_MARKUP_FONTS)
+}
+
+/// Distance between default tab stops, in multiples of the font size.
+pub const DEFAULT_TAB_WIDTH_EM: f32 = 2.0;
+
+/// Settings shared by laying out, drawing and measuring text.
+struct Layout<'a> {
+    /// Fonts in priority order; line metrics come from the first
+    fonts: &'a [FontHandle],
+    alignment: Alignment,
+    line_spacing: f32,
+    /// Explicit tab stops in pixels, ascending
+    tab_stops: &'a [usize],
+    /// Text carries font switch characters from parse_font_markup instead of
+    /// resolving fonts per character
+    font_markup: bool,
+    /// Rtl draws every wrapped line reversed
+    direction: Direction,
+}
+
+impl<'a> Layout<'a> {
+    fn new(fonts: &'a [FontHandle]) -> Self {
+        Self {
+            fonts,
+            alignment: Alignment::Left,
+            line_spacing: 1.0,
+            tab_stops: &[],
+            font_markup: false,
+            direction: Direction::Ltr,
+        }
+    }
+
+    /// Line metrics: the first font's, or with font markup the extremes over
+    /// all fonts so any of them fits.
+    fn v_metrics(&self, scale: Scale) -> VMetrics {
+        let first = self.fonts[0].font.v_metrics(scale);
+        if !self.font_markup {
+            return first;
+        }
+        self.fonts[1..]
+            .iter()
+            .map(|f| f.font.v_metrics(scale))
+            .fold(first, |a, m| VMetrics {
+                ascent: a.ascent.max(m.ascent),
+                descent: a.descent.min(m.descent),
+                line_gap: a.line_gap.max(m.line_gap),
+            })
+    }
+
+    /// Index of the font `ch` switches to, if it is a font switch character.
+    fn font_switch(&self, ch: char) -> Option<usize> {
+        self.font_markup.then(|| font_switch_index(ch)).flatten()
+    }
+
+    /// Font to draw `ch` with; `selected` is the markup's current font index.
+    fn glyph_font(&self, ch: char, selected: usize) -> Option<&'a Font<'static>> {
+        if self.font_markup {
+            let font = &self.fonts[selected].font;
+            (font.glyph(ch).id().0 != 0).then_some(font)
+        } else {
+            resolve_glyph(self.fonts, ch)
+        }
+    }
+
+    /// Position of the first tab stop strictly right of `x`.
+    fn next_tab_stop(&self, x: f32, scale: Scale) -> f32 {
+        if let Some(&stop) = self.tab_stops.iter().find(|&&stop| stop as f32 > x) {
+            return stop as f32;
+        }
+        let interval = (scale.x * DEFAULT_TAB_WIDTH_EM).round().max(1.0);
+        ((x / interval).floor() + 1.0) * interval
+    }
+}
+
+fn rasterize_fonts(text: &str, width: usize, font_size: f32, layout: &Layout) -> Vec<u8> {
+    let mut pixels = Vec::new();
+    draw_text(text, width, font_size, layout, |height| {
+        pixels = vec![255u8; width * height];
+        |x: usize, y: usize, v: f32| {
+            if v > 0.3 {
+                pixels[y * width + x] = 0;
+            }
+        }
+    });
+    pixels
+}
+
+/// Rasterizes text by dithering the glyph coverage instead of thresholding it,
+/// giving smoother edges on small text.
+///
+/// - `text`: The text to render (supports multiline)
+/// - `width`: Output image width in pixels
+/// - `font_size`: Font size in points
+/// - `dithering`: algorithm applied to the coverage map
+///
+/// Returns a Vec<u8> (row-major, 0=black, 255=white)
+pub fn rasterize_text_dithered(
+    text: &str,
+    width: usize,
+    font_size: f32,
+    dithering: ImageDithering,
+) -> Vec<u8> {
+    let mut coverage: Vec<f32> = Vec::new();
+    let layout = Layout::new(std::slice::from_ref(&*FONT));
+    let height = draw_text(text, width, font_size, &layout, |height| {
+        coverage = vec![0.0f32; width * height];
+        |x: usize, y: usize, v: f32| {
+            let c = &mut coverage[y * width + x];
+            *c = c.max(v);
+        }
+    });
     if width == 0 {
         return vec![];
     }
 
+    let gray: Vec<u8> = coverage
+        .iter()
+        .map(|c| 255 - (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+        .collect();
+    let mut img = match GrayImage::from_raw(width as u32, height as u32, gray) {
+        Some(img) => img,
+        None => return vec![255u8; width * height],
+    };
+    apply_dithering(&mut img, dithering);
+    // Some algorithms (halftone) pad the output; crop back to the text block
+    if img.dimensions() != (width as u32, height as u32) {
+        img = image::imageops::crop_imm(&img, 0, 0, width as u32, height as u32).to_image();
+    }
+    img.into_raw()
+}
+
+/// Lays out wrapped, aligned text and reports glyph coverage per pixel.
+///
+/// `setup` is called once with the block height and returns the plot callback,
+/// which receives (x, y, coverage) for every in-bounds glyph pixel.
+///
+/// Returns the block height in pixels (0 if width is 0)
+fn draw_text<F, P>(text: &str, width: usize, font_size: f32, layout: &Layout, setup: F) -> usize
+where
+    F: FnOnce(usize) -> P,
+    P: FnMut(usize, usize, f32),
+{
+    let scale = Scale::uniform(font_size);
+    let v_metrics = layout.v_metrics(scale);
+    let line_height = line_height(layout, scale, layout.line_spacing);
+    if width == 0 {
+        return 0;
+    }
+
+    let lines = wrap_lines(layout, text, scale, width);
+    let height = block_height(&lines, layout, scale);
+
+    let mut plot = setup(height);
+    let mut plot_clipped = |px: i32, py: i32, v: f32| {
+        if px >= 0 && py >= 0 {
+            let ux = px as usize;
+            let uy = py as usize;
+            if ux < width && uy < height {
+                plot(ux, uy, v);
+            }
+        }
+    };
+
+    for (line_idx, line) in lines.iter().enumerate() {
+        // Lines wrap in logical order; RTL only flips how each one is drawn
+        let reversed: String;
+        let line = match layout.direction {
+            Direction::Ltr => line.as_str(),
+            Direction::Rtl => {
+                reversed = line.chars().rev().collect();
+                &reversed
+            }
+        };
+        let y_baseline = (line_idx * line_height) as f32 + v_metrics.ascent;
+        // Leftover space is floored so odd remainders always round towards the left
+        let leftover = (width as f32 - text_pixel_width(layout, line, scale)).max(0.0);
+        let mut x = match layout.alignment {
+            Alignment::Left => 0.0,
+            Alignment::Center => (leftover / 2.0).floor(),
+            Alignment::Right => leftover.floor(),
+        };
+        let mut prev = None;
+        let mut selected = 0;
+        for ch in line.chars() {
+            if let Some(index) = layout.font_switch(ch) {
+                selected = index;
+                continue;
+            }
+            if ch == '\t' {
+                prev = None;
+                x = layout.next_tab_stop(x, scale);
+                continue;
+            }
+            match layout.glyph_font(ch, selected) {
+                Some(font) => {
+                    let glyph = font.glyph(ch).scaled(scale);
+                    if let Some((prev_font, prev_id)) = prev
+                        && std::ptr::eq(prev_font, font)
+                    {
+                        x += font.pair_kerning(scale, prev_id, glyph.id());
+                    }
+                    prev = Some((font, glyph.id()));
+                    let advance = glyph.h_metrics().advance_width;
+                    let glyph = glyph.positioned(point(x, y_baseline));
+                    if let Some(bb) = glyph.pixel_bounding_box() {
+                        glyph.draw(|gx, gy, v| {
+                            plot_clipped(bb.min.x + gx as i32, bb.min.y + gy as i32, v);
+                        });
+                    }
+                    x += advance;
+                }
+                None => {
+                    prev = None;
+                    let advance = missing_glyph_advance(scale);
+                    if !ch.is_whitespace() {
+                        draw_missing_box(
+                            x,
+                            y_baseline,
+                            advance,
+                            v_metrics.ascent,
+                            &mut plot_clipped,
+                        );
+                    }
+                    x += advance;
+                }
+            }
+        }
+    }
+
+    height
+}
+
+/// Picks the first font with a real glyph for `ch` (glyph id 0 is .notdef).
+fn resolve_glyph(fonts: &[FontHandle], ch: char) -> Option<&Font<'static>> {
+    fonts
+        .iter()
+        .map(|f| &f.font)
+        .find(|f| f.glyph(ch).id().0 != 0)
+}
+
+fn missing_glyph_advance(scale: Scale) -> f32 {
+    (scale.x * 0.5).round()
+}
+
+/// Draws a hollow box in place of a character no font can render.
+fn draw_missing_box<P: FnMut(i32, i32, f32)>(
+    x: f32,
+    y_baseline: f32,
+    advance: f32,
+    ascent: f32,
+    plot: &mut P,
+) {
+    let left = x.round() as i32 + 1;
+    let right = (x + advance).round() as i32 - 2;
+    let top = (y_baseline - ascent * 0.7).round() as i32;
+    let bottom = y_baseline.round() as i32 - 1;
+    if right <= left || bottom <= top {
+        return;
+    }
+    for px in left..=right {
+        plot(px, top, 1.0);
+        plot(px, bottom, 1.0);
+    }
+    for py in top..=bottom {
+        plot(left, py, 1.0);
+        plot(right, py, 1.0);
+    }
+}
+
+/// Measures text as rasterize_text would render it, without drawing.
+///
+/// - `text`: The text to measure (supports multiline)
+/// - `width`: Wrap width in pixels
+/// - `font_size`: Font size in points
+///
+/// Returns (width_used, height) in pixels
+pub fn measure_text(text: &str, width: usize, font_size: f32) -> (usize, usize) {
+    measure_text_with_font(text, width, font_size, &FONT)
+}
+
+/// Measures text rendered with a custom font, without drawing.
+///
+/// - `text`: The text to measure (supports multiline)
+/// - `width`: Wrap width in pixels
+/// - `font_size`: Font size in points
+/// - `font`: font to measure with
+///
+/// Returns (width_used, height) in pixels
+pub fn measure_text_with_font(
+    text: &str,
+    width: usize,
+    font_size: f32,
+    font: &FontHandle,
+) -> (usize, usize) {
+    measure_fonts(
+        text,
+        width,
+        font_size,
+        &Layout::new(std::slice::from_ref(font)),
+    )
+}
+
+/// Measures text as rasterize_text_with_fallback would render it.
+///
+/// - `text`: The text to measure (supports multiline)
+/// - `width`: Wrap width in pixels
+/// - `font_size`: Font size in points
+/// - `fonts`: fonts in priority order (empty = embedded font)
+///
+/// Returns (width_used, height) in pixels
+pub fn measure_text_with_fallback(
+    text: &str,
+    width: usize,
+    font_size: f32,
+    fonts: &[FontHandle],
+) -> (usize, usize) {
+    measure_fonts(text, width, font_size, &Layout::new(or_default(fonts)))
+}
+
+/// Measures text as rasterize_text_spaced would render it.
+///
+/// - `text`: The text to measure (supports multiline)
+/// - `width`: Wrap width in pixels
+/// - `font_size`: Font size in points
+/// - `line_spacing`: multiplier on the font's line height
+///
+/// Returns (width_used, height) in pixels
+pub fn measure_text_spaced(
+    text: &str,
+    width: usize,
+    font_size: f32,
+    line_spacing: f32,
+) -> (usize, usize) {
+    let layout = Layout {
+        line_spacing,
+        ..Layout::new(std::slice::from_ref(&*FONT))
+    };
+    measure_fonts(text, width, font_size, &layout)
+}
+
+fn measure_fonts(text: &str, width: usize, font_size: f32, layout: &Layout) -> (usize, usize) {
+    if width == 0 {
+        return (0, 0);
+    }
+    let scale = Scale::uniform(font_size);
+    let lines = wrap_lines(layout, text, scale, width);
+    let width_used = lines
+        .iter()
+        .map(|line| text_pixel_width(layout, line, scale).ceil() as usize)
+        .max()
+        .unwrap_or(0)
+        .min(width);
+    (width_used, block_height(&lines, layout, scale))
+}
+
+fn or_default(fonts: &[FontHandle]) -> &[FontHandle] {
+    if fonts.is_empty() {
+        std::slice::from_ref(&*FONT)
+    } else {
+        fonts
+    }
+}
+
+/// Distance between baselines; non-positive or non-finite spacing counts as 1.0.
+fn line_height(layout: &Layout, scale: Scale, line_spacing: f32) -> usize {
+    let v_metrics = layout.v_metrics(scale);
+    let spacing = if line_spacing.is_finite() && line_spacing > 0.0 {
+        line_spacing
+    } else {
+        1.0
+    };
+    let natural = v_metrics.ascent - v_metrics.descent + v_metrics.line_gap;
+    ((natural * spacing).ceil() as usize).max(1)
+}
+
+/// Height of a block of lines: every line but the last takes the spaced line
+/// height, the last one at least the unspaced height so tight spacing never
+/// clips its descenders.
+fn block_height(lines: &[String], layout: &Layout, scale: Scale) -> usize {
+    let pitch = line_height(layout, scale, layout.line_spacing);
+    let last = pitch.max(line_height(layout, scale, 1.0));
+    (lines.len().max(1) - 1) * pitch + last
+}
+
+/// Word-wraps text to fit `width`. Words wider than a whole line are broken
+/// between characters (no hyphen is added, so URLs and hashes stay intact).
+/// Runs of spaces collapse to one; a run containing a tab becomes one tab,
+/// dropped if the wrap puts the next word at the start of a line.
+fn wrap_lines(layout: &Layout, text: &str, scale: Scale, width: usize) -> Vec<String> {
     let mut lines: Vec<String> = Vec::new();
     for raw_line in text.lines() {
         let mut cur = String::new();
-        for word in raw_line.split_whitespace() {
-            if cur.is_empty() {
+        for (i, (tab, word)) in split_words(raw_line).into_iter().enumerate() {
+            let sep = if tab { "\t" } else { " " };
+            if text_pixel_width(layout, word, scale) > width as f32 {
+                if !cur.is_empty() {
+                    lines.push(std::mem::take(&mut cur));
+                }
+                let mut pieces = break_word(layout, word, scale, width);
+                cur = pieces.pop().unwrap_or_default();
+                lines.extend(pieces);
+            } else if cur.is_empty() {
+                // Only the raw line's leading tab survives, as indentation
+                if i == 0 && tab {
+                    cur.push('\t');
+                }
                 cur.push_str(word);
             } else {
-                let trial = format!("{} {}", cur, word);
-                if text_pixel_width(&trial, scale) <= width as f32 {
+                let trial = format!("{}{}{}", cur, sep, word);
+                if text_pixel_width(layout, &trial, scale) <= width as f32 {
                     cur = trial;
                 } else {
                     lines.push(cur);
@@ -38,45 +904,299 @@ pub fn rasterize_text(text: &str, width: usize, font_size: f32) -> Vec<u8> {
         }
         lines.push(cur);
     }
+    lines
+}
 
-    let height = if lines.is_empty() {
-        line_height
-    } else {
-        lines.len() * line_height
-    };
-
-    let mut pixels = vec![255u8; width * height];
-
-    for (line_idx, line) in lines.iter().enumerate() {
-        let y_baseline = (line_idx * line_height) as f32 + v_metrics.ascent;
-        let glyphs: Vec<PositionedGlyph> =
-            FONT.layout(line, scale, point(0.0, y_baseline)).collect();
-        for glyph in glyphs {
-            if let Some(bb) = glyph.pixel_bounding_box() {
-                glyph.draw(|gx, gy, v| {
-                    let px = bb.min.x + gx as i32;
-                    let py = bb.min.y + gy as i32;
-                    if px >= 0 && py >= 0 {
-                        let ux = px as usize;
-                        let uy = py as usize;
-                        if ux < width && uy < height && v > 0.3 {
-                            pixels[uy * width + ux] = 0;
-                        }
-                    }
-                });
+/// Splits a line into words, each flagged with whether the whitespace before
+/// it contained a tab.
+fn split_words(line: &str) -> Vec<(bool, &str)> {
+    let mut words = Vec::new();
+    let mut tab = false;
+    let mut start = None;
+    for (i, ch) in line.char_indices() {
+        if ch.is_whitespace() {
+            if let Some(s) = start.take() {
+                words.push((tab, &line[s..i]));
+                tab = false;
             }
+            tab |= ch == '\t';
+        } else if start.is_none() {
+            start = Some(i);
         }
     }
+    if let Some(s) = start {
+        words.push((tab, &line[s..]));
+    }
+    words
+}
 
-    pixels
+/// Splits a word into pieces that each fit `width`, keeping at least one
+/// character per piece. With font markup each piece starts with the switch
+/// to its font.
+fn break_word(layout: &Layout, word: &str, scale: Scale, width: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut cur = String::new();
+    let mut visible = 0;
+    let mut switch = None;
+    for ch in word.chars() {
+        if layout.font_switch(ch).is_some() {
+            switch = Some(ch);
+            cur.push(ch);
+            continue;
+        }
+        cur.push(ch);
+        visible += 1;
+        if visible > 1 && text_pixel_width(layout, &cur, scale) > width as f32 {
+            cur.pop();
+            pieces.push(std::mem::take(&mut cur));
+            cur.extend(switch);
+            cur.push(ch);
+            visible = 1;
+        }
+    }
+    pieces.push(cur);
+    pieces
 }
 
-fn text_pixel_width(s: &str, scale: Scale) -> f32 {
+/// Width of `s` as draw_text lays it out from the line start: per-character
+/// font resolution (or font switches with markup), kerning between characters
+/// taken from the same font, and tabs jumping to the next tab stop.
+fn text_pixel_width(layout: &Layout, s: &str, scale: Scale) -> f32 {
     let mut w = 0.0f32;
+    let mut prev = None;
+    let mut selected = 0;
     for ch in s.chars() {
-        let g = FONT.glyph(ch).scaled(scale);
-        let h = g.h_metrics().advance_width;
-        w += h;
+        if let Some(index) = layout.font_switch(ch) {
+            selected = index;
+            continue;
+        }
+        if ch == '\t' {
+            prev = None;
+            w = layout.next_tab_stop(w, scale);
+            continue;
+        }
+        match layout.glyph_font(ch, selected) {
+            Some(font) => {
+                let g = font.glyph(ch).scaled(scale);
+                if let Some((prev_font, prev_id)) = prev
+                    && std::ptr::eq(prev_font, font)
+                {
+                    w += font.pair_kerning(scale, prev_id, g.id());
+                }
+                prev = Some((font, g.id()));
+                w += g.h_metrics().advance_width;
+            }
+            None => {
+                prev = None;
+                w += missing_glyph_advance(scale);
+            }
+        }
     }
     w
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::DEFAULT_TEXT_SIZE;
+
+    fn ink(pixels: &[u8]) -> usize {
+        pixels.iter().filter(|&&p| p == 0).count()
+    }
+
+    #[test]
+    fn long_token_breaks_within_width() {
+        let token = "abcdefghij0123456789".repeat(3);
+        let token = token.as_str();
+        assert_eq!(token.len(), 60);
+        let width = 200;
+        let scale = Scale::uniform(DEFAULT_TEXT_SIZE);
+        let layout = Layout::new(std::slice::from_ref(&*FONT));
+        assert!(text_pixel_width(&layout, token, scale) > width as f32);
+
+        let lines = wrap_lines(&layout, token, scale, width);
+        assert!(lines.len() > 1);
+        assert_eq!(lines.concat(), token);
+        for line in &lines {
+            assert!(text_pixel_width(&layout, line, scale) <= width as f32);
+        }
+
+        // Every glyph is drawn and nothing is plotted past the last column
+        let mut max_x = 0;
+        draw_text(token, width, DEFAULT_TEXT_SIZE, &layout, |_| {
+            |x: usize, _: usize, _: f32| max_x = max_x.max(x)
+        });
+        assert!(max_x < width);
+        let wrapped = ink(&rasterize_text(token, width, DEFAULT_TEXT_SIZE));
+        let single_line = ink(&rasterize_text(token, 10_000, DEFAULT_TEXT_SIZE));
+        let diff = wrapped.abs_diff(single_line) as f64;
+        assert!(
+            diff / (single_line as f64) < 0.02,
+            "{} vs {}",
+            wrapped,
+            single_line
+        );
+    }
+
+    #[test]
+    fn line_spacing_scales_line_pitch() {
+        let text = "Ag\nAg\nAg";
+        let width = PRINTER_WIDTH;
+        let height =
+            |spacing| rasterize_text_spaced(text, width, DEFAULT_TEXT_SIZE, spacing).len() / width;
+        let layout = Layout::new(std::slice::from_ref(&*FONT));
+        let scale = Scale::uniform(DEFAULT_TEXT_SIZE);
+        let single = line_height(&layout, scale, 1.0);
+        let double = line_height(&layout, scale, 2.0);
+        assert!(double >= 2 * single - 1 && double <= 2 * single);
+
+        assert_eq!(height(1.0), 3 * single);
+        assert_eq!(height(2.0), 3 * double);
+        assert_eq!(
+            rasterize_text_spaced(text, width, DEFAULT_TEXT_SIZE, 1.0),
+            rasterize_text(text, width, DEFAULT_TEXT_SIZE)
+        );
+        // Tight spacing keeps the last line whole
+        let tight = line_height(&layout, scale, 0.5);
+        assert_eq!(height(0.5), 2 * tight + single);
+        // Nonsense spacing falls back to 1.0
+        assert_eq!(height(0.0), height(1.0));
+        assert_eq!(height(f32::NAN), height(1.0));
+    }
+
+    /// Leftmost inked column at or after `from`.
+    fn first_ink_from(pixels: &[u8], width: usize, from: usize) -> Option<usize> {
+        pixels
+            .chunks_exact(width)
+            .filter_map(|row| row[from..].iter().position(|&p| p < 128))
+            .min()
+            .map(|x| x + from)
+    }
+
+    #[test]
+    fn tab_stops_line_up_columns() {
+        let width = PRINTER_WIDTH;
+        let stop = 200;
+        let short = rasterize_text_tabbed("a\tX", width, DEFAULT_TEXT_SIZE, &[stop]);
+        let long = rasterize_text_tabbed("abcde\tX", width, DEFAULT_TEXT_SIZE, &[stop]);
+        assert_eq!(
+            first_ink_from(&long, width, 0),
+            first_ink_from(&short, width, 0)
+        );
+        // Nothing of the first column reaches the stop, so this is the X
+        let x_short = first_ink_from(&short, width, stop - 10).unwrap();
+        let x_long = first_ink_from(&long, width, stop - 10).unwrap();
+        assert_eq!(x_short, x_long);
+        assert!((stop..stop + 8).contains(&x_short));
+
+        // Both lines of one block use the same stop
+        let block = rasterize_text_tabbed("a\tX\nabcde\tX", width, DEFAULT_TEXT_SIZE, &[stop]);
+        let half = block.len() / 2 / width * width;
+        assert_eq!(
+            first_ink_from(&block[..half], width, stop - 10),
+            first_ink_from(&block[half..], width, stop - 10)
+        );
+    }
+
+    #[test]
+    fn markup_switches_between_two_fonts() {
+        let (f0, f1) = (font_switch_char(0), font_switch_char(1));
+        assert_eq!(
+            parse_font_markup("ab{font:1}c d\ne{font:0}f", 2).unwrap(),
+            format!("{f0}ab{f1}c {f1}d\n{f1}e{f0}f")
+        );
+
+        // The same font under both indices renders exactly like plain text
+        let fonts = [default_font().clone(), default_font().clone()];
+        let width = PRINTER_WIDTH;
+        assert_eq!(
+            rasterize_text_markup("Hi {font:1}there\nyou", width, DEFAULT_TEXT_SIZE, &fonts)
+                .unwrap(),
+            rasterize_text("Hi there\nyou", width, DEFAULT_TEXT_SIZE)
+        );
+        assert_eq!(
+            measure_text_markup("{font:1}Hi", width, DEFAULT_TEXT_SIZE, &fonts).unwrap(),
+            measure_text("Hi", width, DEFAULT_TEXT_SIZE)
+        );
+    }
+
+    #[test]
+    fn markup_escapes_braces() {
+        assert_eq!(
+            parse_font_markup("{{x}", 1).unwrap(),
+            format!("{}{{x}}", font_switch_char(0))
+        );
+        let width = PRINTER_WIDTH;
+        assert_eq!(
+            rasterize_text_markup("a{{b", width, DEFAULT_TEXT_SIZE, &[]).unwrap(),
+            rasterize_text("a{b", width, DEFAULT_TEXT_SIZE)
+        );
+    }
+
+    #[test]
+    fn markup_errors() {
+        let fonts = [default_font().clone(), default_font().clone()];
+        let err = |text: &str| {
+            rasterize_text_markup(text, PRINTER_WIDTH, DEFAULT_TEXT_SIZE, &fonts).unwrap_err()
+        };
+        assert!(err("a{b").contains("byte 1"));
+        assert!(err("{font:}x").contains("invalid markup"));
+        assert!(err("{font:1x}").contains("invalid markup"));
+        assert!(err("{font:0").contains("invalid markup"));
+        assert!(err("{").contains("invalid markup"));
+        assert!(err("ok {font:2}").contains("out of range"));
+        assert!(err("{font:99999999999999999999}").contains("out of range"));
+        assert!(err("\u{100000}").contains("reserved"));
+        // Without fonts only the embedded one, index 0, exists
+        assert!(rasterize_text_markup("{font:1}x", PRINTER_WIDTH, DEFAULT_TEXT_SIZE, &[]).is_err());
+    }
+
+    #[test]
+    fn rtl_puts_the_first_glyph_at_the_right_edge() {
+        let width = PRINTER_WIDTH;
+        let rtl = |text| {
+            rasterize_text_with(
+                text,
+                &TextRenderOptions::new()
+                    .font_size(DEFAULT_TEXT_SIZE)
+                    .direction(Direction::Rtl),
+            )
+        };
+        let last_ink = |pixels: &[u8]| {
+            pixels
+                .chunks_exact(width)
+                .filter_map(|row| row.iter().rposition(|&p| p < 128))
+                .max()
+        };
+
+        let text = rtl("HIL");
+        let first = rtl("H");
+        assert_eq!(text.len(), first.len());
+        let right = last_ink(&text).unwrap();
+        assert!(right >= width - 8, "rightmost ink at {right}");
+        assert_eq!(last_ink(&first), Some(right));
+        // The right end of the line is the H (up to subpixel placement), the
+        // rest is further left
+        let h_start = first_ink_from(&first, width, 0).unwrap();
+        assert!(h_start > width / 2);
+        let ink_from = |pixels: &[u8], from: usize| {
+            ink(&pixels
+                .chunks_exact(width)
+                .flat_map(|row| &row[from..])
+                .copied()
+                .collect::<Vec<_>>())
+        };
+        let h_ink = ink_from(&first, h_start);
+        assert!(ink_from(&text, h_start).abs_diff(h_ink) * 10 < h_ink);
+        assert!(first_ink_from(&text, width, 0).unwrap() < h_start - 4);
+
+        // Same pixels as the reversed string right-aligned left to right
+        let reversed = rasterize_text_with(
+            "LIH",
+            &TextRenderOptions::new()
+                .font_size(DEFAULT_TEXT_SIZE)
+                .alignment(Alignment::Right),
+        );
+        assert_eq!(text, reversed);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::error::PrinterError;
+use std::time::Duration;
+
+/// Limit on a whole download (connect, redirects, request and body) in fetch_image.
+pub const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+/// Default cap on a downloaded image body (16 MiB).
+pub const DEFAULT_MAX_DOWNLOAD: usize = 16 * 1024 * 1024;
+/// Redirects followed before fetch_image gives up.
+pub const MAX_REDIRECTS: usize = 10;
+
+/// Downloads an image with a GET through reqwest.
+///
+/// Both `http://` and `https://` URLs work (TLS through rustls, so no system
+/// OpenSSL is needed), and up to MAX_REDIRECTS redirects are followed.
+///
+/// - `url`: `http://` or `https://` URL of the image
+/// - `max_bytes`: largest accepted body; bigger responses are refused as soon
+///   as their Content-Length or the bytes received so far exceed it
+///
+/// Returns the response body, or Err for invalid URLs, network and TLS
+/// failures, non-2xx statuses, a Content-Type other than image/*, oversized
+/// bodies and downloads taking longer than FETCH_TIMEOUT
+pub async fn fetch_image(url: &str, max_bytes: usize) -> Result<Vec<u8>, PrinterError> {
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::limited(MAX_REDIRECTS))
+        .build()
+        .map_err(|e| request_error(url, e))?;
+    let mut response = client
+        .get(url)
+        .header(reqwest::header::ACCEPT, "image/*")
+        .send()
+        .await
+        .map_err(|e| request_error(url, e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(PrinterError::Transport(format!(
+            "{} answered {}",
+            url, status
+        )));
+    }
+    if let Some(content_type) = response.headers().get(reqwest::header::CONTENT_TYPE) {
+        let content_type = String::from_utf8_lossy(content_type.as_bytes());
+        if !content_type.to_ascii_lowercase().starts_with("image/") {
+            return Err(PrinterError::Image(format!(
+                "{} is {}, not an image",
+                url, content_type
+            )));
+        }
+    }
+    if response
+        .content_length()
+        .is_some_and(|len| len > max_bytes as u64)
+    {
+        return Err(too_large(url, max_bytes));
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| request_error(url, e))? {
+        if body.len() + chunk.len() > max_bytes {
+            return Err(too_large(url, max_bytes));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+/// Maps a reqwest failure to Timeout when FETCH_TIMEOUT ran out, else Transport.
+fn request_error(url: &str, e: reqwest::Error) -> PrinterError {
+    if e.is_timeout() {
+        PrinterError::Timeout(FETCH_TIMEOUT)
+    } else {
+        PrinterError::Transport(format!("fetching {} failed: {}", url, e))
+    }
+}
+
+fn too_large(url: &str, max_bytes: usize) -> PrinterError {
+    PrinterError::Image(format!(
+        "{} is larger than the {} byte download limit",
+        url, max_bytes
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Serves one canned response per connection, picked by request path.
+    fn serve(routes: &'static [(&'static str, &'static str)]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+                let response = routes
+                    .iter()
+                    .find(|(route, _)| *route == path)
+                    .map_or("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n", |r| {
+                        r.1
+                    });
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        base
+    }
+
+    #[tokio::test]
+    async fn follows_redirects_and_checks_the_response() {
+        let base = serve(&[
+            (
+                "/old",
+                "HTTP/1.1 302 Found\r\nLocation: /img\r\nContent-Length: 0\r\n\r\n",
+            ),
+            (
+                "/img",
+                "HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: 4\r\n\r\nPNG!",
+            ),
+            (
+                "/page",
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 2\r\n\r\nhi",
+            ),
+            (
+                "/loop",
+                "HTTP/1.1 302 Found\r\nLocation: /loop\r\nContent-Length: 0\r\n\r\n",
+            ),
+        ]);
+
+        let fetched = fetch_image(&format!("{base}/old"), DEFAULT_MAX_DOWNLOAD).await;
+        assert_eq!(fetched.unwrap(), b"PNG!");
+        assert!(matches!(
+            fetch_image(&format!("{base}/img"), 3).await,
+            Err(PrinterError::Image(_))
+        ));
+        assert!(matches!(
+            fetch_image(&format!("{base}/page"), DEFAULT_MAX_DOWNLOAD).await,
+            Err(PrinterError::Image(_))
+        ));
+        assert!(matches!(
+            fetch_image(&format!("{base}/missing"), DEFAULT_MAX_DOWNLOAD).await,
+            Err(PrinterError::Transport(_))
+        ));
+        assert!(matches!(
+            fetch_image(&format!("{base}/loop"), DEFAULT_MAX_DOWNLOAD).await,
+            Err(PrinterError::Transport(_))
+        ));
+        assert!(matches!(
+            fetch_image("not a url", DEFAULT_MAX_DOWNLOAD).await,
+            Err(PrinterError::Transport(_))
+        ));
+    }
+}
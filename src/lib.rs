@@ -1,21 +1,67 @@
 //! CatPrinter library: print text and images to CatPrinter devices via BLE.
 //!
 //! Main modules:
-//! - ble: BLE transport and async printer
+//! - barcode: Code128 and EAN-13 rendering
+//! - ble: BLE transports (async and blocking) and async printer
 //! - dithering: image dithering algorithms
+//! - error: error type shared by all printer APIs
 //! - font: text rasterization
+//! - http: image downloads for print_image_from_url (`http` feature)
 //! - printer: sync printer
 //! - protocol: packet and data utilities
+//! - qr: QR code rendering
+//! - queue: FIFO job queue sharing one async printer between tasks
+//! - testutil: mock transports (sync and async) for tests without hardware,
+//!   plus a simulated printer with the `testing` feature
+//! - wire: packet framing and pixel packing, the only module without the
+//!   default `std` feature (no_std + alloc)
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod barcode;
+#[cfg(feature = "std")]
 pub mod ble;
+#[cfg(feature = "std")]
 pub mod dithering;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "std")]
 pub mod font;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "std")]
 pub mod printer;
+#[cfg(feature = "std")]
 pub mod protocol;
+#[cfg(feature = "std")]
+pub mod qr;
+#[cfg(feature = "std")]
+pub mod queue;
+#[cfg(feature = "std")]
+pub mod testutil;
+pub mod wire;
 
 /// BLE API: scan/connect to printers, async printing
-pub use ble::{connect, scan, CatPrinterAsync, DeviceInfo};
+#[cfg(feature = "std")]
+pub use ble::{
+    connect, connect_first, scan, scan_filtered, scan_stream, BleSyncTransport, CatPrinterAsync,
+    DeviceInfo, Keepalive, LiveStatus,
+};
+/// Error type
+#[cfg(feature = "std")]
+pub use error::PrinterError;
 /// Sync printer API
+#[cfg(feature = "std")]
 pub use printer::*;
 /// Protocol utilities (packets, pixel packing, etc)
+#[cfg(feature = "std")]
 pub use protocol::*;
+/// Print queue for sharing a printer between tasks
+#[cfg(feature = "std")]
+pub use queue::{JobId, JobStatus, PrintQueue, QueuedJob};
+/// Without std the wire format is the whole API
+#[cfg(not(feature = "std"))]
+pub use wire::*;
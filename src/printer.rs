@@ -1,187 +1,669 @@
-use crate::dithering::{atkinson_dither, bayer_dither, halftone_dither, ImageDithering};
+use crate::barcode::{render_barcode_to_pixels, Barcode};
+use crate::dithering::{
+    apply_brightness_contrast, apply_dithering_strength, apply_gamma, flatten_alpha, to_grayscale,
+    GrayscaleMode, ImageDithering, DEFAULT_ALPHA_BACKGROUND, DEFAULT_DIFFUSION_STRENGTH,
+};
+use crate::error::PrinterError;
 use crate::protocol::*;
+use crate::qr::{render_qr_to_pixels, EcLevel, DEFAULT_EC_LEVEL};
+pub use image::imageops::FilterType;
+pub use image::metadata::Orientation;
+use image::{DynamicImage, GrayImage, ImageDecoder, ImageReader, Luma};
+use std::io::{BufRead, Cursor, Seek};
+use std::path::PathBuf;
 use std::time::Duration;
 
 /// Transport trait for CatPrinter communication (sync).
 /// Implement this for your BLE or mock transport.
 pub trait Transport {
     /// Write a control packet to the printer.
-    fn write_control(&mut self, data: &[u8]) -> Result<(), String>;
+    fn write_control(&mut self, data: &[u8]) -> Result<(), PrinterError>;
     /// Write image/text data to the printer.
-    fn write_data(&mut self, data: &[u8]) -> Result<(), String>;
-    /// Read a notification from the printer (with timeout).
-    fn read_notification(&mut self, timeout: Duration) -> Result<Vec<u8>, String>;
+    fn write_data(&mut self, data: &[u8]) -> Result<(), PrinterError>;
+    /// Write image/text data and wait until the link acknowledges it, returning
+    /// an error if it was not delivered. The default has no acknowledgement and
+    /// just calls write_data.
+    fn write_data_acked(&mut self, data: &[u8]) -> Result<(), PrinterError> {
+        self.write_data(data)
+    }
+    /// Read a notification from the printer, returning
+    /// Err(PrinterError::Timeout(timeout)) if none arrives in time.
+    fn read_notification(&mut self, timeout: Duration) -> Result<Vec<u8>, PrinterError>;
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "state", content = "code"))]
 pub enum PrinterState {
     Standby,
     Printing,
-    Error(u8),
+    Error(PrinterErrorCode),
     Unknown,
 }
 
+/// Error condition reported in byte 13 of the 0xA1 status payload.
+///
+/// Mapping (from MXW01 captures):
+/// - 0x01, 0x09: out of paper
+/// - 0x04: print head overheated
+/// - 0x08: battery too low to print
+///
+/// Anything else (including cover open, whose code isn't confirmed) is `Unknown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PrinterErrorCode {
+    OutOfPaper,
+    Overheated,
+    LowBattery,
+    Unknown(u8),
+}
+
+impl PrinterErrorCode {
+    pub fn from_u8(code: u8) -> Self {
+        match code {
+            0x01 | 0x09 => PrinterErrorCode::OutOfPaper,
+            0x04 => PrinterErrorCode::Overheated,
+            0x08 => PrinterErrorCode::LowBattery,
+            other => PrinterErrorCode::Unknown(other),
+        }
+    }
+}
+
+impl std::fmt::Display for PrinterErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrinterErrorCode::OutOfPaper => write!(f, "out of paper"),
+            PrinterErrorCode::Overheated => write!(f, "print head overheated"),
+            PrinterErrorCode::LowBattery => write!(f, "battery too low to print"),
+            PrinterErrorCode::Unknown(code) => write!(f, "unknown printer error 0x{:02X}", code),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PrinterStatus {
     pub battery_percent: Option<u8>,
     pub temperature: Option<u8>,
     pub state: PrinterState,
 }
 
+/// Battery percentage below which PrinterStatus::is_battery_low reports true.
+pub const DEFAULT_MIN_BATTERY_PERCENT: u8 = 10;
+/// Head temperature above which PrinterStatus::is_overheated reports true.
+pub const DEFAULT_MAX_TEMPERATURE: u8 = 60;
+
+impl PrinterStatus {
+    /// True if the reported battery is below DEFAULT_MIN_BATTERY_PERCENT.
+    /// An unreported battery level counts as not low.
+    pub fn is_battery_low(&self) -> bool {
+        self.battery_percent
+            .is_some_and(|b| b < DEFAULT_MIN_BATTERY_PERCENT)
+    }
+
+    /// True if the reported temperature is above DEFAULT_MAX_TEMPERATURE.
+    /// An unreported temperature counts as not overheated.
+    pub fn is_overheated(&self) -> bool {
+        self.temperature
+            .is_some_and(|t| t > DEFAULT_MAX_TEMPERATURE)
+    }
+
+    /// Check the status against the default thresholds.
+    ///
+    /// Returns NotReady with the reason if a print job would likely fail
+    pub fn can_print(&self) -> Result<(), PrinterError> {
+        self.can_print_with(DEFAULT_MIN_BATTERY_PERCENT, DEFAULT_MAX_TEMPERATURE)
+    }
+
+    /// Check the status against custom thresholds.
+    ///
+    /// - `min_battery_percent`: lowest battery level allowed to print
+    /// - `max_temperature`: highest head temperature allowed to print
+    ///
+    /// Returns NotReady with the reason if a print job would likely fail
+    pub fn can_print_with(
+        &self,
+        min_battery_percent: u8,
+        max_temperature: u8,
+    ) -> Result<(), PrinterError> {
+        if let PrinterState::Error(code) = self.state {
+            return Err(PrinterError::NotReady(code));
+        }
+        if self
+            .battery_percent
+            .is_some_and(|b| b < min_battery_percent)
+        {
+            return Err(PrinterError::NotReady(PrinterErrorCode::LowBattery));
+        }
+        if self.temperature.is_some_and(|t| t > max_temperature) {
+            return Err(PrinterError::NotReady(PrinterErrorCode::Overheated));
+        }
+        Ok(())
+    }
+
+    /// Explain a rejected print request (0xA9 payload byte 0x01) from the
+    /// status read right after it.
+    ///
+    /// The 0xA9 acknowledgement carries a single result byte (from MXW01
+    /// captures): 0x00 accepted, 0x01 rejected. It has no reason code, but the
+    /// printer only refuses while it is still printing or in an error state,
+    /// which the status tells apart.
+    ///
+    /// Returns Busy while printing, NotReady with the cause when can_print
+    /// fails, otherwise Rejected
+    pub fn rejection_reason(&self) -> PrinterError {
+        if self.state == PrinterState::Printing {
+            return PrinterError::Busy;
+        }
+        match self.can_print() {
+            Err(e) => e,
+            Ok(()) => PrinterError::Rejected,
+        }
+    }
+}
+
+/// How images taller than the configured max height are fitted.
+///
+/// Images are always scaled to the printer width first, so sources narrower
+/// than the printer width are upscaled and wider ones downscaled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResizeMode {
+    /// Squash the scaled image vertically so it fits within max height.
+    #[default]
+    FitWidth,
+    /// Keep the aspect ratio and cut off everything below max height.
+    CropTop,
+    /// Keep the aspect ratio and keep the vertically centered part.
+    CropCenter,
+}
+
+/// Clockwise rotation applied to loaded images after EXIF orientation, e.g.
+/// to print a landscape photo lengthwise along the paper.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    #[default]
+    None,
+    Cw90,
+    Cw180,
+    Cw270,
+}
+
 /// Synchronous CatPrinter API for printing text and images.
 ///
 /// - `transport`: implements Transport trait (BLE or mock)
-/// - `chunk_size`: bytes per data chunk (default: 180)
+/// - `chunk_size`: bytes per data chunk (default: DEFAULT_CHUNK_SIZE)
+/// - `density`: print energy sent before each job (default: DEFAULT_DENSITY)
+/// - `width`: print head width in pixels; images are resized and text wrapped to it
+///   (default: PRINTER_WIDTH)
+/// - `resize_mode`: how tall images are fitted (default: FitWidth)
+/// - `resize_filter`: scaling filter for loaded images; None picks Nearest when
+///   dithering is None and Lanczos3 otherwise (default: None). For 1bpp thermal
+///   output Nearest keeps text and line art crisp but aliases photos; Triangle
+///   and CatmullRom are in between; Lanczos3 gives the smoothest photo tones
+///   but its slight blur can thin out small text once thresholded
+/// - `max_height`: maximum image height in pixels (default: 800)
+/// - `pad_top`, `pad_bottom`: white rows added above and below loaded images,
+///   after dithering and invert so they never print (default: 0)
+/// - `min_height`: loaded images shorter than this, padding included, are
+///   centered vertically on white rows up to it (default: 0, off)
+/// - `unsharp`: (sigma, threshold) of an unsharp mask applied right after
+///   resizing, before brightness/contrast, gamma and dithering; e.g. (1.0, 4)
+///   crisps up soft photos (default: None)
+/// - `brightness`: added to each pixel before gamma and dithering (default: 0)
+/// - `contrast`: contrast multiplier applied with brightness (default: 1.0)
+/// - `gamma`: gamma correction applied before dithering (default: 1.0, no change)
+/// - `diffusion_strength`: share of the error the diffusion dithers propagate,
+///   0.0 (plain threshold) to 1.0; lower values cut grain on small text
+///   (default: DEFAULT_DIFFUSION_STRENGTH, 1.0)
+/// - `alpha_background`: color transparent areas of loaded images are blended
+///   onto (default: DEFAULT_ALPHA_BACKGROUND, white)
+/// - `grayscale`: how loaded color images collapse to gray (default: Luma709)
+/// - `signature`: how print_text sets off the author line (default: "-- " after
+///   one blank line)
+/// - `orientation`: rotation/flip forced on loaded images; None follows the
+///   EXIF tag (default: None)
+/// - `rotation`: extra rotation of loaded images after orientation (default: None)
+/// - `flip_h`, `flip_v`: mirror loaded images left-right / top-bottom after
+///   rotation (default: false)
+/// - `transform`: orientation change applied by print_image to every job (default: None)
+/// - `text_transform`: orientation of rendered text, before `transform` (default: Rotate180)
+/// - `autocrop`: crop loaded images to their non-white content before resizing (default: false)
+/// - `autocrop_threshold`: gray level at or above which autocrop treats pixels as
+///   background (default: DEFAULT_AUTOCROP_THRESHOLD)
+/// - `invert`: print white-on-black by inverting images after dithering (default: false)
+/// - `debug_dump_path`: if set, processed images are also saved there as PNG (default: None)
+/// - `ack_timeout`: wait for command acknowledgements like 0xA9 (default: 2s)
+/// - `print_timeout`: wait for the 0xAA print complete notification (default: 60s)
+/// - `retries`: extra attempts for each failed packet write (default: 0)
+/// - `retry_delay`: pause before each retry (default: 20ms)
+/// - `bit_order`: pixel order within packed 1bpp bytes (default: Lsb0)
+/// - `on_progress`: called with the lines printed so far for each 0xBB progress
+///   notification while waiting for print complete (default: None)
+/// - `reliable`: send data chunks with write_data_acked and retry a failed one
+///   max(retries, RELIABLE_RETRIES) times, so it is sent at most 1 + that
+///   many times; slower (default: false)
+/// - `cooldown_temp`: if set, poll status before each job and wait while the head
+///   is hotter than this; trades speed for avoiding mid-print thermal stalls
+///   (default: None, off)
+/// - `cooldown_delay`: wait between those polls (default: DEFAULT_COOLDOWN_DELAY)
+/// - `precheck`: query status before each job and refuse it if can_print fails;
+///   costs one extra round trip per job (default: false)
+/// - `busy_wait`: if set, a print request rejected because the printer is busy
+///   waits up to this long with wait_until_ready and is sent once more
+///   (default: None)
+/// - `inter_chunk_delay`: pause after each data chunk write; a workaround for
+///   firmware whose receive buffer overruns and drops bands when chunks
+///   arrive back to back (default: None, full speed)
 pub struct CatPrinter<T: Transport> {
     pub transport: T,
     pub chunk_size: usize,
+    pub density: u8,
+    pub width: usize,
+    pub resize_mode: ResizeMode,
+    pub resize_filter: Option<FilterType>,
+    pub max_height: u32,
+    pub pad_top: u16,
+    pub pad_bottom: u16,
+    pub min_height: u16,
+    pub unsharp: Option<(f32, i32)>,
+    pub brightness: i16,
+    pub contrast: f32,
+    pub gamma: f32,
+    pub diffusion_strength: f32,
+    pub alpha_background: image::Rgb<u8>,
+    pub grayscale: GrayscaleMode,
+    pub signature: SignatureStyle,
+    pub orientation: Option<Orientation>,
+    pub rotation: Rotation,
+    pub flip_h: bool,
+    pub flip_v: bool,
+    pub transform: Transform,
+    pub text_transform: Transform,
+    pub autocrop: bool,
+    pub autocrop_threshold: u8,
+    pub invert: bool,
+    pub debug_dump_path: Option<PathBuf>,
+    pub ack_timeout: Duration,
+    pub print_timeout: Duration,
+    pub retries: usize,
+    pub retry_delay: Duration,
+    pub bit_order: BitOrder,
+    pub on_progress: Option<Box<dyn FnMut(u16) + Send>>,
+    pub reliable: bool,
+    pub cooldown_temp: Option<u8>,
+    pub cooldown_delay: Duration,
+    pub precheck: bool,
+    pub busy_wait: Option<Duration>,
+    pub inter_chunk_delay: Option<Duration>,
 }
 
+/// Default maximum height of loaded images; reasonable for most prints.
+pub const DEFAULT_MAX_HEIGHT: u32 = 800;
+/// Data chunk size used when the link doesn't report its MTU, which includes
+/// every btleplug connection.
+pub const DEFAULT_CHUNK_SIZE: usize = 180;
+/// Default wait for command acknowledgements.
+pub const DEFAULT_ACK_TIMEOUT: Duration = Duration::from_secs(2);
+/// Default wait for a print job to complete.
+pub const DEFAULT_PRINT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Default gray level from which autocrop treats pixels as background; a bit
+/// below 255 so scanner noise on white paper doesn't count as content.
+pub const DEFAULT_AUTOCROP_THRESHOLD: u8 = 240;
+/// Default pause between write retries.
+pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(20);
+/// Pause between status polls in wait_until_ready.
+pub const READY_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Default wait between temperature polls while the head cools down.
+pub const DEFAULT_COOLDOWN_DELAY: Duration = Duration::from_secs(5);
+/// Pause CatPrinterAsync leaves after each data chunk by default.
+pub const DEFAULT_INTER_CHUNK_DELAY: Duration = Duration::from_millis(10);
+/// Retries of a failed data chunk in reliable mode: the chunk is sent at most
+/// 1 + RELIABLE_RETRIES times (CatPrinter retries more if `retries` is higher).
+///
+/// The MXW01 firmware sends no per-chunk acknowledgement notification, so
+/// reliable mode relies on the transport: the BLE transports use GATT write
+/// requests, which the printer acknowledges at the link layer, and fall back
+/// to plain writes if the data characteristic doesn't accept them.
+pub const RELIABLE_RETRIES: usize = 3;
+
 impl<T: Transport> CatPrinter<T> {
+    /// Create a printer with default settings; see CatPrinterBuilder to customize.
     pub fn new(transport: T) -> Self {
         Self {
             transport,
-            chunk_size: 180,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            density: DEFAULT_DENSITY,
+            width: PRINTER_WIDTH,
+            resize_mode: ResizeMode::FitWidth,
+            resize_filter: None,
+            max_height: DEFAULT_MAX_HEIGHT,
+            pad_top: 0,
+            pad_bottom: 0,
+            min_height: 0,
+            unsharp: None,
+            brightness: 0,
+            contrast: 1.0,
+            gamma: 1.0,
+            diffusion_strength: DEFAULT_DIFFUSION_STRENGTH,
+            alpha_background: DEFAULT_ALPHA_BACKGROUND,
+            grayscale: GrayscaleMode::Luma709,
+            signature: SignatureStyle::default(),
+            orientation: None,
+            rotation: Rotation::None,
+            flip_h: false,
+            flip_v: false,
+            transform: Transform::None,
+            text_transform: Transform::Rotate180,
+            autocrop: false,
+            autocrop_threshold: DEFAULT_AUTOCROP_THRESHOLD,
+            invert: false,
+            debug_dump_path: None,
+            ack_timeout: DEFAULT_ACK_TIMEOUT,
+            print_timeout: DEFAULT_PRINT_TIMEOUT,
+            retries: 0,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            bit_order: BitOrder::Lsb0,
+            on_progress: None,
+            reliable: false,
+            cooldown_temp: None,
+            cooldown_delay: DEFAULT_COOLDOWN_DELAY,
+            precheck: false,
+            busy_wait: None,
+            inter_chunk_delay: None,
         }
     }
 
+    /// Start building a printer with custom settings.
+    pub fn builder(transport: T) -> CatPrinterBuilder<T> {
+        CatPrinterBuilder::new(transport)
+    }
+
+    /// Set the print density and send it to the printer right away.
+    ///
+    /// - `level`: clamped to MIN_DENSITY..=MAX_DENSITY; 0x40-0x6F suits most paper,
+    ///   go higher only for glossy rolls that print too light
+    ///
+    /// The value is also re-sent at the start of every print job.
+    pub fn set_density(&mut self, level: u8) -> Result<(), PrinterError> {
+        self.density = level.clamp(MIN_DENSITY, MAX_DENSITY);
+        let req = build_density_packet(self.density);
+        self.write_control(&req)
+    }
+
     /// Query the printer for its current status (battery, temperature, state).
     ///
     /// - `timeout`: max time to wait for response
     ///
     /// Returns PrinterStatus struct
-    pub fn get_status(&mut self, timeout: Duration) -> Result<PrinterStatus, String> {
-        let req = build_control_packet(0xA1, &[0x00]);
-        self.transport.write_control(&req)?;
+    pub fn get_status(&mut self, timeout: Duration) -> Result<PrinterStatus, PrinterError> {
+        let req = build_control_packet(Command::GetStatus, &[0x00]);
+        self.write_control(&req)?;
         let raw = self.transport.read_notification(timeout)?;
-        let notif = parse_notification(&raw).map_err(|e| e.to_string())?;
+        let notif = parse_notification(&raw)?;
         Ok(parse_printer_status(&notif.payload))
     }
 
+    /// Query the battery level (0xAB).
+    ///
+    /// - `timeout`: wait for the response
+    ///
+    /// Returns the battery percent
+    pub fn get_battery(&mut self, timeout: Duration) -> Result<u8, PrinterError> {
+        let req = build_control_packet(Command::GetBattery, &[0x00]);
+        self.write_control(&req)?;
+        let notif = self.wait_for_notification(Command::GetBattery, timeout)?;
+        parse_battery(&notif.payload)
+            .ok_or_else(|| PrinterError::Protocol("Battery payload too short".to_string()))
+    }
+
+    /// Query firmware/hardware version (0xB1).
+    ///
+    /// - `timeout`: wait for the response
+    ///
+    /// Returns DeviceInfoReport on success
+    pub fn get_device_info(&mut self, timeout: Duration) -> Result<DeviceInfoReport, PrinterError> {
+        let req = build_control_packet(Command::GetDeviceInfo, &[0x00]);
+        self.write_control(&req)?;
+        let notif = self.wait_for_notification(Command::GetDeviceInfo, timeout)?;
+        Ok(parse_device_info(&notif.payload)?)
+    }
+
+    /// Print the diagnostic page described at render_test_page: alignment
+    /// marks, density wedges and the firmware, battery, temperature, state,
+    /// density and width. Queries the printer for the info lines first; those
+    /// it doesn't answer within ack_timeout print as unknown. The page is
+    /// rotated like print_text so its text reads upright.
+    pub fn print_test_page(&mut self) -> Result<(), PrinterError> {
+        let info = self.get_device_info(self.ack_timeout);
+        let status = self.get_status(self.ack_timeout);
+        let lines = test_page_info(info.ok(), status.ok(), self.density, self.width);
+        let (pixels, height) = render_test_page(&lines, self.width);
+        let rotated = transform_pixels(&pixels, self.width, height, self.text_transform);
+        self.print_image(&rotated, self.width, height, MODE_1BPP, None)
+    }
+
+    /// Advance the paper, e.g. to tear off a finished print.
+    ///
+    /// - `lines`: dot lines to feed (clamped to MAX_FEED_LINES)
+    ///
+    /// Returns Err(PrinterError::Busy) if the printer refuses
+    pub fn feed_paper(&mut self, lines: u16) -> Result<(), PrinterError> {
+        self.move_paper(Command::FeedPaper, lines)
+    }
+
+    /// Pull the paper back into the printer.
+    ///
+    /// - `lines`: dot lines to retract (clamped to MAX_FEED_LINES)
+    ///
+    /// Returns Err(PrinterError::Busy) if the printer refuses
+    pub fn retract_paper(&mut self, lines: u16) -> Result<(), PrinterError> {
+        self.move_paper(Command::RetractPaper, lines)
+    }
+
+    fn move_paper(&mut self, command: Command, lines: u16) -> Result<(), PrinterError> {
+        let req = build_feed_packet(command, lines);
+        self.write_control(&req)?;
+        let notif = self.wait_for_notification(command, self.ack_timeout)?;
+        if notif.payload.first() == Some(&0x01u8) {
+            return Err(PrinterError::Busy);
+        }
+        Ok(())
+    }
+
     /// Print text to the CatPrinter (with author signature).
     ///
     /// - `main`: main text to print
-    /// - `author`: author name
+    /// - `author`: author name; empty prints the text without a signature
     ///
-    /// Returns Ok(()) on success
-    pub fn print_text(&mut self, main: &str, author: &str) -> Result<(), String> {
-        let width = 384usize;
-        let pixels = render_text_to_pixels(main, author, width);
+    /// Returns Ok(()) on success, Err(PrinterError::Image) without printing
+    /// if the rendered text has no black pixels (e.g. both strings empty)
+    pub fn print_text(&mut self, main: &str, author: &str) -> Result<(), PrinterError> {
+        let width = self.width;
+        let pixels = render_text_to_pixels_signed(
+            main,
+            author,
+            width,
+            DEFAULT_TEXT_SIZE,
+            0,
+            &self.signature,
+        );
+        ensure_ink(&pixels)?;
         let height = pixels.len() / width;
         // Rotate and mirror text buffer for CatPrinter
-        let rotated_pixels = crate::protocol::rotate_mirror_pixels(&pixels, width, height);
-        let packed = pack_1bpp_pixels(&rotated_pixels, width, height).map_err(|e| e.to_string())?;
-
-        let line_count: u16 = height as u16;
-        let mut a9_payload = Vec::new();
-        a9_payload.extend_from_slice(&line_count.to_le_bytes());
-        a9_payload.push(0x30);
-        a9_payload.push(0x00); // mode 0 = 1bpp
-        let a9 = build_control_packet(0xA9, &a9_payload);
-        self.transport.write_control(&a9)?;
-        let resp = self.transport.read_notification(Duration::from_secs(2))?;
-        let parsed = parse_notification(&resp).map_err(|e| e.to_string())?;
-        if parsed.command_id != 0xA9 || parsed.payload.first() == Some(&0x01u8) {
-            return Err("printer rejected print request".into());
-        }
-
-        let chunks = chunk_data(&packed, self.chunk_size);
-        for chunk in chunks {
-            self.transport.write_data(chunk)?;
-        }
+        let rotated_pixels = transform_pixels(&pixels, width, height, self.text_transform);
+        self.print_image(&rotated_pixels, width, height, MODE_1BPP, None)
+    }
 
-        let ad = build_control_packet(0xAD, &[0x00]);
-        self.transport.write_control(&ad)?;
+    /// Print lightly formatted text: `# ` title lines at a larger size and
+    /// blank-line paragraph breaks, see render_formatted_to_pixels for the
+    /// syntax. Rotated like print_text.
+    ///
+    /// - `text`: formatted text
+    ///
+    /// Returns Ok(()) on success
+    pub fn print_formatted(&mut self, text: &str) -> Result<(), PrinterError> {
+        let width = self.width;
+        let pixels = render_formatted_to_pixels(text, width, DEFAULT_TEXT_SIZE);
+        ensure_ink(&pixels)?;
+        let height = pixels.len() / width;
+        let rotated = transform_pixels(&pixels, width, height, self.text_transform);
+        self.print_image(&rotated, width, height, MODE_1BPP, None)
+    }
 
-        let deadline = std::time::Instant::now() + Duration::from_secs(60);
-        loop {
-            let timeout = deadline
-                .checked_duration_since(std::time::Instant::now())
-                .unwrap_or_else(|| Duration::from_secs(0));
-            if timeout.is_zero() {
-                return Err("timed out waiting for print complete".into());
-            }
-            let raw = self.transport.read_notification(timeout)?;
-            let notif = parse_notification(&raw).map_err(|e| e.to_string())?;
-            if notif.command_id == 0xAA {
-                return Ok(());
-            }
-        }
+    /// Print images and text stacked top to bottom as one job, e.g. a logo
+    /// followed by receipt text. The page is rotated like print_text so it
+    /// reads in the same direction as text prints.
+    ///
+    /// - `elements`: layout blocks in print order
+    ///
+    /// Returns Ok(()) on success
+    pub fn print_layout(&mut self, elements: &[LayoutElement]) -> Result<(), PrinterError> {
+        let (pixels, height) = render_layout(elements, self.width)?;
+        let rotated = transform_pixels(&pixels, self.width, height, self.text_transform);
+        self.print_image(&rotated, self.width, height, MODE_1BPP, None)
     }
 
-    /// Print an image from a file path, with improved clarity and correctness.
-    /// Steps:
-    /// 1. Load image
-    /// 2. Convert to grayscale
-    /// 3. Resize/crop to printer width and reasonable height
-    /// 4. Optionally rotate/flip for correct orientation
-    /// 5. Apply dithering
-    /// 6. Pack pixels and send to printer
     /// Print an image from a file path, with optional dithering.
     ///
+    /// The image is turned upright (EXIF tag or `orientation` override), rotated
+    /// and flipped, blended onto `alpha_background` and optionally autocropped,
+    /// then run through prepare_image (grayscale, resize, sharpen, tone, dither)
+    /// before invert and padding are applied.
+    ///
     /// - `path`: path to image file
     /// - `dithering`: dithering algorithm to apply
     ///
     /// Returns Ok(()) on success
-    pub fn print_image_from_path(&mut self, path: &str, dithering: ImageDithering) -> Result<(), String> {
-        // 1. Load image
-        let img = image::open(path).map_err(|e| e.to_string())?;
-        let printer_width = 384;
-        let max_height = 800; // reasonable max height for most prints
-
-        // 2. Convert to grayscale
-        let gray = img.to_luma8();
-
-        // 3. Resize/crop to printer width and max height, center vertically if needed
-        let (orig_w, orig_h) = gray.dimensions();
-        let scale = printer_width as f32 / orig_w as f32;
-        let target_h = ((orig_h as f32) * scale).min(max_height as f32) as u32;
-        let resized = image::imageops::resize(&gray, printer_width, target_h, image::imageops::FilterType::Lanczos3);
-        let mut gray = resized;
-// Now gray is the resized grayscale image, ready for orientation and dithering.
-
-        // 4. Optionally rotate/flip for correct orientation
-        // Uncomment one of the following lines if your prints are upside down or sideways:
-        // gray = image::imageops::rotate90(&gray);
-        // gray = image::imageops::rotate180(&gray);
-        // gray = image::imageops::flip_vertical(&gray);
-        // gray = image::imageops::flip_horizontal(&gray);
-
-        // 5. Apply dithering
-        match dithering {
-            ImageDithering::FloydSteinberg => {
-                image::imageops::dither(&mut gray, &image::imageops::BiLevel);
-            }
-            ImageDithering::Atkinson => {
-                atkinson_dither(&mut gray);
-            }
-            ImageDithering::Bayer => {
-                bayer_dither(&mut gray);
-            }
-            ImageDithering::Halftone => {
-                gray = halftone_dither(&gray);
-            }
-            ImageDithering::Threshold => {
-                for pixel in gray.pixels_mut() {
-                    pixel[0] = if pixel[0] > 127 { 255 } else { 0 };
-                }
-            }
+    pub fn print_image_from_path(
+        &mut self,
+        path: &str,
+        dithering: ImageDithering,
+    ) -> Result<(), PrinterError> {
+        let img = decode_oriented(ImageReader::open(path)?, self.orientation)?;
+        self.print_dynamic_image(img, dithering)
+    }
+
+    /// Print an image from encoded bytes (PNG, JPEG, ...), with optional dithering.
+    ///
+    /// - `data`: encoded image file contents
+    /// - `dithering`: dithering algorithm to apply
+    ///
+    /// Returns Ok(()) on success
+    pub fn print_image_from_bytes(
+        &mut self,
+        data: &[u8],
+        dithering: ImageDithering,
+    ) -> Result<(), PrinterError> {
+        let img = decode_oriented(ImageReader::new(Cursor::new(data)), self.orientation)?;
+        self.print_dynamic_image(img, dithering)
+    }
+
+    /// Settings of the image pipeline steps run by prepare_image.
+    fn image_settings(&self) -> ImageSettings {
+        ImageSettings {
+            width: self.width,
+            grayscale: self.grayscale,
+            resize_mode: self.resize_mode,
+            resize_filter: self.resize_filter,
+            max_height: self.max_height,
+            unsharp: self.unsharp,
+            brightness: self.brightness,
+            contrast: self.contrast,
+            gamma: self.gamma,
+            diffusion_strength: self.diffusion_strength,
         }
+    }
 
-        // 6. Save processed image for debugging
-        let _ = gray.save("processed_for_print.png"); // Save to disk for visual inspection
+    fn print_dynamic_image(
+        &mut self,
+        img: DynamicImage,
+        dithering: ImageDithering,
+    ) -> Result<(), PrinterError> {
+        let img = rotate_flip(img, self.rotation, self.flip_h, self.flip_v);
+        let img = flatten_alpha(img, self.alpha_background);
+        let img = if self.autocrop {
+            autocrop_image(&img, self.autocrop_threshold, self.grayscale)
+        } else {
+            img
+        };
+        let (mut pixels, width, _) = prepare_image(&img, dithering, &self.image_settings());
+        if self.invert {
+            invert_pixels(&mut pixels);
+        }
+        let (pixels, height) = pad_pixels_vertical(
+            &pixels,
+            width,
+            self.pad_top.into(),
+            self.pad_bottom.into(),
+            self.min_height.into(),
+        );
+
+        if let Some(path) = &self.debug_dump_path {
+            image::save_buffer(
+                path,
+                &pixels,
+                width as u32,
+                height as u32,
+                image::ExtendedColorType::L8,
+            )?;
+        }
+
+        self.print_image(&pixels, width, height, MODE_1BPP, None)
+    }
+
+    /// Print a QR code centered on the paper, with the default error correction.
+    ///
+    /// - `data`: text or URL to encode
+    /// - `scale`: pixels per QR module
+    ///
+    /// Returns Ok(()) on success
+    pub fn print_qr(&mut self, data: &str, scale: u32) -> Result<(), PrinterError> {
+        self.print_qr_with_level(data, scale, DEFAULT_EC_LEVEL)
+    }
+
+    /// Print a QR code centered on the paper.
+    ///
+    /// - `data`: text or URL to encode
+    /// - `scale`: pixels per QR module
+    /// - `ec_level`: error-correction level
+    ///
+    /// Returns Ok(()) on success
+    pub fn print_qr_with_level(
+        &mut self,
+        data: &str,
+        scale: u32,
+        ec_level: EcLevel,
+    ) -> Result<(), PrinterError> {
+        let width = self.width;
+        let (pixels, height) = render_qr_to_pixels(data, scale, ec_level, width)?;
+        self.print_image(&pixels, width, height, MODE_1BPP, None)
+    }
 
-        // 7. Pack pixels and send to printer
-        let (width, height) = gray.dimensions();
-        let pixels = gray.as_raw();
-        self.print_image(pixels, width as usize, height as usize, 0x00, None)
+    /// Print a linear barcode with its human-readable caption underneath.
+    /// The page is rotated like print_text so the caption reads upright.
+    ///
+    /// - `data`: text to encode (12 or 13 digits for EAN-13)
+    /// - `symbology`: Barcode::Code128 or Barcode::Ean13
+    /// - `height_px`: bar height in pixels
+    ///
+    /// Returns Ok(()) on success, or Err if the data is invalid for the symbology
+    pub fn print_barcode(
+        &mut self,
+        data: &str,
+        symbology: Barcode,
+        height_px: u32,
+    ) -> Result<(), PrinterError> {
+        let (pixels, height) = render_barcode_to_pixels(data, symbology, height_px, self.width)?;
+        let rotated = transform_pixels(&pixels, self.width, height, self.text_transform);
+        self.print_image(&rotated, self.width, height, MODE_1BPP, None)
     }
 
     /// Print a raw grayscale pixel buffer as an image.
     ///
     /// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
-    /// - `width`, `height`: image dimensions
-    /// - `mode`: print mode (0x00 = 1bpp)
+    /// - `width`, `height`: image dimensions; width must be at most the printer width
+    ///   (see print_image_tiled for wider images)
+    /// - `mode`: print mode (0x00 = 1bpp, 0x02 = 4bpp grayscale)
     /// - `chunk_size`: optional override for data chunk size
     ///
     /// Returns Ok(()) on success
@@ -192,45 +674,1538 @@ impl<T: Transport> CatPrinter<T> {
         height: usize,
         mode: u8,
         chunk_size: Option<usize>,
-    ) -> Result<(), String> {
-        let packed = pack_1bpp_pixels(pixels, width, height).map_err(|e| e.to_string())?;
-
-        // Send A9
-        let line_count: u16 = height as u16;
-        let mut a9_payload = Vec::new();
-        a9_payload.extend_from_slice(&line_count.to_le_bytes());
-        a9_payload.push(0x30);
-        a9_payload.push(mode);
-        let a9 = build_control_packet(0xA9, &a9_payload);
-        self.transport.write_control(&a9)?;
-        let resp = self.transport.read_notification(Duration::from_secs(2))?;
-        let parsed = parse_notification(&resp).map_err(|e| e.to_string())?;
-        if parsed.command_id != 0xA9 || parsed.payload.first() == Some(&0x01u8) {
-            return Err("printer rejected print request".into());
-        }
+    ) -> Result<(), PrinterError> {
+        let line_count = check_line_count(height)?;
+        let packed = self.pack_job(pixels, width, height, mode)?;
+        self.begin_print(line_count, mode)?;
+        self.send_data_chunked(&packed, chunk_size.unwrap_or(self.chunk_size))?;
+        self.end_print()
+    }
+
+    /// Build the exact packets print_image would send, without sending anything.
+    /// Useful for debugging and for comparing against golden files.
+    ///
+    /// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+    /// - `width`, `height`: image dimensions
+    /// - `mode`: print mode (0x00 = 1bpp, 0x02 = 4bpp grayscale)
+    ///
+    /// Returns PrintJob with the density, 0xA9, data and 0xAD packets
+    pub fn render_image_job(
+        &self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        mode: u8,
+    ) -> Result<PrintJob, PrinterError> {
+        self.build_job(pixels, width, height, mode, self.chunk_size)
+    }
+
+    /// Show exactly what print_image would put on paper: the buffer goes
+    /// through `transform`, 1bpp packing with `bit_order` and back, so
+    /// quantization matches the real output (only pure black prints).
+    ///
+    /// Row 0 is the first line printed, so text previews appear rotated by
+    /// `text_transform`, just like the data sent to the printer.
+    ///
+    /// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+    /// - `width`, `height`: image dimensions
+    ///
+    /// Returns the preview as a GrayImage of the same size
+    pub fn preview_image(
+        &self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Result<GrayImage, PrinterError> {
+        preview_pixels(
+            pixels,
+            width,
+            height,
+            self.width,
+            self.transform,
+            self.bit_order,
+        )
+    }
+
+    /// Preview of print_text, see preview_image.
+    ///
+    /// - `main`: main text
+    /// - `author`: author name; empty prints no signature
+    ///
+    /// Returns the preview as a GrayImage `width` pixels wide
+    pub fn preview_text(&self, main: &str, author: &str) -> Result<GrayImage, PrinterError> {
+        let width = self.width;
+        let pixels = render_text_to_pixels_signed(
+            main,
+            author,
+            width,
+            DEFAULT_TEXT_SIZE,
+            0,
+            &self.signature,
+        );
+        let height = pixels.len() / width;
+        let rotated = transform_pixels(&pixels, width, height, self.text_transform);
+        self.preview_image(&rotated, width, height)
+    }
+
+    /// Print `copies` of an image in one continuous job, e.g. a sheet of labels.
+    ///
+    /// The printer handles one long job better than many short ones: each job
+    /// costs an 0xA9/0xAA handshake and the paper stops between jobs, which
+    /// can leave a visible seam. So the packed rows are repeated with
+    /// `gap_lines` blank rows in between and sent as a single job.
+    ///
+    /// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+    /// - `width`, `height`: image dimensions
+    /// - `mode`: print mode (0x00 = 1bpp, 0x02 = 4bpp grayscale)
+    /// - `copies`: number of copies, at least 1
+    /// - `gap_lines`: blank rows between copies
+    ///
+    /// Returns Err(PrinterError::Image) if copies is 0 or the whole run exceeds
+    /// 65535 lines
+    pub fn print_image_copies(
+        &mut self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        mode: u8,
+        copies: usize,
+        gap_lines: u16,
+    ) -> Result<(), PrinterError> {
+        let packed = self.pack_job(pixels, width, height, mode)?;
+        let row_bytes = packed_row_bytes(width, mode)?;
+        let (data, line_count) = repeat_packed_rows(&packed, row_bytes, copies, gap_lines)
+            .map_err(|e| PrinterError::Image(e.into()))?;
+        self.begin_print(line_count, mode)?;
+        self.send_data(&data)?;
+        self.end_print()
+    }
+
+    /// Validates, transforms and packs a pixel buffer as print_image sends it.
+    fn pack_job(
+        &self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        mode: u8,
+    ) -> Result<Vec<u8>, PrinterError> {
+        check_print_width(width, self.width)?;
+        check_pixel_buffer(pixels, width, height)?;
+        let packed = match self.transform {
+            Transform::None => {
+                pack_pixels_for_mode_ordered(pixels, width, height, mode, self.bit_order)?
+            }
+            t => pack_pixels_for_mode_ordered(
+                &transform_pixels(pixels, width, height, t),
+                width,
+                height,
+                mode,
+                self.bit_order,
+            )?,
+        };
+        Ok(packed)
+    }
+
+    fn build_job(
+        &self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        mode: u8,
+        chunk_size: usize,
+    ) -> Result<PrintJob, PrinterError> {
+        let line_count = check_line_count(height)?;
+        let packed = self.pack_job(pixels, width, height, mode)?;
+        Ok(PrintJob {
+            density_packet: build_density_packet(self.density),
+            request_packet: build_print_request_packet(line_count, mode),
+            data_chunks: chunk_data_iter(&packed, chunk_size)
+                .map(|c| c.to_vec())
+                .collect(),
+            end_packet: build_data_end_packet(),
+        })
+    }
+
+    /// Print several printer-width images as a single print job.
+    ///
+    /// - `images`: (pixels, width, height) for each image, top to bottom
+    /// - `mode`: print mode (0x00 = 1bpp, 0x02 = 4bpp grayscale)
+    ///
+    /// Returns Ok(()) on success
+    pub fn print_images(
+        &mut self,
+        images: &[(&[u8], usize, usize)],
+        mode: u8,
+    ) -> Result<(), PrinterError> {
+        self.print_images_with_gap(images, mode, 0)
+    }
+
+    /// Print several printer-width images as a single print job, separated by blank rows.
+    ///
+    /// - `images`: (pixels, width, height) for each image, top to bottom
+    /// - `mode`: print mode (0x00 = 1bpp, 0x02 = 4bpp grayscale)
+    /// - `gap`: blank rows between images
+    ///
+    /// Returns Ok(()) on success
+    pub fn print_images_with_gap(
+        &mut self,
+        images: &[(&[u8], usize, usize)],
+        mode: u8,
+        gap: usize,
+    ) -> Result<(), PrinterError> {
+        let width = self.width;
+        let (pixels, height) = stack_images(images, width, gap)?;
+        self.print_image(&pixels, width, height, mode, None)
+    }
+
+    /// Print an image wider than the printer by cutting it into printer-width vertical
+    /// tiles and printing them one below the other, left tile first.
+    ///
+    /// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+    /// - `width`, `height`: image dimensions (any width)
+    /// - `mode`: print mode (0x00 = 1bpp, 0x02 = 4bpp grayscale)
+    /// - `separator`: blank rows printed between tiles
+    ///
+    /// Returns Ok(()) on success
+    pub fn print_image_tiled(
+        &mut self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        mode: u8,
+        separator: usize,
+    ) -> Result<(), PrinterError> {
+        let tiles = split_into_tiles(pixels, width, height, self.width)?;
+        let images: Vec<(&[u8], usize, usize)> = tiles
+            .iter()
+            .map(|t| (t.as_slice(), self.width, height))
+            .collect();
+        self.print_images_with_gap(&images, mode, separator)
+    }
+
+    /// Print a raw grayscale pixel buffer in 1bpp mode, packing and sending a
+    /// few rows at a time instead of building the whole packed image first.
+    /// Sends exactly the same bytes and chunks as print_image, except that the
+    /// transform setting is not applied; pass pixels already oriented.
+    ///
+    /// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+    /// - `width`, `height`: image dimensions
+    /// - `chunk_size`: optional override for data chunk size
+    ///
+    /// Returns Ok(()) on success
+    pub fn print_image_streaming(
+        &mut self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        chunk_size: Option<usize>,
+    ) -> Result<(), PrinterError> {
+        check_print_width(width, self.width)?;
+        check_pixel_buffer(pixels, width, height)?;
+        self.begin_print(check_line_count(height)?, MODE_1BPP)?;
 
         let size = chunk_size.unwrap_or(self.chunk_size);
-        let chunks = chunk_data(&packed, size);
-        for chunk in chunks {
-            self.transport.write_data(chunk)?;
+        let bytes_per_row = width.div_ceil(8);
+        let rows_per_batch = if size == 0 {
+            height
+        } else {
+            (size / bytes_per_row).max(1)
+        };
+        let mut buf = Vec::with_capacity(size.max(bytes_per_row) + bytes_per_row * rows_per_batch);
+        let mut row = 0;
+        while row < height {
+            let end = usize::min(row + rows_per_batch, height);
+            pack_rows_into_ordered(pixels, width, row..end, &mut buf, self.bit_order);
+            row = end;
+            if size > 0 {
+                let mut sent = 0;
+                for chunk in buf.chunks_exact(size) {
+                    self.write_data(chunk)?;
+                    sent += size;
+                }
+                buf.drain(..sent);
+            }
+        }
+        if !buf.is_empty() {
+            self.write_data(&buf)?;
+        }
+        self.end_print()
+    }
+
+    /// Print rows that are already packed in the printer's format, e.g. by
+    /// pack_pixels_for_mode or an external pipeline, without repacking them.
+    ///
+    /// - `packed`: exactly `line_count` rows of `width` pixels, the configured
+    ///   print width (packed_row_bytes(width, mode) bytes each, e.g. 48 in 1bpp
+    ///   and 192 in 4bpp at PRINTER_WIDTH); transform and invert are not applied
+    /// - `line_count`: rows in `packed`
+    /// - `mode`: print mode (0x00 = 1bpp, 0x02 = 4bpp grayscale)
+    ///
+    /// Returns Ok(()) once the printer reports print complete
+    pub fn print_packed(
+        &mut self,
+        packed: &[u8],
+        line_count: u16,
+        mode: u8,
+    ) -> Result<(), PrinterError> {
+        let expected = packed_row_bytes(self.width, mode)? * line_count as usize;
+        if line_count == 0 || packed.len() != expected {
+            return Err(PrinterError::Image(format!(
+                "packed data is {} bytes, {} lines need {}",
+                packed.len(),
+                line_count,
+                expected
+            )));
         }
-        let ad = build_control_packet(0xAD, &[0x00]);
-        self.transport.write_control(&ad)?;
+        self.begin_print(line_count, mode)?;
+        self.send_data(packed)?;
+        self.end_print()
+    }
+
+    /// Start a print job by hand: sends density and the 0xA9 print request and
+    /// checks the printer accepted it. Follow with send_data and end_print.
+    ///
+    /// The printer needs the line count up front. When content is generated
+    /// lazily and the total height is unknown, declare an upper bound and pad
+    /// with white rows up to it before end_print.
+    ///
+    /// - `line_count`: rows that will be sent
+    /// - `mode`: print mode (0x00 = 1bpp, 0x02 = 4bpp grayscale)
+    ///
+    /// Returns Ok(()) if the printer accepted the job
+    pub fn begin_print(&mut self, line_count: u16, mode: u8) -> Result<(), PrinterError> {
+        self.send_print_request(
+            &build_density_packet(self.density),
+            &build_print_request_packet(line_count, mode),
+        )
+    }
 
-        let deadline = std::time::Instant::now() + Duration::from_secs(60);
+    /// Send packed row data for a job started with begin_print, split into
+    /// chunk_size writes. Pack rows with pack_pixels_for_mode first; any
+    /// number of calls is fine as long as rows aren't split across modes.
+    ///
+    /// - `data`: packed rows
+    pub fn send_data(&mut self, data: &[u8]) -> Result<(), PrinterError> {
+        self.send_data_chunked(data, self.chunk_size)
+    }
+
+    /// send_data with an explicit chunk size, for print_image's override.
+    fn send_data_chunked(&mut self, data: &[u8], chunk_size: usize) -> Result<(), PrinterError> {
+        for chunk in chunk_data_iter(data, chunk_size) {
+            self.write_data(chunk)?;
+        }
+        Ok(())
+    }
+
+    /// Finish a job started with begin_print: sends 0xAD and waits up to
+    /// print_timeout for the 0xAA print complete notification.
+    pub fn end_print(&mut self) -> Result<(), PrinterError> {
+        self.finish_print(&build_data_end_packet(), self.print_timeout)
+    }
+
+    /// Writes a control packet, retrying up to `retries` times if the transport
+    /// returns an error. Retries are per packet: a write that succeeded is
+    /// never sent again.
+    fn write_control(&mut self, data: &[u8]) -> Result<(), PrinterError> {
+        let mut attempt = 0;
+        loop {
+            match self.transport.write_control(data) {
+                Err(_) if attempt < self.retries => {
+                    attempt += 1;
+                    std::thread::sleep(self.retry_delay);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Writes a data chunk with the same per-packet retry policy as write_control,
+    /// acknowledged and with max(retries, RELIABLE_RETRIES) retries in reliable mode.
+    fn write_data(&mut self, data: &[u8]) -> Result<(), PrinterError> {
+        let retries = if self.reliable {
+            self.retries.max(RELIABLE_RETRIES)
+        } else {
+            self.retries
+        };
+        let mut attempt = 0;
+        loop {
+            let result = if self.reliable {
+                self.transport.write_data_acked(data)
+            } else {
+                self.transport.write_data(data)
+            };
+            match result {
+                Err(_) if attempt < retries => {
+                    attempt += 1;
+                    std::thread::sleep(self.retry_delay);
+                }
+                Ok(()) => {
+                    if let Some(delay) = self.inter_chunk_delay {
+                        std::thread::sleep(delay);
+                    }
+                    return Ok(());
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Sends density and the 0xA9 print request, then checks the printer accepted it.
+    fn send_print_request(&mut self, density: &[u8], request: &[u8]) -> Result<(), PrinterError> {
+        if let Some(limit) = self.cooldown_temp {
+            self.wait_for_cooldown(limit)?;
+        }
+        if self.precheck {
+            self.get_status(self.ack_timeout)?.can_print()?;
+        }
+        match (self.try_print_request(density, request), self.busy_wait) {
+            (Err(PrinterError::Busy), Some(wait)) => {
+                self.wait_until_ready(wait)?;
+                self.try_print_request(density, request)
+            }
+            (result, _) => result,
+        }
+    }
+
+    /// One density + 0xA9 exchange; a rejection is explained by a status query.
+    fn try_print_request(&mut self, density: &[u8], request: &[u8]) -> Result<(), PrinterError> {
+        self.write_control(density)?;
+        self.write_control(request)?;
+        let parsed = self.wait_for_notification(Command::PrintStart, self.ack_timeout)?;
+        if parsed.payload.first() == Some(&0x01u8) {
+            return Err(match self.get_status(self.ack_timeout) {
+                Ok(status) => status.rejection_reason(),
+                Err(_) => PrinterError::Rejected,
+            });
+        }
+        Ok(())
+    }
+
+    /// Poll the status every READY_POLL_INTERVAL until the printer is idle
+    /// (Standby), e.g. before retrying a job rejected with Busy.
+    ///
+    /// - `timeout`: longest time to wait
+    ///
+    /// Returns Ok(()) once idle, or Err(PrinterError::Timeout) if it is still
+    /// printing or in an error state when `timeout` runs out
+    pub fn wait_until_ready(&mut self, timeout: Duration) -> Result<(), PrinterError> {
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            if self.get_status(self.ack_timeout)?.state == PrinterState::Standby {
+                return Ok(());
+            }
+            if std::time::Instant::now() + READY_POLL_INTERVAL > deadline {
+                return Err(PrinterError::Timeout(timeout));
+            }
+            std::thread::sleep(READY_POLL_INTERVAL);
+        }
+    }
+
+    /// Stop the job the printer is working on: sends 0xAC (cancel print), which
+    /// drops the job and any data it has buffered, then polls status until
+    /// the printer reports Standby.
+    ///
+    /// The printer doesn't acknowledge 0xAC, so the status polls are the only
+    /// confirmation; stray progress notifications of the dropped job are
+    /// skipped. Safe to call when nothing is printing.
+    ///
+    /// Returns Ok(()) once idle, or Err(PrinterError::Timeout) if the printer
+    /// isn't back in Standby within ack_timeout
+    pub fn cancel_print(&mut self) -> Result<(), PrinterError> {
+        self.write_control(&build_cancel_packet())?;
+        let deadline = std::time::Instant::now() + self.ack_timeout;
+        loop {
+            self.write_control(&build_control_packet(Command::GetStatus, &[0x00]))?;
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            let notif = self
+                .wait_for_notification(Command::GetStatus, remaining)
+                .map_err(|e| e.timed_out_after(self.ack_timeout))?;
+            if parse_printer_status(&notif.payload).state == PrinterState::Standby {
+                return Ok(());
+            }
+            if std::time::Instant::now() + READY_POLL_INTERVAL > deadline {
+                return Err(PrinterError::Timeout(self.ack_timeout));
+            }
+            std::thread::sleep(READY_POLL_INTERVAL);
+        }
+    }
+
+    /// Reads notifications until one with `command_id` arrives, skipping
+    /// unrelated frames such as stray status reports.
+    ///
+    /// Returns the matching notification, or Err(PrinterError::Timeout) if none
+    /// arrives within `timeout`
+    fn wait_for_notification(
+        &mut self,
+        command: Command,
+        timeout: Duration,
+    ) -> Result<Notification, PrinterError> {
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            let remaining = deadline
+                .checked_duration_since(std::time::Instant::now())
+                .unwrap_or_else(|| Duration::from_secs(0));
+            if remaining.is_zero() {
+                return Err(PrinterError::Timeout(timeout));
+            }
+            let raw = self
+                .transport
+                .read_notification(remaining)
+                .map_err(|e| e.timed_out_after(timeout))?;
+            let notif = parse_notification(&raw)?;
+            if notif.command_id == u8::from(command) {
+                return Ok(notif);
+            }
+        }
+    }
+
+    /// Polls status every cooldown_delay until the head temperature is at or
+    /// below `limit`, giving up with NotReady(Overheated) after print_timeout.
+    fn wait_for_cooldown(&mut self, limit: u8) -> Result<(), PrinterError> {
+        let deadline = std::time::Instant::now() + self.print_timeout;
+        loop {
+            let status = self.get_status(self.ack_timeout)?;
+            if status.temperature.is_none_or(|t| t <= limit) {
+                return Ok(());
+            }
+            if std::time::Instant::now() + self.cooldown_delay > deadline {
+                return Err(PrinterError::NotReady(PrinterErrorCode::Overheated));
+            }
+            std::thread::sleep(self.cooldown_delay);
+        }
+    }
+
+    /// Sends 0xAD (data done) and waits up to `print_timeout` for the 0xAA
+    /// print complete notification.
+    fn finish_print(&mut self, end: &[u8], print_timeout: Duration) -> Result<(), PrinterError> {
+        self.write_control(end)?;
+
+        let deadline = std::time::Instant::now() + print_timeout;
         loop {
             let timeout = deadline
                 .checked_duration_since(std::time::Instant::now())
                 .unwrap_or_else(|| Duration::from_secs(0));
             if timeout.is_zero() {
-                return Err("timed out waiting for print complete".into());
+                return Err(PrinterError::Timeout(print_timeout));
             }
-            let raw = self.transport.read_notification(timeout)?;
-            let notif = parse_notification(&raw).map_err(|e| e.to_string())?;
-            if notif.command_id == 0xAA {
-                return Ok(());
+            let raw = self
+                .transport
+                .read_notification(timeout)
+                .map_err(|e| e.timed_out_after(print_timeout))?;
+            let notif = parse_notification(&raw)?;
+            match Command::try_from(notif.command_id) {
+                Ok(Command::PrintComplete) => return Ok(()),
+                Ok(Command::PrintProgress) => {
+                    if let (Some(lines), Some(cb)) =
+                        (parse_print_progress(&notif.payload), &mut self.on_progress)
+                    {
+                        cb(lines);
+                    }
+                }
+                _ => {}
             }
         }
     }
 }
 
+/// Decodes an image and rotates/flips it upright.
+///
+/// - `reader`: image source
+/// - `orientation`: forced orientation; None reads the EXIF tag, falling back
+///   to no rotation when it is absent or unreadable
+///
+/// Returns the upright DynamicImage
+pub(crate) fn decode_oriented<R: BufRead + Seek>(
+    reader: ImageReader<R>,
+    orientation: Option<Orientation>,
+) -> Result<DynamicImage, PrinterError> {
+    let mut decoder = reader.with_guessed_format()?.into_decoder()?;
+    let exif = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut img = DynamicImage::from_decoder(decoder)?;
+    img.apply_orientation(orientation.unwrap_or(exif));
+    Ok(img)
+}
+
+/// Info lines of the test page, "unknown" for whatever the printer didn't report.
+pub(crate) fn test_page_info(
+    info: Option<DeviceInfoReport>,
+    status: Option<PrinterStatus>,
+    density: u8,
+    width: usize,
+) -> Vec<String> {
+    let unknown = || "unknown".to_string();
+    let status = status.as_ref();
+    vec![
+        format!(
+            "Firmware: {}",
+            info.map_or_else(unknown, |i| i.firmware_version)
+        ),
+        format!(
+            "Battery: {}",
+            status
+                .and_then(|s| s.battery_percent)
+                .map_or_else(unknown, |b| format!("{}%", b))
+        ),
+        format!(
+            "Temperature: {}",
+            status
+                .and_then(|s| s.temperature)
+                .map_or_else(unknown, |t| t.to_string())
+        ),
+        format!(
+            "State: {}",
+            status.map_or_else(unknown, |s| format!("{:?}", s.state))
+        ),
+        format!("Density: 0x{:02X}", density),
+        format!("Width: {} px", width),
+    ]
+}
+
+/// Refuses rendered content without a single black pixel, which would only
+/// feed blank paper.
+pub(crate) fn ensure_ink(pixels: &[u8]) -> Result<(), PrinterError> {
+    if pixels.contains(&0) {
+        Ok(())
+    } else {
+        Err(PrinterError::Image("nothing to print".into()))
+    }
+}
+
+/// Runs a buffer through the print transform and a 1bpp pack/unpack round trip.
+///
+/// Returns the preview GrayImage, or Err if the buffer is invalid or wider
+/// than `head_width`
+pub(crate) fn preview_pixels(
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+    head_width: usize,
+    transform: Transform,
+    bit_order: BitOrder,
+) -> Result<GrayImage, PrinterError> {
+    check_print_width(width, head_width)?;
+    check_pixel_buffer(pixels, width, height)?;
+    let transformed = transform_pixels(pixels, width, height, transform);
+    let packed = pack_1bpp_pixels_ordered(&transformed, width, height, bit_order)?;
+    let unpacked = unpack_1bpp_pixels_ordered(&packed, width, height, bit_order)?;
+    GrayImage::from_raw(width as u32, height as u32, unpacked)
+        .ok_or_else(|| PrinterError::Image("preview buffer size mismatch".into()))
+}
+
+/// The part of a printer's configuration prepare_image needs, so CatPrinter
+/// and CatPrinterAsync run loaded images through the same pipeline.
+pub(crate) struct ImageSettings {
+    pub width: usize,
+    pub grayscale: GrayscaleMode,
+    pub resize_mode: ResizeMode,
+    pub resize_filter: Option<FilterType>,
+    pub max_height: u32,
+    pub unsharp: Option<(f32, i32)>,
+    pub brightness: i16,
+    pub contrast: f32,
+    pub gamma: f32,
+    pub diffusion_strength: f32,
+}
+
+/// Shared image pipeline of both printers: grayscale, resize to the printer
+/// width (fitting max_height per resize_mode), unsharp mask,
+/// brightness/contrast, gamma, dither.
+///
+/// - `img`: decoded source image
+/// - `dithering`: dithering algorithm to apply
+/// - `settings`: the printer's image settings
+///
+/// Returns (pixels, width, height) ready for print_image
+pub(crate) fn prepare_image(
+    img: &DynamicImage,
+    dithering: ImageDithering,
+    settings: &ImageSettings,
+) -> (Vec<u8>, usize, usize) {
+    let printer_width = settings.width as u32;
+
+    let gray = to_grayscale(img, settings.grayscale);
+
+    // Resize/crop to printer width and max height
+    let (orig_w, orig_h) = gray.dimensions();
+    let scale = printer_width as f32 / orig_w as f32;
+    let scaled_h = (((orig_h as f32) * scale) as u32).max(1);
+    let max_height = settings.max_height.max(1);
+    let filter = settings
+        .resize_filter
+        .unwrap_or_else(|| default_resize_filter(dithering));
+    let mut gray = match settings.resize_mode {
+        ResizeMode::FitWidth => {
+            image::imageops::resize(&gray, printer_width, scaled_h.min(max_height), filter)
+        }
+        ResizeMode::CropTop | ResizeMode::CropCenter => {
+            let resized = image::imageops::resize(&gray, printer_width, scaled_h, filter);
+            let crop_h = scaled_h.min(max_height);
+            let top = match settings.resize_mode {
+                ResizeMode::CropCenter => (scaled_h - crop_h) / 2,
+                _ => 0,
+            };
+            image::imageops::crop_imm(&resized, 0, top, printer_width, crop_h).to_image()
+        }
+    };
+
+    // Sharpen, adjust brightness/contrast, correct gamma, then apply dithering
+    if let Some((sigma, threshold)) = settings.unsharp {
+        gray = image::imageops::unsharpen(&gray, sigma, threshold);
+    }
+    apply_brightness_contrast(&mut gray, settings.brightness, settings.contrast);
+    apply_gamma(&mut gray, settings.gamma);
+    apply_dithering_strength(&mut gray, dithering, settings.diffusion_strength);
+
+    let (width, height) = gray.dimensions();
+    (gray.into_raw(), width as usize, height as usize)
+}
+
+/// Scaling filter used when none is configured: line art skipping dithering
+/// keeps hard edges with nearest-neighbor scaling, everything else gets Lanczos3.
+pub(crate) fn default_resize_filter(dithering: ImageDithering) -> FilterType {
+    match dithering {
+        ImageDithering::None => FilterType::Nearest,
+        _ => FilterType::Lanczos3,
+    }
+}
+
+/// Applies a user rotation, then horizontal and vertical mirroring.
+///
+/// - `img`: upright decoded image
+/// - `rotation`: clockwise rotation
+/// - `flip_h`, `flip_v`: mirror left-right / top-bottom after rotating
+///
+/// Returns the transformed image
+pub(crate) fn rotate_flip(
+    img: DynamicImage,
+    rotation: Rotation,
+    flip_h: bool,
+    flip_v: bool,
+) -> DynamicImage {
+    let img = match rotation {
+        Rotation::None => img,
+        Rotation::Cw90 => img.rotate90(),
+        Rotation::Cw180 => img.rotate180(),
+        Rotation::Cw270 => img.rotate270(),
+    };
+    let img = if flip_h { img.fliph() } else { img };
+    if flip_v {
+        img.flipv()
+    } else {
+        img
+    }
+}
+
+/// Rejects rows wider than the print head instead of sending garbled data.
+pub(crate) fn check_print_width(width: usize, head_width: usize) -> Result<(), PrinterError> {
+    if width > head_width {
+        return Err(PrinterError::Image(format!(
+            "image is {}px wide but the print head is only {}px wide; \
+             resize it or use print_image_tiled",
+            width, head_width
+        )));
+    }
+    Ok(())
+}
+
+/// Rejects images taller than the 65535 lines a print request can announce;
+/// a larger height would wrap around in the u16 line count and print garbage.
+/// The MXW01 has no multi-segment job, so split such images into several
+/// print_image calls.
+///
+/// Returns the height as the u16 line count
+pub(crate) fn check_line_count(height: usize) -> Result<u16, PrinterError> {
+    u16::try_from(height).map_err(|_| {
+        PrinterError::Image(format!(
+            "image too tall: {} lines exceeds u16 max ({})",
+            height,
+            u16::MAX
+        ))
+    })
+}
+
+/// Fluent builder for CatPrinter; unset options keep CatPrinter::new defaults.
+pub struct CatPrinterBuilder<T: Transport> {
+    printer: CatPrinter<T>,
+}
+
+impl<T: Transport> CatPrinterBuilder<T> {
+    pub fn new(transport: T) -> Self {
+        Self {
+            printer: CatPrinter::new(transport),
+        }
+    }
+
+    /// Bytes per data chunk.
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.printer.chunk_size = size;
+        self
+    }
+
+    /// Print density, clamped to MIN_DENSITY..=MAX_DENSITY.
+    pub fn density(mut self, level: u8) -> Self {
+        self.printer.density = level.clamp(MIN_DENSITY, MAX_DENSITY);
+        self
+    }
+
+    /// Print head width in pixels: 384 for the MXW01, 576 for wide-head
+    /// models. It must match the printer; a wrong value shifts every row and
+    /// produces a skewed, garbled print.
+    pub fn width(mut self, width: usize) -> Self {
+        self.printer.width = width;
+        self
+    }
+
+    /// How long to wait for the print complete notification.
+    pub fn print_timeout(mut self, timeout: Duration) -> Self {
+        self.printer.print_timeout = timeout;
+        self
+    }
+
+    /// How long to wait for command acknowledgements.
+    pub fn ack_timeout(mut self, timeout: Duration) -> Self {
+        self.printer.ack_timeout = timeout;
+        self
+    }
+
+    /// How images taller than max height are fitted.
+    pub fn resize_mode(mut self, mode: ResizeMode) -> Self {
+        self.printer.resize_mode = mode;
+        self
+    }
+
+    /// Scale loaded images with this filter instead of picking one from the
+    /// dithering mode; see CatPrinter for the tradeoffs.
+    pub fn resize_filter(mut self, filter: FilterType) -> Self {
+        self.printer.resize_filter = Some(filter);
+        self
+    }
+
+    /// Maximum image height in pixels.
+    pub fn max_height(mut self, height: u32) -> Self {
+        self.printer.max_height = height;
+        self
+    }
+
+    /// White rows added above and below loaded images.
+    pub fn padding(mut self, top: u16, bottom: u16) -> Self {
+        self.printer.pad_top = top;
+        self.printer.pad_bottom = bottom;
+        self
+    }
+
+    /// Center loaded images vertically on at least this many rows.
+    pub fn min_height(mut self, height: u16) -> Self {
+        self.printer.min_height = height;
+        self
+    }
+
+    /// Sharpen loaded images after resizing with an unsharp mask.
+    ///
+    /// - `sigma`: blur radius of the mask, e.g. 1.0
+    /// - `threshold`: minimum difference that gets sharpened, e.g. 4
+    pub fn unsharp(mut self, sigma: f32, threshold: i32) -> Self {
+        self.printer.unsharp = Some((sigma, threshold));
+        self
+    }
+
+    /// Brightness and contrast applied to images before gamma and dithering;
+    /// see apply_brightness_contrast.
+    pub fn brightness_contrast(mut self, brightness: i16, contrast: f32) -> Self {
+        self.printer.brightness = brightness;
+        self.printer.contrast = contrast;
+        self
+    }
+
+    /// Gamma correction applied to images before dithering; see apply_gamma.
+    pub fn gamma(mut self, gamma: f32) -> Self {
+        self.printer.gamma = gamma;
+        self
+    }
+
+    /// Share of the error the diffusion dithers propagate; see
+    /// apply_dithering_strength.
+    pub fn diffusion_strength(mut self, strength: f32) -> Self {
+        self.printer.diffusion_strength = strength;
+        self
+    }
+
+    /// Set the separator and spacing print_text puts before the author.
+    pub fn signature(mut self, signature: SignatureStyle) -> Self {
+        self.printer.signature = signature;
+        self
+    }
+
+    /// Color transparent areas of loaded images are blended onto; white
+    /// leaves them unprinted.
+    pub fn alpha_background(mut self, background: image::Rgb<u8>) -> Self {
+        self.printer.alpha_background = background;
+        self
+    }
+
+    /// Choose how loaded color images are converted to gray.
+    pub fn grayscale(mut self, mode: GrayscaleMode) -> Self {
+        self.printer.grayscale = mode;
+        self
+    }
+
+    /// Force a rotation/flip for loaded images instead of reading EXIF,
+    /// e.g. for PNGs that have no orientation tag.
+    pub fn orientation(mut self, orientation: Orientation) -> Self {
+        self.printer.orientation = Some(orientation);
+        self
+    }
+
+    /// Rotate loaded images clockwise after EXIF orientation is applied.
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.printer.rotation = rotation;
+        self
+    }
+
+    /// Mirror loaded images after rotation: left-right and/or top-bottom.
+    pub fn flip(mut self, flip_h: bool, flip_v: bool) -> Self {
+        self.printer.flip_h = flip_h;
+        self.printer.flip_v = flip_v;
+        self
+    }
+
+    /// Orientation change applied to every printed job, e.g. FlipH for iron-on transfers.
+    pub fn transform(mut self, transform: Transform) -> Self {
+        self.printer.transform = transform;
+        self
+    }
+
+    /// Orientation of rendered text (print_text, print_layout, print_barcode).
+    pub fn text_transform(mut self, transform: Transform) -> Self {
+        self.printer.text_transform = transform;
+        self
+    }
+
+    /// Invert images after dithering so they print white-on-black.
+    pub fn invert(mut self, invert: bool) -> Self {
+        self.printer.invert = invert;
+        self
+    }
+
+    /// Save processed images to this path before printing.
+    pub fn debug_dump_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.printer.debug_dump_path = Some(path.into());
+        self
+    }
+
+    /// Retry each failed packet write up to `retries` times, `delay` apart.
+    /// Applies per packet, not per job: a failed chunk is resent on its own.
+    pub fn retries(mut self, retries: usize, delay: Duration) -> Self {
+        self.printer.retries = retries;
+        self.printer.retry_delay = delay;
+        self
+    }
+
+    /// Pause after every data chunk write. Slows printing down; use it only
+    /// if a printer drops bands because chunks arrive faster than its
+    /// firmware can buffer them (a few milliseconds is usually enough).
+    pub fn inter_chunk_delay(mut self, delay: Duration) -> Self {
+        self.printer.inter_chunk_delay = Some(delay);
+        self
+    }
+
+    /// Bit order for packed 1bpp rows; Msb0 fixes prints scrambled within each byte.
+    pub fn bit_order(mut self, bit_order: BitOrder) -> Self {
+        self.printer.bit_order = bit_order;
+        self
+    }
+
+    /// Report print progress (lines printed so far) while a job prints.
+    pub fn on_progress(mut self, callback: impl FnMut(u16) + Send + 'static) -> Self {
+        self.printer.on_progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Acknowledge and retransmit data chunks; see RELIABLE_RETRIES.
+    pub fn reliable(mut self, reliable: bool) -> Self {
+        self.printer.reliable = reliable;
+        self
+    }
+
+    /// Crop loaded images to their non-white content before resizing.
+    ///
+    /// - `enabled`: turn autocrop on or off
+    /// - `white_threshold`: gray level at or above which pixels count as background
+    pub fn autocrop(mut self, enabled: bool, white_threshold: u8) -> Self {
+        self.printer.autocrop = enabled;
+        self.printer.autocrop_threshold = white_threshold;
+        self
+    }
+
+    /// Wait before each job while the print head is hotter than `temp`,
+    /// polling status every `delay`. Slower, but avoids mid-print stalls on
+    /// back-to-back long prints.
+    pub fn cooldown(mut self, temp: u8, delay: Duration) -> Self {
+        self.printer.cooldown_temp = Some(temp);
+        self.printer.cooldown_delay = delay;
+        self
+    }
+
+    /// Check the printer status before each job and fail fast with NotReady.
+    pub fn precheck(mut self, precheck: bool) -> Self {
+        self.printer.precheck = precheck;
+        self
+    }
+
+    /// When a print request is rejected as Busy, wait up to `timeout` for the
+    /// printer to become idle and send it once more.
+    pub fn busy_wait(mut self, timeout: Duration) -> Self {
+        self.printer.busy_wait = Some(timeout);
+        self
+    }
+
+    pub fn build(self) -> CatPrinter<T> {
+        self.printer
+    }
+}
+
+/// Crops a decoded image to its non-white content. The content box is found
+/// on a grayscale copy made with `mode`, but the original image is cropped,
+/// so the later grayscale conversion still sees the colors. A blank image
+/// becomes a single white row so the job still prints.
+///
+/// - `img`: decoded source image
+/// - `white_threshold`: gray levels at or above this count as background
+/// - `mode`: grayscale conversion used to judge the background
+///
+/// Returns the cropped image
+pub(crate) fn autocrop_image(
+    img: &DynamicImage,
+    white_threshold: u8,
+    mode: GrayscaleMode,
+) -> DynamicImage {
+    let gray = to_grayscale(img, mode);
+    let (w, h) = gray.dimensions();
+    match content_bounding_box(gray.as_raw(), w as usize, h as usize, white_threshold) {
+        Some((x, y, cw, ch)) => img.crop_imm(x as u32, y as u32, cw as u32, ch as u32),
+        None => DynamicImage::ImageLuma8(GrayImage::from_pixel(w.max(1), 1, Luma([255]))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::MockTransport;
+
+    fn printer() -> CatPrinter<MockTransport> {
+        CatPrinter::new(MockTransport::new())
+    }
+
+    #[test]
+    fn set_density_sends_and_keeps_the_clamped_level() {
+        let mut p = printer();
+        p.set_density(0x60).unwrap();
+        p.set_density(0xF0).unwrap();
+        assert_eq!(
+            p.transport.control_writes,
+            [
+                vec![
+                    0x22,
+                    0x21,
+                    0xA2,
+                    0x00,
+                    0x01,
+                    0x00,
+                    0x60,
+                    crc8(&[0x60]),
+                    0xFF
+                ],
+                vec![
+                    0x22,
+                    0x21,
+                    0xA2,
+                    0x00,
+                    0x01,
+                    0x00,
+                    0x7F,
+                    crc8(&[0x7F]),
+                    0xFF
+                ],
+            ]
+        );
+        assert_eq!(p.density, MAX_DENSITY);
+
+        p.transport.control_writes.clear();
+        p.transport.push_print_success();
+        p.print_image(&[0; 8], 8, 1, MODE_1BPP, None).unwrap();
+        assert_eq!(
+            p.transport.control_writes[0],
+            build_density_packet(MAX_DENSITY)
+        );
+    }
+
+    /// Deterministic test pattern, neither all black nor all white.
+    fn pattern(width: usize, height: usize) -> Vec<u8> {
+        (0..width * height)
+            .map(|i| if (i * 7 + i / width) % 5 < 2 { 0 } else { 255 })
+            .collect()
+    }
+
+    #[test]
+    fn streaming_sends_the_same_chunks_as_print_image() {
+        for (width, height, chunk_size) in [(100, 37, Some(50)), (384, 20, None), (9, 3, Some(1))] {
+            let pixels = pattern(width, height);
+            let mut whole = printer();
+            whole.transport.push_print_success();
+            whole
+                .print_image(&pixels, width, height, MODE_1BPP, chunk_size)
+                .unwrap();
+            let mut streamed = printer();
+            streamed.transport.push_print_success();
+            streamed
+                .print_image_streaming(&pixels, width, height, chunk_size)
+                .unwrap();
+            assert_eq!(whole.transport.data_writes, streamed.transport.data_writes);
+            assert_eq!(
+                whole.transport.control_writes,
+                streamed.transport.control_writes
+            );
+        }
+    }
+
+    #[test]
+    fn print_images_is_one_job() {
+        let mut p = printer();
+        p.transport.push_print_success();
+        let a = vec![0u8; PRINTER_WIDTH * 2];
+        let b = vec![255u8; PRINTER_WIDTH * 3];
+        p.print_images_with_gap(
+            &[(&a, PRINTER_WIDTH, 2), (&b, PRINTER_WIDTH, 3)],
+            MODE_1BPP,
+            4,
+        )
+        .unwrap();
+        let requests: Vec<_> = p
+            .transport
+            .control_writes
+            .iter()
+            .filter(|w| w[2] == u8::from(Command::PrintStart))
+            .collect();
+        assert_eq!(requests, [&build_print_request_packet(9, MODE_1BPP)]);
+        assert_eq!(p.transport.data_bytes().len(), 9 * PRINTER_WIDTH / 8);
+    }
+
+    #[test]
+    fn print_image_is_begin_send_end() {
+        let (width, height) = (PRINTER_WIDTH, 5);
+        let pixels = pattern(width, height);
+        let mut whole = printer();
+        whole.transport.push_print_success();
+        whole
+            .print_image(&pixels, width, height, MODE_1BPP, None)
+            .unwrap();
+
+        let mut manual = printer();
+        manual.transport.push_print_success();
+        let packed = pack_pixels_for_mode(&pixels, width, height, MODE_1BPP).unwrap();
+        manual.begin_print(height as u16, MODE_1BPP).unwrap();
+        manual.send_data(&packed).unwrap();
+        manual.end_print().unwrap();
+
+        assert_eq!(
+            whole.transport.control_writes,
+            manual.transport.control_writes
+        );
+        assert_eq!(whole.transport.data_writes, manual.transport.data_writes);
+        let job = whole
+            .render_image_job(&pixels, width, height, MODE_1BPP)
+            .unwrap();
+        assert_eq!(whole.transport.data_writes, job.data_chunks);
+    }
+
+    /// A 16x8 JPEG, left half black, tagged with EXIF `orientation`.
+    fn jpeg_with_orientation(orientation: u16) -> Vec<u8> {
+        let img = GrayImage::from_fn(16, 8, |x, _| image::Luma([if x < 8 { 0 } else { 255 }]));
+        let mut jpeg = Vec::new();
+        img.write_to(&mut Cursor::new(&mut jpeg), image::ImageFormat::Jpeg)
+            .unwrap();
+        // Big-endian TIFF with a single IFD entry: Orientation (0x0112), SHORT
+        let mut app1 = b"Exif\0\0MM\0\x2A\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01".to_vec();
+        app1.extend_from_slice(&orientation.to_be_bytes());
+        app1.extend_from_slice(&[0; 6]);
+        let mut out = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        out.extend_from_slice(&(app1.len() as u16 + 2).to_be_bytes());
+        out.extend_from_slice(&app1);
+        out.extend_from_slice(&jpeg[2..]);
+        out
+    }
+
+    #[test]
+    fn decode_applies_exif_orientation() {
+        let data = jpeg_with_orientation(6);
+        let img = decode_oriented(ImageReader::new(Cursor::new(&data)), None)
+            .unwrap()
+            .to_luma8();
+        // Rotated 90° clockwise: the black left half is now on top
+        assert_eq!(img.dimensions(), (8, 16));
+        assert!(img.get_pixel(4, 2)[0] < 64);
+        assert!(img.get_pixel(4, 13)[0] > 192);
+
+        let img = decode_oriented(
+            ImageReader::new(Cursor::new(&data)),
+            Some(Orientation::NoTransforms),
+        )
+        .unwrap();
+        assert_eq!((img.width(), img.height()), (16, 8));
+
+        let plain = jpeg_with_orientation(1);
+        let img = decode_oriented(ImageReader::new(Cursor::new(&plain)), None).unwrap();
+        assert_eq!((img.width(), img.height()), (16, 8));
+    }
+
+    fn status(battery: Option<u8>, temperature: Option<u8>, state: PrinterState) -> PrinterStatus {
+        PrinterStatus {
+            battery_percent: battery,
+            temperature,
+            state,
+        }
+    }
+
+    #[test]
+    fn status_thresholds_at_boundaries() {
+        let min = DEFAULT_MIN_BATTERY_PERCENT;
+        let max = DEFAULT_MAX_TEMPERATURE;
+        let ok = |b, t| status(Some(b), Some(t), PrinterState::Standby);
+
+        assert!(!ok(min, 20).is_battery_low());
+        assert!(ok(min - 1, 20).is_battery_low());
+        assert!(!ok(50, max).is_overheated());
+        assert!(ok(50, max + 1).is_overheated());
+        assert!(!status(None, None, PrinterState::Standby).is_battery_low());
+        assert!(!status(None, None, PrinterState::Standby).is_overheated());
+
+        assert!(ok(min, max).can_print().is_ok());
+        assert!(matches!(
+            ok(min - 1, max).can_print(),
+            Err(PrinterError::NotReady(PrinterErrorCode::LowBattery))
+        ));
+        assert!(matches!(
+            ok(min, max + 1).can_print(),
+            Err(PrinterError::NotReady(PrinterErrorCode::Overheated))
+        ));
+        assert!(status(None, None, PrinterState::Standby)
+            .can_print()
+            .is_ok());
+
+        assert!(ok(30, 40).can_print_with(30, 40).is_ok());
+        assert!(ok(29, 40).can_print_with(30, 40).is_err());
+        assert!(ok(30, 41).can_print_with(30, 40).is_err());
+        assert!(ok(0, 255).can_print_with(0, 255).is_ok());
+    }
+
+    #[test]
+    fn error_state_blocks_printing_first() {
+        let s = status(
+            Some(100),
+            Some(20),
+            PrinterState::Error(PrinterErrorCode::OutOfPaper),
+        );
+        assert!(matches!(
+            s.can_print(),
+            Err(PrinterError::NotReady(PrinterErrorCode::OutOfPaper))
+        ));
+    }
+
+    #[test]
+    fn print_packed_rows_follow_the_configured_width() {
+        let mut p = CatPrinter::builder(MockTransport::new()).width(200).build();
+        assert!(p.print_packed(&[0; 48 * 2], 2, MODE_1BPP).is_err());
+        p.transport.push_print_success();
+        p.print_packed(&[0; 25 * 2], 2, MODE_1BPP).unwrap();
+        assert_eq!(p.transport.data_bytes().len(), 50);
+    }
+
+    #[test]
+    fn autocrop_keeps_the_colors() {
+        let mut rgb = image::RgbImage::from_pixel(10, 8, image::Rgb([255, 255, 255]));
+        for (x, y) in [(2, 3), (5, 4)] {
+            rgb.put_pixel(x, y, image::Rgb([255, 0, 0]));
+        }
+        let img = DynamicImage::ImageRgb8(rgb);
+
+        let cropped = autocrop_image(&img, DEFAULT_AUTOCROP_THRESHOLD, GrayscaleMode::Luma709);
+        assert_eq!((cropped.width(), cropped.height()), (4, 2));
+        let cropped = cropped.to_rgb8();
+        assert_eq!(cropped.get_pixel(0, 0).0, [255, 0, 0]);
+        assert_eq!(cropped.get_pixel(3, 1).0, [255, 0, 0]);
+        // The printer's grayscale mode then decides how the red prints
+        assert_eq!(
+            to_grayscale(&DynamicImage::ImageRgb8(cropped), GrayscaleMode::Luma601).get_pixel(0, 0)
+                [0],
+            76
+        );
+
+        // Red is white in the red channel, so nothing is left to keep
+        let blank = autocrop_image(
+            &img,
+            DEFAULT_AUTOCROP_THRESHOLD,
+            GrayscaleMode::Channel(crate::dithering::ColorChannel::Red),
+        );
+        assert_eq!((blank.width(), blank.height()), (10, 1));
+        assert!(blank.to_luma8().pixels().all(|p| p[0] == 255));
+    }
+
+    #[test]
+    fn print_request_skips_a_stray_status_before_the_ack() {
+        let mut p = printer();
+        p.transport.push_response(Command::GetStatus, &[0; 12]);
+        p.transport.push_print_success();
+        p.print_image(&[0; 8], 8, 1, MODE_1BPP, None).unwrap();
+        assert!(p.transport.notifications.is_empty());
+
+        let mut p = printer();
+        p.transport.push_response(Command::GetStatus, &[0; 12]);
+        p.transport.push_response(Command::PrintStart, &[0x00]);
+        let notif = p
+            .wait_for_notification(Command::PrintStart, Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(notif.command_id, u8::from(Command::PrintStart));
+        // Only skipped frames are consumed; a missing ack is an error
+        assert!(p
+            .wait_for_notification(Command::PrintStart, Duration::from_secs(1))
+            .is_err());
+    }
+
+    #[test]
+    fn preview_of_a_1bpp_buffer_is_the_buffer() {
+        let (width, height) = (13, 6);
+        let pixels = pattern(width, height);
+        for bit_order in [BitOrder::Lsb0, BitOrder::Msb0] {
+            let p = CatPrinter::builder(MockTransport::new())
+                .bit_order(bit_order)
+                .build();
+            let preview = p.preview_image(&pixels, width, height).unwrap();
+            assert_eq!(preview.dimensions(), (width as u32, height as u32));
+            assert_eq!(preview.into_raw(), pixels);
+        }
+        // Gray levels print white; only pure black survives
+        let p = printer();
+        let preview = p.preview_image(&[0, 1, 128, 254, 255], 5, 1).unwrap();
+        assert_eq!(preview.into_raw(), [0, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn print_image_copies_is_one_job_with_gaps() {
+        let mut p = printer();
+        p.transport.push_print_success();
+        let pixels = vec![0u8; PRINTER_WIDTH * 3];
+        p.print_image_copies(&pixels, PRINTER_WIDTH, 3, MODE_1BPP, 2, 4)
+            .unwrap();
+        assert_eq!(
+            p.transport.control_writes[1],
+            build_print_request_packet(2 * 3 + 4, MODE_1BPP)
+        );
+        let data = p.transport.data_bytes();
+        assert_eq!(data.len(), 10 * 48);
+        assert!(data[..3 * 48].iter().all(|&b| b == 0xFF));
+        assert!(data[3 * 48..7 * 48].iter().all(|&b| b == 0x00));
+        assert!(data[7 * 48..].iter().all(|&b| b == 0xFF));
+
+        assert!(p
+            .print_image_copies(&pixels, PRINTER_WIDTH, 3, MODE_1BPP, 0, 4)
+            .is_err());
+    }
+
+    #[test]
+    fn blank_text_is_refused_without_printing() {
+        let mut p = printer();
+        for (main, author) in [("", ""), ("   ", ""), ("\n\n", ""), ("\t", "")] {
+            assert!(matches!(
+                p.print_text(main, author),
+                Err(PrinterError::Image(_))
+            ));
+        }
+        assert!(matches!(
+            p.print_formatted("\n\n"),
+            Err(PrinterError::Image(_))
+        ));
+        assert!(p.transport.control_writes.is_empty());
+        assert!(p.transport.data_writes.is_empty());
+
+        p.transport.push_print_success();
+        p.print_text("", "Ann").unwrap();
+    }
+
+    #[test]
+    fn unsharp_none_leaves_the_image_alone() {
+        // Already printer-wide, so resizing is the identity too
+        // Both stripe levels threshold to black unless sharpening lifts an edge
+        let gray = GrayImage::from_fn(PRINTER_WIDTH as u32, 4, |x, _| {
+            Luma([if x % 64 < 32 { 100 } else { 125 }])
+        });
+        let img = DynamicImage::ImageLuma8(gray.clone());
+        let mut settings = printer().image_settings();
+        assert_eq!(settings.unsharp, None);
+        let (pixels, width, height) = prepare_image(&img, ImageDithering::None, &settings);
+        assert_eq!((width, height), (PRINTER_WIDTH, 4));
+        let mut expected = gray;
+        apply_dithering_strength(&mut expected, ImageDithering::None, 1.0);
+        assert_eq!(pixels, expected.into_raw());
+        assert!(pixels.iter().all(|&p| p == 0));
+
+        settings.unsharp = Some((1.0, 0));
+        let (sharpened, _, _) = prepare_image(&img, ImageDithering::None, &settings);
+        assert_ne!(sharpened, pixels);
+    }
+
+    #[test]
+    fn line_count_limit_is_u16_max() {
+        assert_eq!(check_line_count(0).unwrap(), 0);
+        assert_eq!(check_line_count(65535).unwrap(), u16::MAX);
+        assert!(matches!(
+            check_line_count(65536),
+            Err(PrinterError::Image(_))
+        ));
+        assert!(check_line_count(usize::MAX).is_err());
+
+        // Too tall an image fails before anything is sent
+        let mut p = printer();
+        let pixels = vec![255u8; 65536];
+        assert!(p.print_image(&pixels, 1, 65536, MODE_1BPP, None).is_err());
+        assert!(p.transport.control_writes.is_empty());
+    }
+
+    #[test]
+    fn padding_and_min_height_set_the_printed_height() {
+        let img =
+            DynamicImage::ImageLuma8(GrayImage::from_pixel(PRINTER_WIDTH as u32, 10, Luma([0])));
+        let mut png = Vec::new();
+        img.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        for (top, bottom, min_height, expected) in [
+            (0, 0, 0, 10),
+            (3, 4, 0, 17),
+            (0, 0, 25, 25),
+            (3, 4, 12, 17),
+            (3, 4, 30, 30),
+        ] {
+            let mut p = printer();
+            p.pad_top = top;
+            p.pad_bottom = bottom;
+            p.min_height = min_height;
+            p.transport.push_print_success();
+            p.print_image_from_bytes(&png, ImageDithering::None)
+                .unwrap();
+            assert!(p
+                .transport
+                .control_writes
+                .contains(&build_print_request_packet(expected, MODE_1BPP)));
+            let row_bytes = PRINTER_WIDTH / 8;
+            assert_eq!(
+                p.transport.data_bytes().len(),
+                row_bytes * expected as usize
+            );
+        }
+    }
+
+    /// 0xA1 status payload in the given state byte (0 Standby, 1 Printing).
+    fn status_payload(state: u8) -> [u8; 13] {
+        let mut payload = [0; 13];
+        payload[6] = state;
+        payload
+    }
+
+    #[test]
+    fn cancel_print_sends_0xac_and_polls_until_standby() {
+        let mut p = printer();
+        p.transport
+            .push_response(Command::GetStatus, &status_payload(1));
+        p.transport
+            .push_response(Command::GetStatus, &status_payload(0));
+        // Left over if polling didn't stop at Standby
+        p.transport
+            .push_response(Command::GetStatus, &status_payload(1));
+        p.cancel_print().unwrap();
+
+        let status = build_control_packet(Command::GetStatus, &[0x00]);
+        assert_eq!(
+            p.transport.control_writes,
+            [
+                vec![
+                    0x22,
+                    0x21,
+                    0xAC,
+                    0x00,
+                    0x01,
+                    0x00,
+                    0x00,
+                    crc8(&[0x00]),
+                    0xFF
+                ],
+                status.clone(),
+                status
+            ]
+        );
+        assert_eq!(p.transport.notifications.len(), 1);
+    }
+
+    #[test]
+    fn cancel_print_times_out_after_ack_timeout() {
+        let mut p = printer();
+        p.ack_timeout = Duration::from_millis(50);
+        // Nothing answers the status poll, which only gets the time left
+        let err = p.cancel_print().unwrap_err();
+        assert!(
+            matches!(err, PrinterError::Timeout(t) if t == p.ack_timeout),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn move_paper_waits_for_its_own_reply() {
+        let mut p = printer();
+        // A stray status and progress frame from an earlier job come first
+        p.transport
+            .push_response(Command::GetStatus, &status_payload(1));
+        p.transport
+            .push_response(Command::PrintProgress, &[0x10, 0x00]);
+        p.transport.push_response(Command::FeedPaper, &[0x01]);
+        assert!(matches!(p.feed_paper(40), Err(PrinterError::Busy)));
+        assert!(p.transport.notifications.is_empty());
+
+        p.transport
+            .push_response(Command::GetStatus, &status_payload(0));
+        p.transport.push_response(Command::RetractPaper, &[0x00]);
+        p.retract_paper(40).unwrap();
+        assert!(p.transport.notifications.is_empty());
+
+        // Without a reply of its own the move times out
+        p.ack_timeout = Duration::from_millis(20);
+        p.transport
+            .push_response(Command::GetStatus, &status_payload(0));
+        assert!(matches!(p.feed_paper(40), Err(PrinterError::Timeout(_))));
+    }
+
+    #[test]
+    fn timeouts_report_the_whole_wait() {
+        // The mock times out with the duration each read was given, like the
+        // BLE transport; the printer reports the full wait instead
+        let mut p = printer();
+        p.print_timeout = Duration::from_millis(30);
+        p.transport.push_response(Command::PrintStart, &[0x00]);
+        p.transport
+            .push_response(Command::PrintProgress, &[0x01, 0x00]);
+        let err = p.print_image(&[0; 8], 8, 1, MODE_1BPP, None).unwrap_err();
+        assert!(
+            matches!(err, PrinterError::Timeout(t) if t == p.print_timeout),
+            "{err:?}"
+        );
+
+        let wait = Duration::from_millis(20);
+        p.transport
+            .push_response(Command::GetStatus, &status_payload(0));
+        let err = p
+            .wait_for_notification(Command::PrintStart, wait)
+            .unwrap_err();
+        assert!(
+            matches!(err, PrinterError::Timeout(t) if t == wait),
+            "{err:?}"
+        );
+    }
+}
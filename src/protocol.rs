@@ -1,126 +1,152 @@
+use crate::dithering::{ordered_dither, BAYER_8};
 use crate::font;
+pub(crate) use crate::wire::check_pixel_buffer;
+/// Wire format helpers, also usable without std from the wire module
+pub use crate::wire::{
+    build_control_packet, chunk_data, chunk_data_iter, crc8, pack_1bpp_pixels,
+    pack_1bpp_pixels_ordered, pack_rows_into, pack_rows_into_ordered, parse_control_packet,
+    parse_notification, parse_notification_checked, unpack_1bpp_pixels, unpack_1bpp_pixels_ordered,
+    BitOrder, Command, Notification, PacketAssembler,
+};
+use image::{GrayImage, Luma};
 
-/// Computes CRC-8 for a byte slice (CatPrinter protocol).
+/// Maximum number of dot lines a single feed/retract command moves.
+/// 400 lines is roughly 50mm of paper at 203 dpi; larger values are clamped.
+pub const MAX_FEED_LINES: u16 = 400;
+
+/// Builds a paper feed (0xA3) or retract (0xA4) control packet.
 ///
-/// - `data`: input bytes
+/// - `command`: Command::FeedPaper or Command::RetractPaper
+/// - `lines`: dot lines to move, clamped to MAX_FEED_LINES
 ///
-/// Returns CRC-8 value
-pub fn crc8(data: &[u8]) -> u8 {
-    let poly: u8 = 0x07;
-    let mut crc: u8 = 0x00;
+/// Returns Vec<u8> ready to send
+pub fn build_feed_packet(command: Command, lines: u16) -> Vec<u8> {
+    let lines = lines.min(MAX_FEED_LINES);
+    build_control_packet(command, &lines.to_le_bytes())
+}
 
-    for &b in data {
-        crc ^= b;
-        for _ in 0..8 {
-            if (crc & 0x80) != 0 {
-                crc = (crc << 1) ^ poly;
-            } else {
-                crc <<= 1;
-            }
-        }
-    }
-    crc
+/// Lowest density/energy level accepted by the MXW01 (very light).
+pub const MIN_DENSITY: u8 = 0x01;
+/// Highest density level considered safe; higher values can overheat the head.
+pub const MAX_DENSITY: u8 = 0x7F;
+/// Density used when none is configured (matches the vendor app default).
+pub const DEFAULT_DENSITY: u8 = 0x5D;
+
+/// Builds a set-density (0xA2) control packet.
+///
+/// - `level`: print energy, clamped to MIN_DENSITY..=MAX_DENSITY
+///
+/// Returns Vec<u8> ready to send
+pub fn build_density_packet(level: u8) -> Vec<u8> {
+    let level = level.clamp(MIN_DENSITY, MAX_DENSITY);
+    build_control_packet(Command::SetDensity, &[level])
 }
 
-/// Builds a control packet for the CatPrinter protocol.
-/// Returns a Vec<u8> ready to send.
-/// Builds a control packet for the CatPrinter protocol.
+/// Builds a print request (0xA9) control packet announcing a job.
 ///
-/// - `command_id`: command byte
-/// - `payload`: command payload
+/// - `line_count`: number of rows that will follow
+/// - `mode`: print mode (MODE_1BPP or MODE_4BPP)
 ///
 /// Returns Vec<u8> ready to send
-pub fn build_control_packet(command_id: u8, payload: &[u8]) -> Vec<u8> {
-    let mut out = Vec::with_capacity(2 + 1 + 1 + 2 + payload.len() + 1 + 1);
-    out.push(0x22);
-    out.push(0x21);
-    out.push(command_id);
-    out.push(0x00);
-    let len = payload.len() as u16;
-    out.push((len & 0xFF) as u8);
-    out.push((len >> 8) as u8);
-    out.extend_from_slice(payload);
-    out.push(crc8(payload));
-    out.push(0xFF);
-    out
+pub fn build_print_request_packet(line_count: u16, mode: u8) -> Vec<u8> {
+    let mut payload = Vec::new();
+    payload.extend_from_slice(&line_count.to_le_bytes());
+    payload.push(0x30);
+    payload.push(mode);
+    build_control_packet(Command::PrintStart, &payload)
+}
+
+/// Builds the data end (0xAD) control packet sent after the last data chunk.
+pub fn build_data_end_packet() -> Vec<u8> {
+    build_control_packet(Command::PrintEnd, &[0x00])
+}
+
+/// Builds the cancel print (0xAC) control packet, which makes the printer
+/// drop the current job and discard any data it has buffered.
+pub fn build_cancel_packet() -> Vec<u8> {
+    build_control_packet(Command::CancelPrint, &[0x00])
+}
+
+/// Sleep timeout value meaning "never power off while idle".
+pub const SLEEP_NEVER: u16 = 0;
+
+/// Builds the auto-sleep timer query control packet.
+pub fn build_get_sleep_timeout_packet() -> Vec<u8> {
+    build_control_packet(Command::GetSleepTimeout, &[0x00])
+}
+
+/// Builds a set auto-sleep timer control packet.
+///
+/// - `minutes`: idle minutes before the printer powers off, SLEEP_NEVER to
+///   keep it awake
+///
+/// Returns Vec<u8> ready to send
+pub fn build_set_sleep_timeout_packet(minutes: u16) -> Vec<u8> {
+    build_control_packet(Command::SetSleepTimeout, &minutes.to_le_bytes())
+}
+
+/// Parses the payload of a sleep timer notification (answer to either the
+/// query or the set command).
+///
+/// Payload mapping:
+/// - payload[0..2]: idle minutes before power off, little-endian; 0 = never
+///
+/// Returns the minutes, or None if the payload is too short
+pub fn parse_sleep_timeout(payload: &[u8]) -> Option<u16> {
+    match payload {
+        [lo, hi, ..] => Some(u16::from_le_bytes([*lo, *hi])),
+        _ => None,
+    }
 }
 
+/// Every packet of a print job, in the order it is sent.
+/// Built by CatPrinter::render_image_job without touching the transport.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Notification {
-    pub command_id: u8,
-    pub unknown: u8,
-    pub payload: Vec<u8>,
-    pub crc: Option<u8>,
-}
-
-/// Parses a notification packet from the CatPrinter.
-/// Parses a notification packet from the CatPrinter.
-///
-/// - `data`: raw notification bytes
-///
-/// Returns Notification struct on success
-pub fn parse_notification(data: &[u8]) -> Result<Notification, &'static str> {
-    if data.len() < 7 {
-        return Err("packet too short");
-    }
-    if data[0] != 0x22 || data[1] != 0x21 {
-        return Err("bad preamble");
-    }
-    let cmd = data[2];
-    let unknown = data[3];
-    let len_lo = data[4] as usize;
-    let len_hi = data[5] as usize;
-    let payload_len = (len_hi << 8) | len_lo;
-    if data.len() < 6 + payload_len {
-        return Err("not enough bytes for claimed payload length");
-    }
-    let payload = data[6..6 + payload_len].to_vec();
-    let crc = data.get(6 + payload_len).copied();
-    Ok(Notification {
-        command_id: cmd,
-        unknown,
-        payload,
-        crc,
-    })
+pub struct PrintJob {
+    /// Set-density (0xA2) control packet
+    pub density_packet: Vec<u8>,
+    /// Print request (0xA9) control packet
+    pub request_packet: Vec<u8>,
+    /// Packed image data, split into data characteristic writes
+    pub data_chunks: Vec<Vec<u8>>,
+    /// Data end (0xAD) control packet
+    pub end_packet: Vec<u8>,
 }
 
-/// Pack pixels given as bytes (0 = white, non-zero = black) row-major.
-/// width must be >0. Returns bytes in the printer's expected layout:
-/// - rows top->bottom
-/// - within each row, groups of 8 pixels become one byte where bit 0 = leftmost pixel of group.
-/// Packs a grayscale image buffer into 1bpp format for CatPrinter.
-/// - 0 = black, non-zero = white
-/// - Bits are packed LSB-first (bit 0 = leftmost pixel)
-/// - Rows are packed top-to-bottom, left-to-right
-/// Packs a grayscale image buffer into 1bpp format for CatPrinter.
+/// Width of the MXW01 print head in pixels; rows can't be wider than this.
+pub const PRINTER_WIDTH: usize = 384;
+
+/// Print mode byte for 1bpp (black/white) data.
+pub const MODE_1BPP: u8 = 0x00;
+/// Print mode byte for 4bpp (16-level grayscale) data.
+pub const MODE_4BPP: u8 = 0x02;
+
+/// Packs a grayscale image buffer into 4bpp format for CatPrinter.
+/// - each pixel becomes a 4-bit darkness level (0 = white, 15 = black)
+/// - two pixels per byte, low nibble = leftmost pixel
+/// - odd widths leave the high nibble of the last byte in each row empty
 ///
 /// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
 /// - `width`, `height`: image dimensions
 ///
 /// Returns packed bytes in printer's expected layout
-pub fn pack_1bpp_pixels(
+pub fn pack_4bpp_pixels(
     pixels: &[u8],
     width: usize,
     height: usize,
 ) -> Result<Vec<u8>, &'static str> {
-    if width == 0 || height == 0 {
-        return Err("width/height must be > 0");
-    }
-    let required = width.checked_mul(height).ok_or("width*height overflow")?;
-    if pixels.len() < required {
-        return Err("not enough pixels");
-    }
-    let bytes_per_row = (width + 7) / 8;
+    check_pixel_buffer(pixels, width, height)?;
+    let bytes_per_row = width.div_ceil(2);
     let mut out = Vec::with_capacity(bytes_per_row * height);
     for row in 0..height {
         let row_off = row * width;
         for group in 0..bytes_per_row {
             let mut b: u8 = 0;
-            let base = row_off + group * 8;
-            let end = usize::min(base + 8, row_off + width);
-            for (bit, px_idx) in (base..end).enumerate() {
-                let bit_val = if pixels[px_idx] == 0 { 1u8 } else { 0u8 };
-                b |= bit_val << bit;
+            let base = row_off + group * 2;
+            let end = usize::min(base + 2, row_off + width);
+            for (nibble, px_idx) in (base..end).enumerate() {
+                let level = (255 - pixels[px_idx]) >> 4;
+                b |= level << (nibble * 4);
             }
             out.push(b);
         }
@@ -128,7 +154,96 @@ pub fn pack_1bpp_pixels(
     Ok(out)
 }
 
-use crate::printer::{PrinterState, PrinterStatus};
+/// Size of one packed row in the given print mode.
+///
+/// - `width`: row width in pixels
+/// - `mode`: MODE_1BPP or MODE_4BPP
+///
+/// Returns bytes per packed row, or an error for unknown modes
+pub fn packed_row_bytes(width: usize, mode: u8) -> Result<usize, &'static str> {
+    match mode {
+        MODE_1BPP => Ok(width.div_ceil(8)),
+        MODE_4BPP => Ok(width.div_ceil(2)),
+        _ => Err("unsupported print mode"),
+    }
+}
+
+/// Repeats one image's packed rows to print several copies as a single job.
+///
+/// - `packed`: packed rows of one copy
+/// - `row_bytes`: bytes per packed row, see packed_row_bytes
+/// - `copies`: number of copies, at least 1
+/// - `gap_lines`: blank rows between consecutive copies (none after the last)
+///
+/// Returns (data, line_count) with line_count = copies * rows +
+/// (copies - 1) * gap_lines, or Err if copies is 0 or the job would exceed
+/// the 65535 lines a print request can announce
+pub fn repeat_packed_rows(
+    packed: &[u8],
+    row_bytes: usize,
+    copies: usize,
+    gap_lines: u16,
+) -> Result<(Vec<u8>, u16), &'static str> {
+    if copies == 0 {
+        return Err("copies must be at least 1");
+    }
+    let rows = packed.len().checked_div(row_bytes).unwrap_or(0);
+    let line_count = rows
+        .checked_mul(copies)
+        .and_then(|l| l.checked_add((copies - 1).checked_mul(gap_lines as usize)?))
+        .and_then(|l| u16::try_from(l).ok())
+        .ok_or("copies exceed the maximum line count of one print job")?;
+    let mut data = Vec::with_capacity(line_count as usize * row_bytes);
+    for copy in 0..copies {
+        if copy > 0 {
+            // Zero bytes are white in both 1bpp and 4bpp
+            data.resize(data.len() + gap_lines as usize * row_bytes, 0);
+        }
+        data.extend_from_slice(&packed[..rows * row_bytes]);
+    }
+    Ok((data, line_count))
+}
+
+/// Packs a grayscale image buffer using the packer matching `mode`.
+///
+/// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+/// - `width`, `height`: image dimensions
+/// - `mode`: print mode (MODE_1BPP or MODE_4BPP)
+///
+/// Returns packed bytes, or Err for an unknown mode
+pub fn pack_pixels_for_mode(
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+    mode: u8,
+) -> Result<Vec<u8>, &'static str> {
+    pack_pixels_for_mode_ordered(pixels, width, height, mode, BitOrder::Lsb0)
+}
+
+/// Packs a grayscale image buffer using the packer matching `mode`, with the
+/// given bit order for 1bpp. 4bpp nibble order is fixed.
+///
+/// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+/// - `width`, `height`: image dimensions
+/// - `mode`: MODE_1BPP or MODE_4BPP
+/// - `bit_order`: which bit holds the leftmost pixel of each 1bpp byte
+///
+/// Returns packed bytes, or an error for unknown modes
+pub fn pack_pixels_for_mode_ordered(
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+    mode: u8,
+    bit_order: BitOrder,
+) -> Result<Vec<u8>, &'static str> {
+    match mode {
+        MODE_1BPP => pack_1bpp_pixels_ordered(pixels, width, height, bit_order),
+        MODE_4BPP => pack_4bpp_pixels(pixels, width, height),
+        _ => Err("unsupported print mode"),
+    }
+}
+
+use crate::printer::{PrinterErrorCode, PrinterState, PrinterStatus};
 
 /// Parses the payload bytes from a CatPrinter notification into a PrinterStatus struct.
 ///
@@ -137,7 +252,7 @@ use crate::printer::{PrinterState, PrinterStatus};
 /// - payload[9]: battery percent (if available)
 /// - payload[10]: temperature (if available)
 /// - payload[12]: overall flag (if nonzero and payload.len() > 13, error)
-/// - payload[13]: error code (if error)
+/// - payload[13]: error code (if error), see PrinterErrorCode
 ///
 /// Returns PrinterStatus with battery, temperature, and state.
 pub fn parse_printer_status(payload: &[u8]) -> PrinterStatus {
@@ -154,7 +269,7 @@ pub fn parse_printer_status(payload: &[u8]) -> PrinterStatus {
                 _ => PrinterState::Unknown,
             };
         } else if payload.len() > 13 {
-            state = PrinterState::Error(payload[13]);
+            state = PrinterState::Error(PrinterErrorCode::from_u8(payload[13]));
         }
         battery = Some(payload[9]);
         temp = Some(payload[10]);
@@ -166,50 +281,785 @@ pub fn parse_printer_status(payload: &[u8]) -> PrinterStatus {
     }
 }
 
-/// Splits data into chunks of given size.
+/// Parses the payload of a 0xAB battery notification.
+///
+/// Payload mapping:
+/// - payload[0]: battery percent
+/// - anything after that is ignored
+///
+/// Returns the battery percent, or None if the payload is empty
+pub fn parse_battery(payload: &[u8]) -> Option<u8> {
+    payload.first().copied()
+}
+
+/// Parses the payload of a 0xBB print progress notification, sent while a
+/// job is printing.
 ///
-/// - `data`: input bytes
-/// - `chunk_size`: size of each chunk
+/// Payload mapping:
+/// - payload[0..2]: lines printed so far, little-endian (same encoding as the
+///   line count in the 0xA9 print request)
+/// - anything after that is ignored
 ///
-/// Returns Vec of byte slices
-pub fn chunk_data(data: &[u8], chunk_size: usize) -> Vec<&[u8]> {
-    if chunk_size == 0 {
-        return vec![data];
+/// Returns the lines printed, or None if the payload is too short
+pub fn parse_print_progress(payload: &[u8]) -> Option<u16> {
+    match payload {
+        [lo, hi, ..] => Some(u16::from_le_bytes([*lo, *hi])),
+        _ => None,
     }
-    data.chunks(chunk_size).collect()
 }
 
-/// Rotates and mirrors a pixel buffer for CatPrinter (180° rotation).
-/// Input: pixels (row-major, 0=black, 255=white), width, height
-/// Output: rotated and mirrored pixel buffer
-pub fn rotate_mirror_pixels(pixels: &[u8], width: usize, height: usize) -> Vec<u8> {
-    let mut rotated = vec![0u8; pixels.len()];
+/// Firmware and hardware identification returned by the 0xB1 version query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceInfoReport {
+    pub firmware_version: String,
+    pub hardware_version: Option<String>,
+    pub serial: Option<String>,
+}
+
+/// Parses the payload of a 0xB1 version notification.
+///
+/// The payload is ASCII text, fields separated by NUL bytes or whitespace:
+/// firmware version, then optionally hardware version and serial number.
+///
+/// - `payload`: notification payload
+///
+/// Returns DeviceInfoReport, or Err if no firmware version is present
+pub fn parse_device_info(payload: &[u8]) -> Result<DeviceInfoReport, &'static str> {
+    let text = std::str::from_utf8(payload).map_err(|_| "device info is not valid text")?;
+    let mut fields = text
+        .split(|c: char| c == '\0' || c.is_whitespace())
+        .filter(|f| !f.is_empty())
+        .map(str::to_string);
+    let firmware_version = fields.next().ok_or("device info payload is empty")?;
+    Ok(DeviceInfoReport {
+        firmware_version,
+        hardware_version: fields.next(),
+        serial: fields.next(),
+    })
+}
+
+/// Stacks several same-width pixel buffers vertically into one image.
+///
+/// - `images`: (pixels, width, height) for each image, top to bottom
+/// - `width`: required width of every image
+/// - `gap`: blank (white) rows inserted between consecutive images
+///
+/// Returns (pixels, total_height), where total_height is the sum of the image
+/// heights plus `gap` * (images - 1)
+pub fn stack_images(
+    images: &[(&[u8], usize, usize)],
+    width: usize,
+    gap: usize,
+) -> Result<(Vec<u8>, usize), &'static str> {
+    if images.is_empty() {
+        return Err("no images to stack");
+    }
+    let mut total_height = gap * (images.len() - 1);
+    for &(pixels, w, h) in images {
+        if w != width {
+            return Err("image width does not match printer width");
+        }
+        check_pixel_buffer(pixels, w, h)?;
+        total_height += h;
+    }
+    let mut out = Vec::with_capacity(width * total_height);
+    for (i, &(pixels, _, h)) in images.iter().enumerate() {
+        if i > 0 {
+            out.resize(out.len() + gap * width, 255);
+        }
+        out.extend_from_slice(&pixels[..width * h]);
+    }
+    Ok((out, total_height))
+}
+
+/// One block of a receipt-style layout, see render_layout.
+#[derive(Debug, Clone, Copy)]
+pub enum LayoutElement<'a> {
+    /// Grayscale image no wider than the page, placed per `alignment`.
+    Image {
+        pixels: &'a [u8],
+        width: usize,
+        height: usize,
+        alignment: font::Alignment,
+    },
+    /// Wrapped text rendered with rasterize_text.
+    Text { content: &'a str, font_size: f32 },
+}
+
+/// Renders layout elements top to bottom into a single page-wide buffer.
+///
+/// - `elements`: images and text blocks in print order
+/// - `width`: page width in pixels (usually PRINTER_WIDTH)
+///
+/// Returns (pixels, height), or Err if an image is invalid or wider than the page
+pub fn render_layout(
+    elements: &[LayoutElement],
+    width: usize,
+) -> Result<(Vec<u8>, usize), &'static str> {
+    let mut blocks: Vec<(Vec<u8>, usize)> = Vec::with_capacity(elements.len());
+    for element in elements {
+        match *element {
+            LayoutElement::Image {
+                pixels,
+                width: w,
+                height: h,
+                alignment,
+            } => {
+                check_pixel_buffer(pixels, w, h)?;
+                if w > width {
+                    return Err("layout image is wider than the page");
+                }
+                let x0 = match alignment {
+                    font::Alignment::Left => 0,
+                    font::Alignment::Center => (width - w) / 2,
+                    font::Alignment::Right => width - w,
+                };
+                let mut block = vec![255u8; width * h];
+                for row in 0..h {
+                    let dst = row * width + x0;
+                    block[dst..dst + w].copy_from_slice(&pixels[row * w..(row + 1) * w]);
+                }
+                blocks.push((block, h));
+            }
+            LayoutElement::Text { content, font_size } => {
+                let block = font::rasterize_text(content, width, font_size);
+                let h = block.len().checked_div(width).unwrap_or(0);
+                blocks.push((block, h));
+            }
+        }
+    }
+    let images: Vec<(&[u8], usize, usize)> = blocks
+        .iter()
+        .map(|(block, h)| (block.as_slice(), width, *h))
+        .collect();
+    stack_images(&images, width, 0)
+}
+
+/// Font size of the text lines on the test page.
+const TEST_PAGE_FONT_SIZE: f32 = 28.0;
+/// Gray levels of the test page wedges, from full black to white.
+pub const TEST_PAGE_WEDGES: [u8; 8] = [0, 36, 73, 109, 146, 182, 219, 255];
+
+/// Renders the fixed diagnostic page printed by print_test_page.
+///
+/// Layout, top to bottom (row 0 prints first):
+/// - 4-row full-width rule
+/// - 48 rows of alignment marks: 8px bars flush with both edges and a 2px
+///   center line; a bar missing or clipped shows a misaligned or wrong head
+///   width
+/// - "CatPrinter test page" and each `info` line, one per line
+/// - density wedges: TEST_PAGE_WEDGES as 8 equal 64-row blocks, left to right
+///   from black to white, each outlined 1px and shaded with the 8x8 Bayer
+///   pattern; more light wedges fading to white means lower density
+/// - a 32-row continuous black-to-white ramp, Bayer shaded
+/// - 4-row full-width rule
+///
+/// Sections are separated by 12 white rows.
+///
+/// - `info`: text lines, e.g. firmware and battery
+/// - `width`: page width in pixels
+///
+/// Returns (pixels, height) with pixels row-major, 0=black, 255=white
+pub fn render_test_page(info: &[String], width: usize) -> (Vec<u8>, usize) {
+    const GAP: usize = 12;
+    let mut page = Vec::new();
+    let rule = |page: &mut Vec<u8>| page.resize(page.len() + 4 * width, 0);
+    let gap = |page: &mut Vec<u8>| page.resize(page.len() + GAP * width, 255);
+
+    rule(&mut page);
+    gap(&mut page);
+    let mut row = vec![255u8; width];
+    let bar = 8.min(width / 2);
+    row[..bar].fill(0);
+    row[width - bar..].fill(0);
+    let center = (width / 2).saturating_sub(1);
+    row[center..(center + 2).min(width)].fill(0);
+    page.extend(row.repeat(48));
+    gap(&mut page);
+
+    let text = std::iter::once("CatPrinter test page".to_string())
+        .chain(info.iter().cloned())
+        .collect::<Vec<_>>()
+        .join("\n");
+    page.extend(font::rasterize_text(&text, width, TEST_PAGE_FONT_SIZE));
+    gap(&mut page);
+
+    let wedge_width = width / TEST_PAGE_WEDGES.len();
+    let level = |x: u32| {
+        let i = (x as usize / wedge_width.max(1)).min(TEST_PAGE_WEDGES.len() - 1);
+        TEST_PAGE_WEDGES[i]
+    };
+    let mut wedges = GrayImage::from_fn(width as u32, 64, |x, _| Luma([level(x)]));
+    ordered_dither(&mut wedges, &BAYER_8);
+    for (x, y, p) in wedges.enumerate_pixels_mut() {
+        let edge = (x as usize).is_multiple_of(wedge_width.max(1))
+            || x as usize == width - 1
+            || y == 0
+            || y == 63;
+        if edge {
+            *p = Luma([0]);
+        } else if level(x) == 255 {
+            // Bayer leaves one dot in 64 even at full white
+            *p = Luma([255]);
+        }
+    }
+    page.extend(wedges.into_raw());
+    gap(&mut page);
+
+    let mut ramp = GrayImage::from_fn(width as u32, 32, |x, _| {
+        Luma([(x as usize * 255 / width.saturating_sub(1).max(1)) as u8])
+    });
+    ordered_dither(&mut ramp, &BAYER_8);
+    page.extend(ramp.into_raw());
+    gap(&mut page);
+    rule(&mut page);
+
+    let height = page.len().checked_div(width).unwrap_or(0);
+    (page, height)
+}
+
+/// Adds white rows above and below an image, e.g. for even label spacing.
+/// If it is still shorter than `min_height`, the missing rows are split
+/// between top and bottom so the image sits centered vertically (an odd
+/// leftover row goes to the bottom).
+///
+/// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+/// - `width`: image width
+/// - `top`, `bottom`: white rows to add above and below
+/// - `min_height`: height to pad up to; 0 for no minimum
+///
+/// Returns (pixels, height)
+pub fn pad_pixels_vertical(
+    pixels: &[u8],
+    width: usize,
+    top: usize,
+    bottom: usize,
+    min_height: usize,
+) -> (Vec<u8>, usize) {
+    let height = pixels.len().checked_div(width).unwrap_or(0);
+    let missing = min_height.saturating_sub(top + height + bottom);
+    let top = top + missing / 2;
+    let bottom = bottom + missing - missing / 2;
+    let mut out = vec![255u8; width * top];
+    out.extend_from_slice(&pixels[..width * height]);
+    out.resize(out.len() + width * bottom, 255);
+    (out, top + height + bottom)
+}
+
+/// Splits an over-wide image into vertical tiles of `tile_width` columns.
+/// The last tile is padded with white so every tile is exactly `tile_width` wide.
+///
+/// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+/// - `width`, `height`: image dimensions
+/// - `tile_width`: width of each tile
+///
+/// Returns tiles left to right, each `tile_width * height` pixels
+pub fn split_into_tiles(
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+    tile_width: usize,
+) -> Result<Vec<Vec<u8>>, &'static str> {
+    check_pixel_buffer(pixels, width, height)?;
+    if tile_width == 0 {
+        return Err("tile width must be > 0");
+    }
+    let mut tiles = Vec::new();
+    for x0 in (0..width).step_by(tile_width) {
+        let cols = usize::min(tile_width, width - x0);
+        let mut tile = vec![255u8; tile_width * height];
+        for row in 0..height {
+            let src = row * width + x0;
+            let dst = row * tile_width;
+            tile[dst..dst + cols].copy_from_slice(&pixels[src..src + cols]);
+        }
+        tiles.push(tile);
+    }
+    Ok(tiles)
+}
+
+/// Finds the smallest rectangle holding every non-white pixel.
+///
+/// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+/// - `width`, `height`: image dimensions
+/// - `white_threshold`: pixels at or above this value count as background
+///
+/// Returns Some((x, y, w, h)), or None if the image is blank or the buffer
+/// doesn't hold width*height pixels
+pub fn content_bounding_box(
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+    white_threshold: u8,
+) -> Option<(usize, usize, usize, usize)> {
+    if width == 0 || pixels.len() != width * height {
+        return None;
+    }
+    let (mut left, mut right) = (width, 0);
+    let (mut top, mut bottom) = (None, 0);
+    for (y, row) in pixels.chunks_exact(width).enumerate() {
+        let Some(first) = row.iter().position(|&p| p < white_threshold) else {
+            continue;
+        };
+        let last = row
+            .iter()
+            .rposition(|&p| p < white_threshold)
+            .unwrap_or(first);
+        top.get_or_insert(y);
+        bottom = y;
+        left = left.min(first);
+        right = right.max(last);
+    }
+    let top = top?;
+    Some((left, top, right - left + 1, bottom - top + 1))
+}
+
+/// Inverts a pixel buffer in-place (0 <-> 255), for white-on-black prints.
+///
+/// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+pub fn invert_pixels(pixels: &mut [u8]) {
+    for p in pixels.iter_mut() {
+        *p = 255 - *p;
+    }
+}
+
+/// Orientation change applied to a pixel buffer before printing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Transform {
+    /// Print as-is.
+    #[default]
+    None,
+    /// Rotate by 180° (what text needs to come out reading forwards).
+    Rotate180,
+    /// Mirror left-right, e.g. for iron-on transfers.
+    FlipH,
+    /// Mirror top-bottom.
+    FlipV,
+}
+
+/// Applies a Transform to a pixel buffer.
+///
+/// - `pixels`: grayscale buffer (row-major), `width * height` long
+/// - `width`, `height`: image dimensions
+/// - `transform`: orientation change
+///
+/// Returns the transformed buffer, same dimensions
+pub fn transform_pixels(
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+    transform: Transform,
+) -> Vec<u8> {
+    let mut out = vec![0u8; pixels.len()];
     for row in 0..height {
         for col in 0..width {
             let src_idx = row * width + col;
-            let dst_idx = (height - 1 - row) * width + (width - 1 - col);
-            rotated[dst_idx] = pixels[src_idx];
+            let dst_idx = match transform {
+                Transform::None => src_idx,
+                Transform::Rotate180 => (height - 1 - row) * width + (width - 1 - col),
+                Transform::FlipH => row * width + (width - 1 - col),
+                Transform::FlipV => (height - 1 - row) * width + col,
+            };
+            out[dst_idx] = pixels[src_idx];
         }
     }
-    rotated
+    out
+}
+
+/// Rotates and mirrors a pixel buffer for CatPrinter (180° rotation).
+/// Same as transform_pixels with Transform::Rotate180.
+/// Input: pixels (row-major, 0=black, 255=white), width, height
+/// Output: rotated and mirrored pixel buffer
+pub fn rotate_mirror_pixels(pixels: &[u8], width: usize, height: usize) -> Vec<u8> {
+    transform_pixels(pixels, width, height, Transform::Rotate180)
 }
 
 /// Renders text and author signature to a grayscale pixel buffer for printing.
 ///
 /// - `main`: main text
-/// - `author`: author name
+/// - `author`: author name; empty prints no signature line
 /// - `width`: output image width
 ///
 /// Returns Vec<u8> (row-major, 0=black, 255=white)
 pub fn render_text_to_pixels(main: &str, author: &str, width: usize) -> Vec<u8> {
+    render_text_to_pixels_opts(main, author, width, DEFAULT_TEXT_SIZE, 0)
+}
+
+/// Font size used by render_text_to_pixels.
+pub const DEFAULT_TEXT_SIZE: f32 = 48.0;
+
+/// How the author signature is set off from the main text.
+///
+/// - `separator`: printed right before the author name (default: "-- ")
+/// - `blank_lines`: empty lines between the text and the signature (default: 1)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignatureStyle {
+    pub separator: String,
+    pub blank_lines: usize,
+}
+
+impl Default for SignatureStyle {
+    fn default() -> Self {
+        Self {
+            separator: "-- ".to_string(),
+            blank_lines: 1,
+        }
+    }
+}
+
+/// Renders text and author signature with a custom font size and side margins.
+///
+/// Text wraps within `width - 2 * margin_px` and is shifted right by
+/// `margin_px`; the buffer stays `width` wide. Margins are equal on both
+/// sides, so the text stays centered after the 180° rotate_mirror_pixels
+/// step (which swaps left and right).
+///
+/// - `main`: main text
+/// - `author`: author name; empty prints no signature line
+/// - `width`: output image width
+/// - `font_size`: font size in points
+/// - `margin_px`: blank columns on each side (reduced if it leaves no room)
+///
+/// Returns Vec<u8> (row-major, 0=black, 255=white)
+pub fn render_text_to_pixels_opts(
+    main: &str,
+    author: &str,
+    width: usize,
+    font_size: f32,
+    margin_px: usize,
+) -> Vec<u8> {
+    render_text_to_pixels_signed(
+        main,
+        author,
+        width,
+        font_size,
+        margin_px,
+        &SignatureStyle::default(),
+    )
+}
+
+/// Renders text like render_text_to_pixels_opts with a custom signature style.
+///
+/// - `main`: main text
+/// - `author`: author name; empty skips the blank lines and separator too
+/// - `width`: output image width
+/// - `font_size`: font size in points
+/// - `margin_px`: blank columns on each side (reduced if it leaves no room)
+/// - `signature`: separator and spacing of the signature line
+///
+/// Returns Vec<u8> (row-major, 0=black, 255=white)
+pub fn render_text_to_pixels_signed(
+    main: &str,
+    author: &str,
+    width: usize,
+    font_size: f32,
+    margin_px: usize,
+    signature: &SignatureStyle,
+) -> Vec<u8> {
     let mut full = String::new();
     full.push_str(main);
-    full.push('\n');
-    full.push('\n');
-    full.push_str("-- ");
-    full.push_str(author);
+    if !author.is_empty() {
+        for _ in 0..=signature.blank_lines {
+            full.push('\n');
+        }
+        full.push_str(&signature.separator);
+        full.push_str(author);
+    }
+
+    let margin = margin_px.min(width.saturating_sub(1) / 2);
+    let inner = width - 2 * margin;
+    let text = font::rasterize_text(&full, inner, font_size);
+    if margin == 0 {
+        return text;
+    }
+    let height = text.len().checked_div(inner).unwrap_or(0);
+    let mut pixels = vec![255u8; width * height];
+    for (row, line) in text.chunks_exact(inner).enumerate() {
+        let start = row * width + margin;
+        pixels[start..start + inner].copy_from_slice(line);
+    }
+    pixels
+}
+
+/// Size of `# ` title lines relative to body text in render_formatted_to_pixels.
+pub const TITLE_SCALE: f32 = 1.5;
+
+/// Renders lightly formatted text, e.g. a receipt note, to a grayscale buffer.
+///
+/// The syntax is deliberately tiny:
+/// - a line starting with `# ` is a title, rendered at TITLE_SCALE x `font_size`
+/// - one or more blank lines end a paragraph and leave half a line of space
+/// - every other line is body text at `font_size`; consecutive body lines keep
+///   their line breaks and long lines wrap as in rasterize_text
+///
+/// - `text`: formatted text
+/// - `width`: output image width
+/// - `font_size`: body font size in points
+///
+/// Returns Vec<u8> (row-major, 0=black, 255=white)
+pub fn render_formatted_to_pixels(text: &str, width: usize, font_size: f32) -> Vec<u8> {
+    // (text, font size, paragraph break before)
+    let mut blocks: Vec<(String, f32, bool)> = Vec::new();
+    let mut paragraph_break = false;
+    let mut in_body = false;
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            paragraph_break = !blocks.is_empty();
+            in_body = false;
+        } else if let Some(title) = line.strip_prefix("# ") {
+            blocks.push((
+                title.trim().to_string(),
+                font_size * TITLE_SCALE,
+                paragraph_break,
+            ));
+            paragraph_break = false;
+            in_body = false;
+        } else if let (true, Some(body)) = (in_body, blocks.last_mut()) {
+            body.0.push('\n');
+            body.0.push_str(line);
+        } else {
+            blocks.push((line.to_string(), font_size, paragraph_break));
+            paragraph_break = false;
+            in_body = true;
+        }
+    }
+
+    let gap = (font_size / 2.0).round() as usize;
+    let mut pixels = Vec::new();
+    for (content, size, break_before) in blocks {
+        if break_before {
+            pixels.resize(pixels.len() + gap * width, 255);
+        }
+        pixels.extend_from_slice(&font::rasterize_text(&content, width, size));
+    }
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_4bpp_odd_width_half_fills_the_last_byte() {
+        // 3x2: white, black, mid-gray / black, white, black
+        let pixels = [255, 0, 0x77, 0, 255, 0];
+        let packed = pack_4bpp_pixels(&pixels, 3, 2).unwrap();
+        assert_eq!(packed, [0xF0, 0x08, 0x0F, 0x0F]);
+    }
+
+    #[test]
+    fn pack_4bpp_levels() {
+        let pixels = [255, 0xF0, 0x0F, 0];
+        assert_eq!(pack_4bpp_pixels(&pixels, 4, 1).unwrap(), [0x00, 0xFF]);
+        assert_eq!(pack_4bpp_pixels(&pixels[..1], 1, 1).unwrap(), [0x00]);
+    }
 
-    let font_size = 24.0_f32 * 2.0;
+    #[test]
+    fn packer_follows_mode() {
+        let pixels = [0u8; 3];
+        assert_eq!(
+            pack_pixels_for_mode(&pixels, 3, 1, MODE_1BPP).unwrap(),
+            [0b111]
+        );
+        assert_eq!(
+            pack_pixels_for_mode(&pixels, 3, 1, MODE_4BPP).unwrap(),
+            [0xFF, 0x0F]
+        );
+        assert_eq!(
+            pack_pixels_for_mode(&pixels, 3, 1, 0x01),
+            Err("unsupported print mode")
+        );
+        assert_eq!(packed_row_bytes(3, MODE_4BPP), Ok(2));
+        assert_eq!(packed_row_bytes(384, MODE_1BPP), Ok(48));
+    }
 
-    font::rasterize_text(&full, width, font_size)
+    #[test]
+    fn stack_images_adds_heights_and_gaps() {
+        let a = [0u8; 4 * 2];
+        let b = [128u8; 4 * 3];
+        let (pixels, height) = stack_images(&[(&a, 4, 2), (&b, 4, 3), (&a, 4, 2)], 4, 5).unwrap();
+        assert_eq!(height, 2 + 5 + 3 + 5 + 2);
+        assert_eq!(pixels.len(), 4 * height);
+        assert!(pixels[..8].iter().all(|&p| p == 0));
+        assert!(pixels[8..8 + 20].iter().all(|&p| p == 255));
+        assert!(pixels[28..40].iter().all(|&p| p == 128));
+        assert_eq!(stack_images(&[(&a, 4, 2)], 4, 5).unwrap().1, 2);
+    }
+
+    #[test]
+    fn stack_images_rejects_bad_input() {
+        let a = [0u8; 8];
+        assert_eq!(stack_images(&[], 4, 0), Err("no images to stack"));
+        assert_eq!(
+            stack_images(&[(&a, 4, 2), (&a, 8, 1)], 4, 0),
+            Err("image width does not match printer width")
+        );
+        assert!(stack_images(&[(&a, 4, 3)], 4, 0).is_err());
+    }
+
+    #[test]
+    fn parse_device_info_fields() {
+        let info = parse_device_info(b"1.0.5\0MXW01 abc123\0\0").unwrap();
+        assert_eq!(info.firmware_version, "1.0.5");
+        assert_eq!(info.hardware_version.as_deref(), Some("MXW01"));
+        assert_eq!(info.serial.as_deref(), Some("abc123"));
+
+        let info = parse_device_info(b"2.1").unwrap();
+        assert_eq!(info.firmware_version, "2.1");
+        assert_eq!(info.hardware_version, None);
+        assert_eq!(info.serial, None);
+
+        assert!(parse_device_info(b"\0 \0").is_err());
+        assert!(parse_device_info(&[0xFF, 0xFE]).is_err());
+    }
+
+    #[test]
+    fn invert_twice_is_identity() {
+        let original: Vec<u8> = (0..=255).collect();
+        let mut pixels = original.clone();
+        invert_pixels(&mut pixels);
+        assert_eq!(pixels[0], 255);
+        assert_eq!(pixels[255], 0);
+        assert_eq!(pixels[100], 155);
+        invert_pixels(&mut pixels);
+        assert_eq!(pixels, original);
+    }
+
+    #[test]
+    fn transform_pixels_moves_pixels() {
+        // 3x2: 1 2 3 / 4 5 6
+        let pixels = [1, 2, 3, 4, 5, 6];
+        let t = |transform| transform_pixels(&pixels, 3, 2, transform);
+        assert_eq!(t(Transform::None), pixels);
+        assert_eq!(t(Transform::Rotate180), [6, 5, 4, 3, 2, 1]);
+        assert_eq!(t(Transform::FlipH), [3, 2, 1, 6, 5, 4]);
+        assert_eq!(t(Transform::FlipV), [4, 5, 6, 1, 2, 3]);
+        // Each one undoes itself
+        for transform in [Transform::Rotate180, Transform::FlipH, Transform::FlipV] {
+            assert_eq!(transform_pixels(&t(transform), 3, 2, transform), pixels);
+        }
+        assert_eq!(
+            transform_pixels(&t(Transform::FlipH), 3, 2, Transform::FlipV),
+            t(Transform::Rotate180)
+        );
+    }
+
+    #[test]
+    fn content_bounding_box_finds_the_ink() {
+        // 5x4, ink at (1,1) and (3,2); 200 is below the threshold, 240 isn't
+        let mut pixels = vec![255u8; 5 * 4];
+        pixels[5 + 1] = 0;
+        pixels[2 * 5 + 3] = 200;
+        pixels[3 * 5 + 4] = 240;
+        assert_eq!(content_bounding_box(&pixels, 5, 4, 230), Some((1, 1, 3, 2)));
+        assert_eq!(content_bounding_box(&pixels, 5, 4, 250), Some((1, 1, 4, 3)));
+        assert_eq!(content_bounding_box(&pixels, 5, 4, 100), Some((1, 1, 1, 1)));
+
+        assert_eq!(content_bounding_box(&[255; 20], 5, 4, 230), None);
+        assert_eq!(
+            content_bounding_box(&[0; 20], 5, 4, 230),
+            Some((0, 0, 5, 4))
+        );
+        assert_eq!(content_bounding_box(&[0; 19], 5, 4, 230), None);
+        assert_eq!(content_bounding_box(&[], 0, 0, 230), None);
+    }
+
+    #[test]
+    fn empty_author_adds_no_signature_rows() {
+        let width = PRINTER_WIDTH;
+        let plain = crate::font::rasterize_text("Hello", width, DEFAULT_TEXT_SIZE);
+        assert_eq!(render_text_to_pixels("Hello", "", width), plain);
+
+        let line = plain.len() / width;
+        let signed = render_text_to_pixels("Hello", "Ann", width);
+        // Text, one blank line and the signature line
+        assert_eq!(signed.len() / width, 3 * line);
+        assert_eq!(&signed[..plain.len()], &plain[..]);
+        assert!(signed[line * width..2 * line * width]
+            .iter()
+            .all(|&p| p == 255));
+        assert!(signed[2 * line * width..].iter().any(|&p| p < 128));
+
+        let tight = SignatureStyle {
+            separator: String::new(),
+            blank_lines: 0,
+        };
+        let signed =
+            render_text_to_pixels_signed("Hello", "Ann", width, DEFAULT_TEXT_SIZE, 0, &tight);
+        assert_eq!(signed.len() / width, 2 * line);
+        let unsigned =
+            render_text_to_pixels_signed("Hello", "", width, DEFAULT_TEXT_SIZE, 0, &tight);
+        assert_eq!(unsigned, plain);
+    }
+
+    #[test]
+    fn sleep_timeout_packets_and_parser() {
+        let get = build_get_sleep_timeout_packet();
+        assert_eq!(get[..6], [0x22, 0x21, 0xA7, 0x00, 0x01, 0x00]);
+        assert_eq!(get[6], 0x00);
+        let set = build_set_sleep_timeout_packet(0x0102);
+        assert_eq!(set[..6], [0x22, 0x21, 0xA8, 0x00, 0x02, 0x00]);
+        assert_eq!(set[6..8], [0x02, 0x01]);
+        assert_eq!(set[8], crate::wire::crc8(&[0x02, 0x01]));
+        assert_eq!(set[9], 0xFF);
+
+        assert_eq!(parse_sleep_timeout(&[0x02, 0x01]), Some(0x0102));
+        assert_eq!(parse_sleep_timeout(&[0x1E, 0x00, 0xAA]), Some(30));
+        assert_eq!(parse_sleep_timeout(&[0x00, 0x00]), Some(SLEEP_NEVER));
+        assert_eq!(parse_sleep_timeout(&[0x05]), None);
+        assert_eq!(parse_sleep_timeout(&[]), None);
+    }
+
+    #[test]
+    fn repeat_packed_rows_counts_lines_and_gaps() {
+        let packed = [1u8, 2, 3, 4]; // two rows of two bytes
+        let (data, lines) = repeat_packed_rows(&packed, 2, 3, 1).unwrap();
+        assert_eq!(lines, 3 * 2 + 2);
+        assert_eq!(data, [1, 2, 3, 4, 0, 0, 1, 2, 3, 4, 0, 0, 1, 2, 3, 4]);
+
+        assert_eq!(
+            repeat_packed_rows(&packed, 2, 1, 9).unwrap(),
+            (packed.to_vec(), 2)
+        );
+        assert_eq!(repeat_packed_rows(&packed, 2, 2, 0).unwrap().1, 4);
+        assert!(repeat_packed_rows(&packed, 2, 0, 0).is_err());
+        // 2 rows * 32768 copies is 65536 lines, one too many
+        assert_eq!(repeat_packed_rows(&packed, 2, 32767, 0).unwrap().1, 65534);
+        assert!(repeat_packed_rows(&packed, 2, 32768, 0).is_err());
+        assert!(repeat_packed_rows(&packed, 2, 2, u16::MAX).is_err());
+    }
+
+    #[test]
+    fn parse_battery_reads_the_first_byte() {
+        assert_eq!(parse_battery(&[87]), Some(87));
+        assert_eq!(parse_battery(&[100, 0x01, 0x02]), Some(100));
+        assert_eq!(parse_battery(&[0]), Some(0));
+        assert_eq!(parse_battery(&[]), None);
+    }
+
+    #[test]
+    fn pad_pixels_vertical_height() {
+        let pixels = [0u8; 4 * 3];
+        let (out, height) = pad_pixels_vertical(&pixels, 4, 2, 1, 0);
+        assert_eq!(height, 6);
+        assert_eq!(out.len(), 4 * 6);
+        assert!(out[..8].iter().all(|&p| p == 255));
+        assert!(out[8..20].iter().all(|&p| p == 0));
+        assert!(out[20..].iter().all(|&p| p == 255));
+
+        // min_height centers the image, the odd leftover row going to the bottom
+        let (out, height) = pad_pixels_vertical(&pixels, 4, 0, 0, 8);
+        assert_eq!(height, 8);
+        assert_eq!(out, [vec![255u8; 8], vec![0; 12], vec![255; 12]].concat());
+
+        // Padding counts towards min_height
+        assert_eq!(pad_pixels_vertical(&pixels, 4, 2, 1, 8).1, 8);
+        assert_eq!(pad_pixels_vertical(&pixels, 4, 2, 1, 5).1, 6);
+        assert_eq!(
+            pad_pixels_vertical(&pixels, 4, 0, 0, 0),
+            (pixels.to_vec(), 3)
+        );
+    }
 }
@@ -0,0 +1,132 @@
+use crate::error::PrinterError;
+use qrcode::{Color, QrCode};
+
+pub use qrcode::EcLevel;
+
+/// Error-correction level used when none is given (~15% recovery).
+pub const DEFAULT_EC_LEVEL: EcLevel = EcLevel::M;
+
+/// Quiet-zone width in modules; scanners need at least 4 on each side.
+const QUIET_ZONE: usize = 4;
+
+/// Renders a QR code into a grayscale pixel buffer for printing.
+///
+/// - `data`: text or URL to encode
+/// - `scale`: pixels per QR module (reduced if the code would not fit)
+/// - `ec_level`: error-correction level
+/// - `width`: output image width in pixels; the code is centered horizontally
+///
+/// Returns (pixels, height) with pixels row-major, 0=black, 255=white
+pub fn render_qr_to_pixels(
+    data: &str,
+    scale: u32,
+    ec_level: EcLevel,
+    width: usize,
+) -> Result<(Vec<u8>, usize), PrinterError> {
+    let code = QrCode::with_error_correction_level(data, ec_level)
+        .map_err(|e| PrinterError::Image(format!("qr encode error: {}", e)))?;
+    let modules = code.width();
+    let colors = code.to_colors();
+
+    let total_modules = modules + 2 * QUIET_ZONE;
+    let scale = usize::min(scale.max(1) as usize, width / total_modules);
+    if scale == 0 {
+        return Err(PrinterError::Image(
+            "QR code too large for printer width".into(),
+        ));
+    }
+
+    let side = total_modules * scale;
+    let x_off = (width - side) / 2;
+    let mut pixels = vec![255u8; width * side];
+    for (i, color) in colors.iter().enumerate() {
+        if *color != Color::Dark {
+            continue;
+        }
+        let mx = i % modules + QUIET_ZONE;
+        let my = i / modules + QUIET_ZONE;
+        for dy in 0..scale {
+            let row = (my * scale + dy) * width;
+            let start = row + x_off + mx * scale;
+            pixels[start..start + scale].fill(0);
+        }
+    }
+    Ok((pixels, side))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Inked (x, y) bounds of a rendered code.
+    fn ink_bounds(pixels: &[u8], width: usize) -> (usize, usize, usize, usize) {
+        let inked: Vec<(usize, usize)> = pixels
+            .iter()
+            .enumerate()
+            .filter(|&(_, &p)| p == 0)
+            .map(|(i, _)| (i % width, i / width))
+            .collect();
+        let xs = inked.iter().map(|&(x, _)| x);
+        let ys = inked.iter().map(|&(_, y)| y);
+        (
+            xs.clone().min().unwrap(),
+            xs.max().unwrap(),
+            ys.clone().min().unwrap(),
+            ys.max().unwrap(),
+        )
+    }
+
+    #[test]
+    fn quiet_zone_and_centering() {
+        // "hello" at level M is a version 1 code: 21 modules, 29 with the quiet zone
+        let width = 384;
+        let scale = 4;
+        let (pixels, height) = render_qr_to_pixels("hello", scale, EcLevel::M, width).unwrap();
+        let side = 29 * scale as usize;
+        let quiet = QUIET_ZONE * scale as usize;
+        assert_eq!(height, side);
+        assert_eq!(pixels.len(), width * height);
+
+        let x_off = (width - side) / 2;
+        assert_eq!(x_off, 134);
+        // Finder patterns reach the corners of the code, so the ink starts and
+        // ends exactly at the quiet zone
+        assert_eq!(
+            ink_bounds(&pixels, width),
+            (
+                x_off + quiet,
+                x_off + side - quiet - 1,
+                quiet,
+                side - quiet - 1
+            )
+        );
+        for (y, row) in pixels.chunks_exact(width).enumerate() {
+            if y < quiet || y >= side - quiet {
+                assert!(row.iter().all(|&p| p == 255), "row {y}");
+            }
+            assert!(row[..x_off + quiet].iter().all(|&p| p == 255));
+            assert!(row[x_off + side - quiet..].iter().all(|&p| p == 255));
+        }
+    }
+
+    #[test]
+    fn scale_shrinks_to_fit_the_width() {
+        let (pixels, height) = render_qr_to_pixels("hello", 10, EcLevel::M, 100).unwrap();
+        // 100 / 29 modules leaves 3 pixels per module
+        assert_eq!(height, 29 * 3);
+        assert_eq!(pixels.len(), 100 * height);
+        let (left, right, top, _) = ink_bounds(&pixels, 100);
+        assert_eq!((left, top), ((100 - 87) / 2 + 12, 12));
+        assert_eq!(right, (100 - 87) / 2 + 87 - 12 - 1);
+
+        // Scale 0 counts as 1
+        assert_eq!(
+            render_qr_to_pixels("hello", 0, EcLevel::M, 100).unwrap().1,
+            29
+        );
+        assert!(matches!(
+            render_qr_to_pixels("hello", 1, EcLevel::M, 28),
+            Err(PrinterError::Image(_))
+        ));
+    }
+}
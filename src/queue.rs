@@ -0,0 +1,349 @@
+use crate::ble::CatPrinterAsync;
+use crate::dithering::ImageDithering;
+use crate::error::PrinterError;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Work accepted by PrintQueue, mirroring the CatPrinterAsync print methods.
+#[derive(Debug, Clone)]
+pub enum QueuedJob {
+    /// Raw grayscale pixels, see CatPrinterAsync::print_image.
+    Image {
+        pixels: Vec<u8>,
+        width: usize,
+        height: usize,
+        mode: u8,
+    },
+    /// Text with an author signature, see CatPrinterAsync::print_text.
+    Text { main: String, author: String },
+    /// Image file, see CatPrinterAsync::print_image_from_path.
+    ImageFile {
+        path: String,
+        dithering: ImageDithering,
+    },
+    /// QR code, see CatPrinterAsync::print_qr.
+    Qr { data: String, scale: u32 },
+}
+
+/// Identifier handed out by PrintQueue::enqueue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JobId(pub u64);
+
+/// Progress of a queued job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    /// Waiting behind earlier jobs.
+    Pending,
+    /// Being sent to the printer.
+    Printing,
+    /// Printed successfully.
+    Done,
+    /// The print failed; holds the error message.
+    Failed(String),
+}
+
+/// Serializes print jobs from any number of tasks onto one printer.
+///
+/// Jobs run one at a time in FIFO order on a background tokio task, so a
+/// runtime must be running when the queue is created. Statuses of finished
+/// jobs are kept until forget is called for them.
+pub struct PrintQueue {
+    sender: mpsc::Sender<(JobId, QueuedJob)>,
+    statuses: Arc<Mutex<HashMap<JobId, JobStatus>>>,
+    next_id: AtomicU64,
+    worker: JoinHandle<CatPrinterAsync>,
+}
+
+impl PrintQueue {
+    /// Start the worker task.
+    ///
+    /// - `printer`: connected printer; the queue owns it until shutdown
+    /// - `capacity`: jobs that may wait at once (at least 1), not counting the
+    ///   one printing
+    pub fn new(printer: CatPrinterAsync, capacity: usize) -> Self {
+        let (sender, mut receiver) = mpsc::channel::<(JobId, QueuedJob)>(capacity.max(1));
+        let statuses = Arc::new(Mutex::new(HashMap::new()));
+        let worker_statuses = Arc::clone(&statuses);
+        let worker = tokio::spawn(async move {
+            while let Some((id, job)) = receiver.recv().await {
+                set_status(&worker_statuses, id, JobStatus::Printing);
+                let status = match run_job(&printer, job).await {
+                    Ok(()) => JobStatus::Done,
+                    Err(e) => JobStatus::Failed(e.to_string()),
+                };
+                set_status(&worker_statuses, id, status);
+            }
+            printer
+        });
+        Self {
+            sender,
+            statuses,
+            next_id: AtomicU64::new(0),
+            worker,
+        }
+    }
+
+    /// Add a job to the end of the queue.
+    ///
+    /// When `capacity` jobs are already waiting this waits for a free slot,
+    /// which pushes backpressure onto the caller; use try_enqueue to fail fast.
+    ///
+    /// Returns the job's id
+    pub async fn enqueue(&self, job: QueuedJob) -> Result<JobId, PrinterError> {
+        let id = self.register();
+        if self.sender.send((id, job)).await.is_err() {
+            self.forget(id);
+            return Err(PrinterError::Transport("print queue worker stopped".into()));
+        }
+        Ok(id)
+    }
+
+    /// Add a job without waiting.
+    ///
+    /// Returns the job's id, or Err(PrinterError::Busy) if the queue is full
+    pub fn try_enqueue(&self, job: QueuedJob) -> Result<JobId, PrinterError> {
+        let id = self.register();
+        match self.sender.try_send((id, job)) {
+            Ok(()) => Ok(id),
+            Err(e) => {
+                self.forget(id);
+                Err(match e {
+                    mpsc::error::TrySendError::Full(_) => PrinterError::Busy,
+                    mpsc::error::TrySendError::Closed(_) => {
+                        PrinterError::Transport("print queue worker stopped".into())
+                    }
+                })
+            }
+        }
+    }
+
+    /// Current status of a job, or None for unknown (or forgotten) ids.
+    pub fn status(&self, id: JobId) -> Option<JobStatus> {
+        lock(&self.statuses).get(&id).cloned()
+    }
+
+    /// Drop the stored status of a job, e.g. once its result was handled.
+    pub fn forget(&self, id: JobId) {
+        lock(&self.statuses).remove(&id);
+    }
+
+    /// Stop accepting jobs, finish the ones already queued and hand back the
+    /// printer, e.g. to disconnect it.
+    pub async fn shutdown(self) -> Result<CatPrinterAsync, PrinterError> {
+        drop(self.sender);
+        self.worker
+            .await
+            .map_err(|e| PrinterError::Transport(format!("print queue worker failed: {}", e)))
+    }
+
+    fn register(&self) -> JobId {
+        let id = JobId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        set_status(&self.statuses, id, JobStatus::Pending);
+        id
+    }
+}
+
+async fn run_job(printer: &CatPrinterAsync, job: QueuedJob) -> Result<(), PrinterError> {
+    match job {
+        QueuedJob::Image {
+            pixels,
+            width,
+            height,
+            mode,
+        } => {
+            printer
+                .print_image(&pixels, width, height, mode, None)
+                .await
+        }
+        QueuedJob::Text { main, author } => printer.print_text(&main, &author).await,
+        QueuedJob::ImageFile { path, dithering } => {
+            printer.print_image_from_path(&path, dithering).await
+        }
+        QueuedJob::Qr { data, scale } => printer.print_qr(&data, scale).await,
+    }
+}
+
+fn set_status(statuses: &Mutex<HashMap<JobId, JobStatus>>, id: JobId, status: JobStatus) {
+    lock(statuses).insert(id, status);
+}
+
+// Status updates can't leave the map half-written, so a poisoned lock is still usable
+fn lock(
+    statuses: &Mutex<HashMap<JobId, JobStatus>>,
+) -> std::sync::MutexGuard<'_, HashMap<JobId, JobStatus>> {
+    statuses.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ble::TransportAsync;
+    use crate::protocol::{pack_1bpp_pixels, MODE_1BPP};
+    use crate::testutil::MockTransportAsync;
+    use async_trait::async_trait;
+    use std::time::Duration;
+    use tokio::sync::Semaphore;
+
+    /// MockTransportAsync whose reads wait for a permit, so a test can hold a
+    /// job in Printing and release it step by step.
+    #[derive(Clone)]
+    struct GatedTransport {
+        inner: MockTransportAsync,
+        gate: Arc<Semaphore>,
+    }
+
+    #[async_trait]
+    impl TransportAsync for GatedTransport {
+        async fn write_control(&self, data: &[u8]) -> Result<(), PrinterError> {
+            self.inner.write_control(data).await
+        }
+
+        async fn write_data(&self, data: &[u8]) -> Result<(), PrinterError> {
+            self.inner.write_data(data).await
+        }
+
+        async fn read_notification(&self, timeout: Duration) -> Result<Vec<u8>, PrinterError> {
+            self.gate.acquire().await.unwrap().forget();
+            self.inner.read_notification(timeout).await
+        }
+    }
+
+    fn gated_queue(capacity: usize) -> (PrintQueue, MockTransportAsync, Arc<Semaphore>) {
+        let transport = GatedTransport {
+            inner: MockTransportAsync::new(),
+            gate: Arc::new(Semaphore::new(0)),
+        };
+        let (mock, gate) = (transport.inner.clone(), Arc::clone(&transport.gate));
+        let printer = CatPrinterAsync::new(Box::new(transport));
+        (PrintQueue::new(printer, capacity), mock, gate)
+    }
+
+    fn image(pixels: Vec<u8>) -> QueuedJob {
+        QueuedJob::Image {
+            width: pixels.len(),
+            height: 1,
+            pixels,
+            mode: MODE_1BPP,
+        }
+    }
+
+    async fn wait_for(queue: &PrintQueue, id: JobId, status: JobStatus) {
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while queue.status(id).as_ref() != Some(&status) {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap_or_else(|_| panic!("{:?} stuck at {:?}", id, queue.status(id)));
+    }
+
+    #[tokio::test]
+    async fn jobs_run_in_order_through_each_status() {
+        let (queue, mock, gate) = gated_queue(4);
+        let first = vec![0u8; 8];
+        let last = [vec![255u8; 8], vec![0; 8]].concat();
+        mock.lock().push_print_success();
+        mock.lock().push_print_success();
+
+        let a = queue.try_enqueue(image(first.clone())).unwrap();
+        // Nothing black to print, fails before touching the printer
+        let blank = QueuedJob::Text {
+            main: String::new(),
+            author: String::new(),
+        };
+        let b = queue.try_enqueue(blank).unwrap();
+        let c = queue.try_enqueue(image(last.clone())).unwrap();
+        assert!(a < b && b < c);
+        for id in [a, b, c] {
+            assert_eq!(queue.status(id), Some(JobStatus::Pending));
+        }
+
+        // The first job blocks on its print request ack
+        wait_for(&queue, a, JobStatus::Printing).await;
+        assert_eq!(queue.status(b), Some(JobStatus::Pending));
+        assert_eq!(queue.status(c), Some(JobStatus::Pending));
+
+        // Ack and print complete finish it; the blank job fails right away
+        gate.add_permits(2);
+        wait_for(&queue, a, JobStatus::Done).await;
+        wait_for(&queue, c, JobStatus::Printing).await;
+        assert!(matches!(queue.status(b), Some(JobStatus::Failed(msg)) if msg.contains("image")));
+
+        gate.add_permits(2);
+        wait_for(&queue, c, JobStatus::Done).await;
+        queue.forget(a);
+        assert_eq!(queue.status(a), None);
+
+        queue.shutdown().await.unwrap();
+        let expected = [
+            pack_1bpp_pixels(&first, 8, 1).unwrap(),
+            pack_1bpp_pixels(&last, 16, 1).unwrap(),
+        ]
+        .concat();
+        assert_eq!(mock.lock().data_bytes(), expected);
+    }
+
+    #[tokio::test]
+    async fn try_enqueue_is_busy_when_full() {
+        let (queue, mock, gate) = gated_queue(1);
+        mock.lock().push_print_success();
+        mock.lock().push_print_success();
+
+        let a = queue.try_enqueue(image(vec![0; 8])).unwrap();
+        // The one slot is taken until the worker picks the job up
+        assert!(matches!(
+            queue.try_enqueue(image(vec![0; 8])),
+            Err(PrinterError::Busy)
+        ));
+        assert_eq!(queue.status(JobId(a.0 + 1)), None);
+
+        wait_for(&queue, a, JobStatus::Printing).await;
+        let b = queue.try_enqueue(image(vec![0; 8])).unwrap();
+        assert!(matches!(
+            queue.try_enqueue(image(vec![0; 8])),
+            Err(PrinterError::Busy)
+        ));
+
+        // shutdown finishes the queued job before handing the printer back
+        gate.add_permits(4);
+        let statuses = Arc::clone(&queue.statuses);
+        queue.shutdown().await.unwrap();
+        assert_eq!(lock(&statuses).get(&b), Some(&JobStatus::Done));
+        assert_eq!(mock.lock().data_bytes(), [0xFF, 0xFF]);
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn loopback_printer_runs_jobs_in_fifo_order() {
+        use crate::testutil::LoopbackTransportAsync;
+
+        let transport = LoopbackTransportAsync::new();
+        let printer = CatPrinterAsync::new(Box::new(transport.clone()))
+            .with_ack_timeout(Duration::from_millis(100))
+            .with_print_timeout(Duration::from_millis(100));
+        let queue = PrintQueue::new(printer, 4);
+
+        let first = vec![0u8; 16];
+        let second = [vec![0u8; 8], vec![255; 8]].concat();
+        let a = queue.enqueue(image(first.clone())).await.unwrap();
+        // 385 pixels is wider than the print head
+        let wide = queue.enqueue(image(vec![0; 385])).await.unwrap();
+        let b = queue.enqueue(image(second.clone())).await.unwrap();
+        wait_for(&queue, b, JobStatus::Done).await;
+        assert_eq!(queue.status(a), Some(JobStatus::Done));
+        assert!(matches!(queue.status(wide), Some(JobStatus::Failed(_))));
+        queue.shutdown().await.unwrap();
+
+        let loopback = transport.lock();
+        assert_eq!(
+            loopback.jobs,
+            [
+                pack_1bpp_pixels(&first, 16, 1).unwrap(),
+                pack_1bpp_pixels(&second, 16, 1).unwrap(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use crate::ble::TransportAsync;
+use crate::error::PrinterError;
+use crate::printer::Transport;
+use crate::protocol::{build_control_packet, Command};
+#[cfg(feature = "testing")]
+use crate::protocol::{packed_row_bytes, parse_control_packet, PRINTER_WIDTH};
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// In-memory Transport for exercising CatPrinter without hardware.
+///
+/// - `control_writes`: every packet passed to write_control, in order
+/// - `data_writes`: every chunk passed to write_data, in order
+/// - `notifications`: queued responses popped by read_notification, which
+///   returns Timeout once they run out
+/// - `fail_data`: while set, write_data returns a Transport error
+/// - `mtu`: link MTU MockTransportAsync reports (default: None)
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    pub control_writes: Vec<Vec<u8>>,
+    pub data_writes: Vec<Vec<u8>>,
+    pub notifications: VecDeque<Vec<u8>>,
+    pub fail_data: bool,
+    pub mtu: Option<u16>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a raw notification to be returned by read_notification.
+    pub fn push_notification(&mut self, data: Vec<u8>) {
+        self.notifications.push_back(data);
+    }
+
+    /// Queues a notification packet built from a command id and payload.
+    pub fn push_response(&mut self, command: impl Into<u8>, payload: &[u8]) {
+        self.push_notification(build_control_packet(command, payload));
+    }
+
+    /// Queues a 0xA9 ack followed by a 0xAA complete, enough for one
+    /// print_image/print_text call to succeed.
+    pub fn push_print_success(&mut self) {
+        self.push_response(Command::PrintStart, &[0x00]);
+        self.push_response(Command::PrintComplete, &[0x00]);
+    }
+
+    /// All data chunks concatenated, i.e. the packed image as sent.
+    pub fn data_bytes(&self) -> Vec<u8> {
+        self.data_writes.concat()
+    }
+}
+
+impl Transport for MockTransport {
+    fn write_control(&mut self, data: &[u8]) -> Result<(), PrinterError> {
+        self.control_writes.push(data.to_vec());
+        Ok(())
+    }
+
+    fn write_data(&mut self, data: &[u8]) -> Result<(), PrinterError> {
+        if self.fail_data {
+            return Err(PrinterError::Transport("data write failed".to_string()));
+        }
+        self.data_writes.push(data.to_vec());
+        Ok(())
+    }
+
+    fn read_notification(&mut self, timeout: Duration) -> Result<Vec<u8>, PrinterError> {
+        self.notifications
+            .pop_front()
+            .ok_or(PrinterError::Timeout(timeout))
+    }
+}
+
+/// In-memory TransportAsync for exercising CatPrinterAsync without hardware.
+///
+/// Clones share one MockTransport, so keep a clone to queue responses and
+/// inspect writes after boxing another into the printer. notification_stream
+/// yields the notifications queued when it is called, one frame each, then ends.
+#[derive(Debug, Clone, Default)]
+pub struct MockTransportAsync {
+    inner: Arc<Mutex<MockTransport>>,
+}
+
+impl MockTransportAsync {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Locks the shared MockTransport, e.g. to queue notifications or read
+    /// control_writes and data_bytes.
+    pub fn lock(&self) -> MutexGuard<'_, MockTransport> {
+        // A panic mid-call can't leave the plain Vec/VecDeque state invalid
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl TransportAsync for MockTransportAsync {
+    async fn write_control(&self, data: &[u8]) -> Result<(), PrinterError> {
+        self.lock().write_control(data)
+    }
+
+    async fn write_data(&self, data: &[u8]) -> Result<(), PrinterError> {
+        self.lock().write_data(data)
+    }
+
+    async fn read_notification(&self, timeout: Duration) -> Result<Vec<u8>, PrinterError> {
+        self.lock().read_notification(timeout)
+    }
+
+    async fn notification_stream(&self) -> Result<BoxStream<'static, Vec<u8>>, PrinterError> {
+        let frames: Vec<Vec<u8>> = self.lock().notifications.drain(..).collect();
+        Ok(stream::iter(frames).boxed())
+    }
+
+    async fn mtu(&self) -> Option<u16> {
+        self.lock().mtu
+    }
+}
+
+/// Transport that plays the printer side of the protocol, for running whole
+/// print jobs end-to-end without hardware or hand-written responses.
+///
+/// Every control packet is parsed with parse_control_packet and answered at
+/// once, so replies are queued in the order the commands were written:
+/// - 0xA1 status: a status payload reporting `battery` and `temperature`,
+///   state Printing while a job is open and Standby otherwise
+/// - 0xA9 print request: [0x00] and opens a job if `accept_prints`, else
+///   [0x01] (rejected)
+/// - 0xAB battery: [`battery`]
+/// - 0xA3/0xA4 feed/retract: [0x00]
+/// - 0xAD data end: closes the job and queues 0xAA [0x00] if the data written
+///   since the request is `line_count` whole rows no wider than `width`
+///   pixels in the requested mode; otherwise nothing is sent, as a real
+///   printer still waiting for data wouldn't, and the caller times out
+/// - 0xAC cancel: drops the open job without a reply
+/// - anything else (e.g. 0xA2 density): recorded, no reply
+///
+/// Packets that don't parse are rejected with PrinterError::Protocol.
+/// read_notification pops the oldest reply and returns Timeout when none is
+/// queued. Requires the `testing` feature.
+///
+/// - `width`: print head width in pixels (default: PRINTER_WIDTH)
+/// - `battery`: reported battery percent (default: 100)
+/// - `temperature`: reported head temperature (default: 30)
+/// - `accept_prints`: answer print requests with success (default: true)
+/// - `control_writes`: every (command_id, payload) received, in order
+/// - `jobs`: data of each completed job, concatenated in write order
+/// - `notifications`: replies waiting to be read
+#[cfg(feature = "testing")]
+#[derive(Debug)]
+pub struct LoopbackTransport {
+    pub width: usize,
+    pub battery: u8,
+    pub temperature: u8,
+    pub accept_prints: bool,
+    pub control_writes: Vec<(u8, Vec<u8>)>,
+    pub jobs: Vec<Vec<u8>>,
+    pub notifications: VecDeque<Vec<u8>>,
+    // (line_count, mode, data so far) of the job opened by the last 0xA9
+    job: Option<(u16, u8, Vec<u8>)>,
+}
+
+#[cfg(feature = "testing")]
+impl Default for LoopbackTransport {
+    fn default() -> Self {
+        Self {
+            width: PRINTER_WIDTH,
+            battery: 100,
+            temperature: 30,
+            accept_prints: true,
+            control_writes: Vec::new(),
+            jobs: Vec::new(),
+            notifications: VecDeque::new(),
+            job: None,
+        }
+    }
+}
+
+#[cfg(feature = "testing")]
+impl LoopbackTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a print request was accepted and its data end not seen yet.
+    pub fn job_open(&self) -> bool {
+        self.job.is_some()
+    }
+
+    fn reply(&mut self, command: Command, payload: &[u8]) {
+        self.notifications
+            .push_back(build_control_packet(command, payload));
+    }
+
+    /// Status payload in the layout parse_printer_status reads.
+    fn status_payload(&self) -> [u8; 13] {
+        let mut payload = [0u8; 13];
+        payload[6] = u8::from(self.job.is_some());
+        payload[9] = self.battery;
+        payload[10] = self.temperature;
+        payload
+    }
+
+    /// Whether `data` is `lines` whole rows no wider than the print head.
+    fn job_complete(&self, lines: u16, mode: u8, data: &[u8]) -> bool {
+        let Ok(max_row) = packed_row_bytes(self.width, mode) else {
+            return false;
+        };
+        let lines = usize::from(lines);
+        lines > 0
+            && data.len().is_multiple_of(lines)
+            && (1..=max_row).contains(&(data.len() / lines))
+    }
+}
+
+#[cfg(feature = "testing")]
+impl Transport for LoopbackTransport {
+    fn write_control(&mut self, data: &[u8]) -> Result<(), PrinterError> {
+        let (command_id, payload) = parse_control_packet(data)
+            .map_err(|e| PrinterError::Protocol(format!("loopback got a bad packet: {}", e)))?;
+        match Command::try_from(command_id) {
+            Ok(Command::GetStatus) => {
+                let status = self.status_payload();
+                self.reply(Command::GetStatus, &status);
+            }
+            Ok(Command::PrintStart) if self.accept_prints => {
+                let lines = match payload[..] {
+                    [lo, hi, ..] => u16::from_le_bytes([lo, hi]),
+                    _ => 0,
+                };
+                let mode = payload.get(3).copied().unwrap_or(0);
+                self.job = Some((lines, mode, Vec::new()));
+                self.reply(Command::PrintStart, &[0x00]);
+            }
+            Ok(Command::PrintStart) => self.reply(Command::PrintStart, &[0x01]),
+            Ok(Command::GetBattery) => self.reply(Command::GetBattery, &[self.battery]),
+            Ok(command @ (Command::FeedPaper | Command::RetractPaper)) => {
+                self.reply(command, &[0x00])
+            }
+            Ok(Command::PrintEnd) => {
+                if let Some((lines, mode, data)) = self.job.take() {
+                    if self.job_complete(lines, mode, &data) {
+                        self.jobs.push(data);
+                        self.reply(Command::PrintComplete, &[0x00]);
+                    } else {
+                        self.job = Some((lines, mode, data));
+                    }
+                }
+            }
+            Ok(Command::CancelPrint) => self.job = None,
+            _ => {}
+        }
+        self.control_writes.push((command_id, payload));
+        Ok(())
+    }
+
+    fn write_data(&mut self, data: &[u8]) -> Result<(), PrinterError> {
+        if let Some((_, _, job)) = &mut self.job {
+            job.extend_from_slice(data);
+        }
+        Ok(())
+    }
+
+    fn read_notification(&mut self, timeout: Duration) -> Result<Vec<u8>, PrinterError> {
+        self.notifications
+            .pop_front()
+            .ok_or(PrinterError::Timeout(timeout))
+    }
+}
+
+/// LoopbackTransport for CatPrinterAsync; clones share one LoopbackTransport,
+/// like MockTransportAsync. Requires the `testing` feature.
+#[cfg(feature = "testing")]
+#[derive(Debug, Clone, Default)]
+pub struct LoopbackTransportAsync {
+    inner: Arc<Mutex<LoopbackTransport>>,
+}
+
+#[cfg(feature = "testing")]
+impl LoopbackTransportAsync {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Locks the shared LoopbackTransport, e.g. to change the simulated
+    /// battery or read the completed jobs.
+    pub fn lock(&self) -> MutexGuard<'_, LoopbackTransport> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(feature = "testing")]
+#[async_trait]
+impl TransportAsync for LoopbackTransportAsync {
+    async fn write_control(&self, data: &[u8]) -> Result<(), PrinterError> {
+        self.lock().write_control(data)
+    }
+
+    async fn write_data(&self, data: &[u8]) -> Result<(), PrinterError> {
+        self.lock().write_data(data)
+    }
+
+    async fn read_notification(&self, timeout: Duration) -> Result<Vec<u8>, PrinterError> {
+        self.lock().read_notification(timeout)
+    }
+}
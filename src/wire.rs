@@ -0,0 +1,676 @@
+//! CatPrinter wire format: control packet framing, notification parsing,
+//! 1bpp pixel packing and data chunking.
+//!
+//! Needs only `core` and `alloc`, so firmware talking to the printer over its
+//! own BLE stack can build just this module with
+//! `default-features = false`. The protocol module re-exports everything here.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Computes CRC-8 for a byte slice (CatPrinter protocol).
+///
+/// - `data`: input bytes
+///
+/// Returns CRC-8 value
+pub fn crc8(data: &[u8]) -> u8 {
+    let poly: u8 = 0x07;
+    let mut crc: u8 = 0x00;
+
+    for &b in data {
+        crc ^= b;
+        for _ in 0..8 {
+            if (crc & 0x80) != 0 {
+                crc = (crc << 1) ^ poly;
+            } else {
+                crc <<= 1;
+            }
+        }
+    }
+    crc
+}
+
+/// Command ids of the MXW01 control protocol.
+///
+/// Requests and the notifications answering them share an id; PrintComplete
+/// and PrintProgress are only ever sent by the printer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Command {
+    /// Status query, answered with the status report
+    GetStatus = 0xA1,
+    /// Sets the print density/energy
+    SetDensity = 0xA2,
+    /// Feeds paper forward
+    FeedPaper = 0xA3,
+    /// Pulls paper back
+    RetractPaper = 0xA4,
+    /// Auto-sleep timer query; not confirmed for every firmware, printers
+    /// that don't know it stay silent and the query times out
+    GetSleepTimeout = 0xA7,
+    /// Sets the auto-sleep timer; not confirmed for every firmware
+    SetSleepTimeout = 0xA8,
+    /// Announces a print job (line count and mode)
+    PrintStart = 0xA9,
+    /// Job finished printing
+    PrintComplete = 0xAA,
+    /// Battery level query
+    GetBattery = 0xAB,
+    /// Drops the current job
+    CancelPrint = 0xAC,
+    /// Sent after the last data chunk
+    PrintEnd = 0xAD,
+    /// Firmware version and model query
+    GetDeviceInfo = 0xB1,
+    /// Lines printed so far, while a job prints
+    PrintProgress = 0xBB,
+}
+
+impl From<Command> for u8 {
+    fn from(command: Command) -> u8 {
+        command as u8
+    }
+}
+
+impl TryFrom<u8> for Command {
+    type Error = &'static str;
+
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        Ok(match id {
+            0xA1 => Command::GetStatus,
+            0xA2 => Command::SetDensity,
+            0xA3 => Command::FeedPaper,
+            0xA4 => Command::RetractPaper,
+            0xA7 => Command::GetSleepTimeout,
+            0xA8 => Command::SetSleepTimeout,
+            0xA9 => Command::PrintStart,
+            0xAA => Command::PrintComplete,
+            0xAB => Command::GetBattery,
+            0xAC => Command::CancelPrint,
+            0xAD => Command::PrintEnd,
+            0xB1 => Command::GetDeviceInfo,
+            0xBB => Command::PrintProgress,
+            _ => return Err("unknown command id"),
+        })
+    }
+}
+
+/// Builds a control packet for the CatPrinter protocol.
+///
+/// - `command`: a Command, or a raw command byte for ids not covered by it
+/// - `payload`: command payload
+///
+/// Returns Vec<u8> ready to send
+pub fn build_control_packet(command: impl Into<u8>, payload: &[u8]) -> Vec<u8> {
+    let command_id = command.into();
+    let mut out = Vec::with_capacity(2 + 1 + 1 + 2 + payload.len() + 1 + 1);
+    out.push(0x22);
+    out.push(0x21);
+    out.push(command_id);
+    out.push(0x00);
+    let len = payload.len() as u16;
+    out.push((len & 0xFF) as u8);
+    out.push((len >> 8) as u8);
+    out.extend_from_slice(payload);
+    out.push(crc8(payload));
+    out.push(0xFF);
+    out
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Notification {
+    pub command_id: u8,
+    pub unknown: u8,
+    pub payload: Vec<u8>,
+    pub crc: Option<u8>,
+}
+
+/// Parses a notification packet from the CatPrinter.
+///
+/// - `data`: raw notification bytes
+///
+/// Returns Notification struct on success
+pub fn parse_notification(data: &[u8]) -> Result<Notification, &'static str> {
+    if data.len() < 7 {
+        return Err("packet too short");
+    }
+    if data[0] != 0x22 || data[1] != 0x21 {
+        return Err("bad preamble");
+    }
+    let cmd = data[2];
+    let unknown = data[3];
+    let len_lo = data[4] as usize;
+    let len_hi = data[5] as usize;
+    let payload_len = (len_hi << 8) | len_lo;
+    if data.len() < 6 + payload_len {
+        return Err("not enough bytes for claimed payload length");
+    }
+    let payload = data[6..6 + payload_len].to_vec();
+    let crc = data.get(6 + payload_len).copied();
+    Ok(Notification {
+        command_id: cmd,
+        unknown,
+        payload,
+        crc,
+    })
+}
+
+/// Parses a notification packet and verifies its CRC.
+///
+/// - `data`: raw notification bytes
+///
+/// Returns Notification struct on success, Err("crc mismatch") if the
+/// trailing CRC byte is missing or doesn't match crc8(payload)
+pub fn parse_notification_checked(data: &[u8]) -> Result<Notification, &'static str> {
+    let notif = parse_notification(data)?;
+    if notif.crc != Some(crc8(&notif.payload)) {
+        return Err("crc mismatch");
+    }
+    Ok(notif)
+}
+
+/// Parses a packet made by build_control_packet, the exact inverse of it.
+///
+/// Checks the 0x22 0x21 preamble, the length field, the CRC and the trailing
+/// 0xFF, and rejects bytes after it.
+///
+/// - `data`: one complete control packet
+///
+/// Returns (command_id, payload), or Err describing the first check that failed
+pub fn parse_control_packet(data: &[u8]) -> Result<(u8, Vec<u8>), &'static str> {
+    let notif = parse_notification_checked(data)?;
+    let end = 6 + notif.payload.len() + 1;
+    match data.get(end) {
+        None => Err("missing 0xFF trailer"),
+        Some(&0xFF) if data.len() == end + 1 => Ok((notif.command_id, notif.payload)),
+        Some(&0xFF) => Err("unexpected bytes after packet"),
+        Some(_) => Err("bad trailer, expected 0xFF"),
+    }
+}
+
+/// Rebuilds whole packets from notification fragments, for links that
+/// split one packet over several characteristic-change events.
+///
+/// Feed every received buffer to push and drain next_packet. A packet is
+/// complete once its header, the payload length it claims and the CRC byte
+/// have arrived, so it can be checked with parse_notification_checked; the
+/// 0xFF trailer is taken along when it is already there. Bytes before a
+/// 0x22 0x21 preamble (e.g. a trailer that arrived late) are skipped.
+#[derive(Debug, Clone, Default)]
+pub struct PacketAssembler {
+    buffer: Vec<u8>,
+}
+
+impl PacketAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends received bytes.
+    pub fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Number of buffered bytes not yet returned as a packet.
+    pub fn pending(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Drops buffered bytes, e.g. after reconnecting.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Removes the next complete packet from the buffer.
+    ///
+    /// Returns the raw packet for parse_notification, or None until enough
+    /// bytes arrived
+    pub fn next_packet(&mut self) -> Option<Vec<u8>> {
+        match self.buffer.windows(2).position(|w| w == [0x22, 0x21]) {
+            Some(start) => {
+                self.buffer.drain(..start);
+            }
+            None => {
+                // Keep a trailing 0x22 in case the 0x21 comes in the next fragment
+                let keep = usize::from(self.buffer.last() == Some(&0x22));
+                self.buffer.drain(..self.buffer.len() - keep);
+                return None;
+            }
+        }
+        if self.buffer.len() < 6 {
+            return None;
+        }
+        let payload_len = u16::from_le_bytes([self.buffer[4], self.buffer[5]]) as usize;
+        // Header, payload and CRC
+        let mut end = 6 + payload_len + 1;
+        if self.buffer.len() < end {
+            return None;
+        }
+        if self.buffer.get(end) == Some(&0xFF) {
+            end += 1;
+        }
+        Some(self.buffer.drain(..end).collect())
+    }
+}
+
+/// Order of pixels within each packed 1bpp byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BitOrder {
+    /// Bit 0 is the leftmost pixel of the group (stock MXW01 firmware).
+    #[default]
+    Lsb0,
+    /// Bit 7 is the leftmost pixel of the group; try this if prints come out
+    /// scrambled within every 8-pixel group.
+    Msb0,
+}
+
+/// Packs a grayscale image buffer into 1bpp format for CatPrinter.
+///
+/// Rows are packed top to bottom; each group of 8 pixels becomes one byte
+/// with bit 0 = leftmost pixel (LSB-first), a set bit for black. Only 0 is
+/// black, every other value is white.
+///
+/// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+/// - `width`, `height`: image dimensions
+///
+/// Returns packed bytes in printer's expected layout
+pub fn pack_1bpp_pixels(
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+) -> Result<Vec<u8>, &'static str> {
+    pack_1bpp_pixels_ordered(pixels, width, height, BitOrder::Lsb0)
+}
+
+/// Packs a grayscale image buffer into 1bpp format with the given bit order.
+///
+/// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+/// - `width`, `height`: image dimensions
+/// - `bit_order`: which bit holds the leftmost pixel of each byte
+///
+/// Returns packed bytes
+pub fn pack_1bpp_pixels_ordered(
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+    bit_order: BitOrder,
+) -> Result<Vec<u8>, &'static str> {
+    check_pixel_buffer(pixels, width, height)?;
+    let bytes_per_row = width.div_ceil(8);
+    let mut out = Vec::with_capacity(bytes_per_row * height);
+    pack_rows_into_ordered(pixels, width, 0..height, &mut out, bit_order);
+    Ok(out)
+}
+
+/// Packs a range of rows into 1bpp format, appending to `out`.
+/// Same layout as pack_1bpp_pixels, so packing 0..h in pieces gives identical bytes.
+///
+/// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+/// - `width`: image width
+/// - `rows`: rows to pack; must lie within `pixels`
+/// - `out`: buffer the packed bytes are appended to
+pub fn pack_rows_into(pixels: &[u8], width: usize, rows: Range<usize>, out: &mut Vec<u8>) {
+    pack_rows_into_ordered(pixels, width, rows, out, BitOrder::Lsb0);
+}
+
+/// Packs a range of rows into 1bpp format with the given bit order, appending to `out`.
+///
+/// - `pixels`: grayscale buffer (row-major, 0=black, 255=white)
+/// - `width`: image width
+/// - `rows`: rows to pack; must lie within `pixels`
+/// - `out`: buffer the packed bytes are appended to
+/// - `bit_order`: which bit holds the leftmost pixel of each byte
+pub fn pack_rows_into_ordered(
+    pixels: &[u8],
+    width: usize,
+    rows: Range<usize>,
+    out: &mut Vec<u8>,
+    bit_order: BitOrder,
+) {
+    let bytes_per_row = width.div_ceil(8);
+    for row in rows {
+        let row_off = row * width;
+        for group in 0..bytes_per_row {
+            let mut b: u8 = 0;
+            let base = row_off + group * 8;
+            let end = usize::min(base + 8, row_off + width);
+            for (bit, px_idx) in (base..end).enumerate() {
+                let bit_val = if pixels[px_idx] == 0 { 1u8 } else { 0u8 };
+                b |= match bit_order {
+                    BitOrder::Lsb0 => bit_val << bit,
+                    BitOrder::Msb0 => bit_val << (7 - bit),
+                };
+            }
+            out.push(b);
+        }
+    }
+}
+
+/// Unpacks 1bpp rows back into a grayscale buffer, the inverse of
+/// pack_1bpp_pixels.
+///
+/// Handy for previewing or checking what will actually be printed: the
+/// result is the packed image with 0 kept black and every other value as 255.
+///
+/// - `packed`: packed rows, width.div_ceil(8) bytes each, bit 0 = leftmost pixel
+/// - `width`, `height`: image dimensions
+///
+/// Returns Vec<u8> (row-major, 0=black, 255=white), or Err if `packed` is
+/// shorter than `height` rows
+pub fn unpack_1bpp_pixels(
+    packed: &[u8],
+    width: usize,
+    height: usize,
+) -> Result<Vec<u8>, &'static str> {
+    unpack_1bpp_pixels_ordered(packed, width, height, BitOrder::Lsb0)
+}
+
+/// Unpacks 1bpp rows back into a grayscale buffer, the inverse of
+/// pack_1bpp_pixels_ordered.
+///
+/// - `packed`: packed rows, width.div_ceil(8) bytes each
+/// - `width`, `height`: image dimensions
+/// - `bit_order`: bit order the rows were packed with
+///
+/// Returns Vec<u8> (row-major, 0=black, 255=white), or Err if `packed` is
+/// shorter than `height` rows
+pub fn unpack_1bpp_pixels_ordered(
+    packed: &[u8],
+    width: usize,
+    height: usize,
+    bit_order: BitOrder,
+) -> Result<Vec<u8>, &'static str> {
+    let bytes_per_row = width.div_ceil(8);
+    if packed.len() < bytes_per_row * height {
+        return Err("not enough packed bytes");
+    }
+    let mut out = Vec::with_capacity(width * height);
+    for row in packed.chunks_exact(bytes_per_row.max(1)).take(height) {
+        for x in 0..width {
+            let bit = match bit_order {
+                BitOrder::Lsb0 => x % 8,
+                BitOrder::Msb0 => 7 - x % 8,
+            };
+            let black = row[x / 8] >> bit & 1 == 1;
+            out.push(if black { 0 } else { 255 });
+        }
+    }
+    Ok(out)
+}
+
+/// Checks that a pixel buffer is non-empty and holds width*height pixels.
+pub(crate) fn check_pixel_buffer(
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+) -> Result<(), &'static str> {
+    if width == 0 || height == 0 {
+        return Err("width/height must be > 0");
+    }
+    let required = width.checked_mul(height).ok_or("width*height overflow")?;
+    if pixels.len() < required {
+        return Err("not enough pixels");
+    }
+    Ok(())
+}
+
+/// Splits data into chunks of given size.
+///
+/// - `data`: input bytes
+/// - `chunk_size`: size of each chunk
+///
+/// Returns Vec of byte slices
+pub fn chunk_data(data: &[u8], chunk_size: usize) -> Vec<&[u8]> {
+    if chunk_size == 0 {
+        return alloc::vec![data];
+    }
+    data.chunks(chunk_size).collect()
+}
+
+/// Splits data into chunks of given size without collecting them.
+///
+/// - `data`: input bytes
+/// - `chunk_size`: size of each chunk; 0 yields the whole (non-empty) slice once
+///
+/// Returns an iterator of byte slices
+pub fn chunk_data_iter(data: &[u8], chunk_size: usize) -> impl Iterator<Item = &[u8]> {
+    let size = if chunk_size == 0 {
+        data.len().max(1)
+    } else {
+        chunk_size
+    };
+    data.chunks(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn checked_parse_accepts_a_good_crc() {
+        let packet = build_control_packet(Command::GetBattery, &[0x55]);
+        let notif = parse_notification_checked(&packet).unwrap();
+        assert_eq!(notif.command_id, 0xAB);
+        assert_eq!(notif.payload, [0x55]);
+        assert_eq!(notif.crc, Some(crc8(&[0x55])));
+    }
+
+    #[test]
+    fn checked_parse_rejects_a_bad_crc() {
+        let mut packet = build_control_packet(Command::GetBattery, &[0x55]);
+        let crc_at = packet.len() - 2;
+        packet[crc_at] ^= 0x01;
+        assert_eq!(parse_notification_checked(&packet), Err("crc mismatch"));
+        // The lenient parser still hands the bytes over
+        assert_eq!(parse_notification(&packet).unwrap().payload, [0x55]);
+    }
+
+    #[test]
+    fn checked_parse_needs_the_crc_but_not_the_ff_trailer() {
+        let packet = build_control_packet(Command::GetStatus, &[1, 2, 3]);
+        let without_crc = &packet[..packet.len() - 2];
+        assert_eq!(parse_notification_checked(without_crc), Err("crc mismatch"));
+        let mut bad_trailer = packet.clone();
+        *bad_trailer.last_mut().unwrap() = 0x00;
+        assert!(parse_notification_checked(&bad_trailer).is_ok());
+        assert_eq!(
+            parse_control_packet(&bad_trailer),
+            Err("bad trailer, expected 0xFF")
+        );
+    }
+
+    #[test]
+    fn checked_parse_rejects_short_buffers() {
+        let packet = build_control_packet(Command::GetStatus, &[0; 13]);
+        assert_eq!(
+            parse_notification_checked(&packet[..6]),
+            Err("packet too short")
+        );
+        assert_eq!(
+            parse_notification_checked(&packet[..12]),
+            Err("not enough bytes for claimed payload length")
+        );
+        assert_eq!(parse_notification_checked(&[]), Err("packet too short"));
+        let mut bad_preamble = vec![0x21, 0x22];
+        bad_preamble.extend_from_slice(&packet[2..]);
+        assert_eq!(
+            parse_notification_checked(&bad_preamble),
+            Err("bad preamble")
+        );
+    }
+
+    #[test]
+    fn bit_order_places_the_leftmost_pixel() {
+        // Black at columns 0, 6 and 7
+        let row = [0, 255, 255, 255, 255, 255, 0, 0];
+        assert_eq!(
+            pack_1bpp_pixels_ordered(&row, 8, 1, BitOrder::Lsb0).unwrap(),
+            [0b1100_0001]
+        );
+        assert_eq!(
+            pack_1bpp_pixels_ordered(&row, 8, 1, BitOrder::Msb0).unwrap(),
+            [0b1000_0011]
+        );
+        assert_eq!(pack_1bpp_pixels(&row, 8, 1).unwrap(), [0b1100_0001]);
+        // A partial last group starts at the same end as a full one
+        let short = [0, 255, 0];
+        assert_eq!(
+            pack_1bpp_pixels_ordered(&short, 3, 1, BitOrder::Lsb0).unwrap(),
+            [0b0000_0101]
+        );
+        assert_eq!(
+            pack_1bpp_pixels_ordered(&short, 3, 1, BitOrder::Msb0).unwrap(),
+            [0b1010_0000]
+        );
+    }
+
+    #[test]
+    fn control_packets_round_trip() {
+        let payloads: [&[u8]; 4] = [&[], &[0x00], &[0x7F, 0x80, 0xFF], &[0xAB; 300]];
+        for command in [
+            Command::GetStatus,
+            Command::PrintStart,
+            Command::GetDeviceInfo,
+        ] {
+            for payload in payloads {
+                let packet = build_control_packet(command, payload);
+                assert_eq!(
+                    parse_control_packet(&packet),
+                    Ok((u8::from(command), payload.to_vec()))
+                );
+            }
+        }
+        let packet = build_control_packet(Command::SetDensity, &[0x60]);
+        let mut extra = packet.clone();
+        extra.push(0x00);
+        assert!(parse_control_packet(&extra).is_err());
+        let mut bad_trailer = packet.clone();
+        *bad_trailer.last_mut().unwrap() = 0x00;
+        assert!(parse_control_packet(&bad_trailer).is_err());
+        assert!(parse_control_packet(&packet[..packet.len() - 1]).is_err());
+        let mut bad_crc = packet.clone();
+        bad_crc[7] ^= 1;
+        assert!(parse_control_packet(&bad_crc).is_err());
+    }
+
+    fn drain(assembler: &mut PacketAssembler) -> Vec<Vec<u8>> {
+        core::iter::from_fn(|| assembler.next_packet()).collect()
+    }
+
+    #[test]
+    fn assembler_joins_a_packet_split_over_two_events() {
+        let packet = build_control_packet(0xA1, &[0x11; 20]);
+        // Nothing comes out until the crc is in, including a split right before it
+        for split in 1..packet.len() - 1 {
+            let mut assembler = PacketAssembler::new();
+            assembler.push(&packet[..split]);
+            assert_eq!(assembler.next_packet(), None, "split at {split}");
+            assembler.push(&packet[split..]);
+            let joined = drain(&mut assembler);
+            assert_eq!(joined, core::slice::from_ref(&packet), "split at {split}");
+            assert!(parse_notification_checked(&joined[0]).is_ok());
+            assert_eq!(assembler.pending(), 0);
+        }
+
+        // Only the trailer lagging: the packet is checkable without it and
+        // the late 0xFF is dropped
+        let mut assembler = PacketAssembler::new();
+        assembler.push(&packet[..packet.len() - 1]);
+        let early = assembler.next_packet().unwrap();
+        assert_eq!(early, packet[..packet.len() - 1]);
+        assert!(parse_notification_checked(&early).is_ok());
+        assembler.push(&[0xFF]);
+        assert_eq!(assembler.next_packet(), None);
+        assert_eq!(assembler.pending(), 0);
+    }
+
+    #[test]
+    fn assembler_joins_a_packet_split_over_three_events() {
+        let packet = build_control_packet(0xA1, &[0x5A; 30]);
+        let mut assembler = PacketAssembler::new();
+        assembler.push(&packet[..3]);
+        assert_eq!(assembler.next_packet(), None);
+        assembler.push(&packet[3..20]);
+        assert_eq!(assembler.next_packet(), None);
+        assembler.push(&packet[20..]);
+        let joined = drain(&mut assembler);
+        assert_eq!(joined, [packet]);
+        assert_eq!(
+            parse_notification_checked(&joined[0]).unwrap().payload,
+            [0x5A; 30]
+        );
+        assert_eq!(assembler.pending(), 0);
+    }
+
+    #[test]
+    fn assembler_separates_two_packets_in_one_event() {
+        let first = build_control_packet(0xA1, &[1, 2, 3]);
+        let second = build_control_packet(0xB1, &[]);
+        let mut assembler = PacketAssembler::new();
+        assembler.push(&[first.clone(), second.clone()].concat());
+        assert_eq!(drain(&mut assembler), [first, second]);
+        assert_eq!(assembler.pending(), 0);
+    }
+
+    #[test]
+    fn assembler_skips_leading_garbage() {
+        let packet = build_control_packet(0xAB, &[0x00]);
+        let mut assembler = PacketAssembler::new();
+        // A late crc and trailer, then a lone 0x22 that isn't a preamble
+        assembler.push(&[0x3C, 0xFF, 0x22, 0x00]);
+        assert_eq!(assembler.next_packet(), None);
+        assert_eq!(assembler.pending(), 0);
+
+        // A trailing 0x22 is kept in case its 0x21 arrives next
+        assembler.push(&[0x10, 0x22]);
+        assert_eq!(assembler.next_packet(), None);
+        assert_eq!(assembler.pending(), 1);
+        assembler.push(&packet[1..]);
+        assert_eq!(drain(&mut assembler), [packet]);
+    }
+
+    #[test]
+    fn unpack_inverts_pack_for_random_buffers() {
+        // Small xorshift so the cases are random-looking but reproducible
+        let mut state = 0x2545_F491_u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+        for (width, height) in [(1, 1), (7, 3), (8, 2), (13, 5), (384, 4), (385, 2)] {
+            for _ in 0..8 {
+                // Biased towards 0 so both colors show up often
+                let pixels: Vec<u8> = (0..width * height)
+                    .map(|_| match next() % 4 {
+                        0 | 1 => 0,
+                        _ => next() as u8,
+                    })
+                    .collect();
+                let thresholded: Vec<u8> = pixels
+                    .iter()
+                    .map(|&p| if p == 0 { 0 } else { 255 })
+                    .collect();
+                for bit_order in [BitOrder::Lsb0, BitOrder::Msb0] {
+                    let packed =
+                        pack_1bpp_pixels_ordered(&pixels, width, height, bit_order).unwrap();
+                    assert_eq!(packed.len(), width.div_ceil(8) * height);
+                    assert_eq!(
+                        unpack_1bpp_pixels_ordered(&packed, width, height, bit_order).unwrap(),
+                        thresholded,
+                        "{width}x{height} {bit_order:?}"
+                    );
+                }
+                let packed = pack_1bpp_pixels(&pixels, width, height).unwrap();
+                assert_eq!(
+                    unpack_1bpp_pixels(&packed, width, height).unwrap(),
+                    thresholded
+                );
+            }
+        }
+        assert!(unpack_1bpp_pixels(&[0; 3], 13, 2).is_err());
+    }
+}